@@ -1,23 +1,82 @@
 use std::sync::Arc;
+use std::time::Duration;
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 
+use crate::auth;
+use crate::backup;
+use crate::browser;
 use crate::config;
-use crate::data::{self, CommentService, FeedService, InteractionService, SubredditService};
+use crate::data;
 use crate::hackernews;
+use crate::instance;
+use crate::lemmy;
+use crate::logging;
 use crate::media;
+use crate::net;
+use crate::plugins;
+use crate::profile;
 use crate::reddit;
+use crate::scripting;
+use crate::secrets;
 use crate::session;
 use crate::storage;
+use crate::theme;
 use crate::ui;
+use crate::update;
+
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    pub screen_reader: bool,
+    pub verbose: bool,
+    /// Feed or link to open on launch, or to hand to the running instance.
+    pub open_target: Option<String>,
+}
 
 pub fn run() -> Result<()> {
+    run_with(RunOptions::default())
+}
+
+pub fn run_with(run_opts: RunOptions) -> Result<()> {
+    // Logging is best effort: a read-only data dir shouldn't stop the app.
+    let _log_guard = logging::init(run_opts.verbose).ok();
     let cfg = config::load(config::LoadOptions::default()).context("load config")?;
+    net::configure(&cfg.network).context("configure network")?;
+    browser::configure(&cfg.ui);
+    plugins::configure(&cfg.plugins);
+
+    let (handoff_tx, handoff_rx) = crossbeam_channel::unbounded();
+    let _instance_guard = if cfg.ui.single_instance {
+        match instance::acquire(handoff_tx).context("acquire single-instance lock")? {
+            instance::Acquired::Primary(guard) => Some(guard),
+            instance::Acquired::Secondary => {
+                let Some(target) = run_opts.open_target.as_deref() else {
+                    bail!("HN-TUI is already running (ui.single_instance is on)");
+                };
+                instance::hand_off(target).context("hand off to running instance")?;
+                println!("Opened {target} in the running HN-TUI.");
+                return Ok(());
+            }
+        }
+    } else {
+        None
+    };
     let config_path = config::default_path();
     let display_path = friendly_path(config_path.as_ref());
 
-    let store =
-        Arc::new(storage::Store::open(storage::Options::default()).context("open storage")?);
+    let mut restore_note = None;
+    if let Some(db) = storage::default_path() {
+        match backup::apply_staged(&db) {
+            Ok(true) => restore_note = Some("Backup restored. "),
+            Ok(false) => {}
+            Err(err) => tracing::warn!("failed to apply staged restore: {err:#}"),
+        }
+    }
+    let store = storage::Store::open(storage::Options::default())
+        .context("open storage")?
+        .with_token_storage(secrets::Mode::from_config(&cfg.reddit.token_storage));
+    let store = Arc::new(store);
+    store.set_private(cfg.ui.privacy_mode);
 
     let media_cfg = media::Config {
         cache_dir: cfg.media.cache_dir.clone(),
@@ -30,18 +89,9 @@ pub fn run() -> Result<()> {
     let media_manager = media::Manager::new(store.clone(), media_cfg).ok();
     let media_handle = media_manager.as_ref().map(|manager| manager.handle());
 
-    let _theme = &cfg.ui.theme;
     let status: String;
     let content: String;
-    
-    let subreddits = vec![
-        "Top".to_string(),
-        "New".to_string(),
-        "Best".to_string(),
-        "Ask HN".to_string(),
-        "Show HN".to_string(),
-        "Jobs".to_string(),
-    ];
+    let mut subreddits: Vec<String> = Vec::new();
     let mut posts: Vec<ui::PostPreview> = vec![
         placeholder_post(
             "welcome",
@@ -59,52 +109,97 @@ pub fn run() -> Result<()> {
     let mut subreddit_service: Option<Arc<dyn data::SubredditService + Send + Sync>> = None;
     let mut comment_service: Option<Arc<dyn data::CommentService + Send + Sync>> = None;
     let mut interaction_service: Option<Arc<dyn data::InteractionService + Send + Sync>> = None;
+    let mut request_log: Option<reddit::RequestLog> = None;
+    let mut backend_kind = data::BackendKind::HackerNews;
 
-    let session_manager: Option<Arc<session::Manager>> = None;
+    let mut session_manager: Option<Arc<session::Manager>> = None;
     let fetch_subreddits_on_start = true;
 
-    // Initialize HackerNews client (no authentication needed)
     let user_agent = if !cfg.reddit.user_agent.trim().is_empty() {
         cfg.reddit.user_agent.clone()
     } else {
         format!("hn-tui/{}", crate::VERSION)
     };
 
-    if let Ok(client) = hackernews::Client::new(hackernews::ClientConfig {
-        user_agent: user_agent.clone(),
-        http_client: None,
-    }) {
-        let client = Arc::new(client);
-        
-        // Create HackerNews service implementations
-        let subreddit_api: Arc<dyn SubredditService + Send + Sync> =
-            Arc::new(data::HackerNewsCategoryService::new(client.clone()));
-        let feed_api: Arc<dyn FeedService + Send + Sync> =
-            Arc::new(data::HackerNewsFeedService::new(client.clone()));
-        let comment_api: Arc<dyn CommentService + Send + Sync> =
-            Arc::new(data::HackerNewsCommentService::new(client.clone()));
-        let interaction_api: Arc<dyn InteractionService + Send + Sync> =
-            Arc::new(data::HackerNewsInteractionService::new());
-
-        feed_service = Some(feed_api);
-        subreddit_service = Some(subreddit_api);
-        comment_service = Some(comment_api);
-        interaction_service = Some(interaction_api);
-        
-        status = "Browsing Hacker News. Press j/k to navigate, Enter to view comments, q to quit.".to_string();
-        content = "HN-TUI is ready! Select a category on the left and browse stories.\n\nNo authentication required - all HN content is public.".to_string();
+    let mut fallback_note = None;
+    let mut backend = match data::BackendKind::from_config(&cfg.backend) {
+        data::BackendKind::Reddit => match reddit_backend(&cfg, store.clone(), &user_agent) {
+            Ok((backend, manager)) => {
+                session_manager = Some(manager);
+                Some(backend)
+            }
+            Err(err) => {
+                fallback_note = Some(format!("Reddit backend unavailable ({err:#}); "));
+                None
+            }
+        },
+        data::BackendKind::Lemmy => match lemmy_backend(&cfg, &user_agent) {
+            Ok(backend) => Some(backend),
+            Err(err) => {
+                fallback_note = Some(format!("Lemmy backend unavailable ({err:#}); "));
+                None
+            }
+        },
+        data::BackendKind::HackerNews => None,
+    };
+    if backend.is_none() {
+        backend = hackernews::Client::new(hackernews::ClientConfig {
+            user_agent: user_agent.clone(),
+            http_client: None,
+        })
+        .ok()
+        .map(|client| data::Backend::hacker_news(Arc::new(client)));
+    }
+
+    if let Some(backend) = backend {
+        subreddits = backend.default_categories();
+        feed_service = Some(backend.feed.clone());
+        subreddit_service = Some(backend.subreddits.clone());
+        comment_service = Some(backend.comments.clone());
+        interaction_service = Some(backend.interaction.clone());
+        request_log = backend.requests.clone();
+        backend_kind = backend.kind;
+
+        status = match backend.kind {
+            data::BackendKind::HackerNews => "Browsing Hacker News. Press j/k to navigate, Enter to view comments, q to quit.".to_string(),
+            data::BackendKind::Reddit => "Browsing Reddit. Press j/k to navigate, Enter to view comments, q to quit.".to_string(),
+            data::BackendKind::Lemmy => "Browsing Lemmy. Press j/k to navigate, Enter to view comments, q to quit.".to_string(),
+        };
+        content = match (backend.kind, fallback_note) {
+            (_, Some(note)) => format!("{note}showing Hacker News instead."),
+            (data::BackendKind::HackerNews, None) => "HN-TUI is ready! Select a category on the left and browse stories.\n\nNo authentication required - all HN content is public.".to_string(),
+            (data::BackendKind::Reddit, None) => "HN-TUI is ready! Pick a subreddit on the left and browse posts.".to_string(),
+            (data::BackendKind::Lemmy, None) => format!(
+                "HN-TUI is ready! Browsing {}; pick a community on the left or open any name@instance.",
+                cfg.lemmy.instance_url
+            ),
+        };
         posts.clear();
     } else {
         status = "Failed to initialize HackerNews client.".to_string();
         content = "Could not connect to Hacker News. Please check your internet connection.".to_string();
     }
 
+    // A broken script shouldn't keep the app from starting; run without scripts.
+    let (scripts, status) = match scripting::Scripts::load(&cfg.scripts) {
+        Ok(scripts) => (scripts, status),
+        Err(err) => {
+            tracing::warn!("{err:#}");
+            (
+                scripting::Scripts::default(),
+                format!("Scripts disabled: {err:#}"),
+            )
+        }
+    };
+    let status = format!("{}{status}", restore_note.unwrap_or_default());
+
     let options = ui::Options {
         status_message: status,
         subreddits,
         posts,
         content,
         feed_service,
+        backend: backend_kind,
         subreddit_service,
         default_sort: reddit::SortOption::Hot,
         default_comment_sort: reddit::CommentSortOption::Confidence,
@@ -115,19 +210,150 @@ pub fn run() -> Result<()> {
         store: store.clone(),
         session_manager: session_manager.clone(),
         fetch_subreddits_on_start,
+        pipe_command: cfg.ui.pipe_command.clone(),
+        translate_command: cfg.ui.translate_command.clone(),
+        osc52_clipboard: cfg.ui.clipboard.trim().eq_ignore_ascii_case("osc52"),
+        post_row_fields: cfg.ui.post_row_fields.clone(),
+        ignore_suggested_sort: cfg.ui.ignore_suggested_sort,
+        recent_subreddits: cfg.ui.recent_subreddits,
+        auto_collapse: ui::AutoCollapseRules {
+            below_score: cfg.ui.collapse_below_score,
+            automoderator: cfg.ui.collapse_automoderator,
+            max_depth: cfg.ui.collapse_depth,
+        },
+        auto_refresh: ui::AutoRefresh::new(cfg.ui.auto_refresh, &cfg.ui.auto_refresh_feeds),
+        desktop_notifications: cfg.ui.desktop_notifications,
+        inbox_all_accounts: cfg.ui.inbox_all_accounts,
+        reddit_user_agent: user_agent.clone(),
+        restore_session: cfg.ui.restore_session,
+        update_channel: update::Channel::from_config(&cfg.update.channel),
+        update_snooze_days: cfg.update.snooze_days,
+        screen_reader: run_opts.screen_reader || cfg.ui.screen_reader,
+        reduced_motion: cfg.ui.reduced_motion,
+        palette: theme::Palette::from_config(&cfg.ui),
+        request_log,
+        cache: cfg.cache.clone(),
+        video_cache_ttl: cfg.media.video_ttl,
+        download_dir: cfg.media.download_dir.clone(),
+        filename_template: cfg.media.filename_template.clone(),
+        remote_session: cfg.media.remote_session.clone(),
+        launch_target: run_opts.open_target.clone(),
+        handoff: cfg.ui.single_instance.then_some(handoff_rx),
+        scripts: Arc::new(scripts),
+        usage_stats: cfg.ui.usage_stats,
+        sfw_only: cfg.ui.sfw_only,
+        remember_comment_sorts: cfg.ui.remember_comment_sorts,
+        hide_stickied: cfg.ui.hide_stickied,
     };
 
     let mut model = ui::Model::new(options);
     model.run()?;
+    let profile_switch = model.take_profile_switch();
 
     if let Some(manager) = session_manager {
         manager.close();
     }
     drop(media_manager);
 
+    if let Some(name) = profile_switch {
+        drop(_instance_guard);
+        profile::relaunch(&name)?;
+    }
+
     Ok(())
 }
 
+fn reddit_backend(
+    cfg: &config::Config,
+    store: Arc<storage::Store>,
+    user_agent: &str,
+) -> Result<(data::Backend, Arc<session::Manager>)> {
+    let defaults = config::RedditConfig::default();
+    if cfg.reddit.client_id.trim().is_empty() {
+        anyhow::bail!("reddit.client_id is not configured");
+    }
+    let scopes = if cfg.reddit.scopes.is_empty() {
+        defaults.scopes
+    } else {
+        cfg.reddit.scopes.clone()
+    };
+    let flow_cfg = auth::Config {
+        client_id: cfg.reddit.client_id.clone(),
+        client_secret: cfg.reddit.client_secret.clone(),
+        scope: scopes,
+        user_agent: user_agent.to_string(),
+        auth_url: "https://www.reddit.com/api/v1/authorize".into(),
+        token_url: "https://www.reddit.com/api/v1/access_token".into(),
+        identity_url: "https://oauth.reddit.com/api/v1/me".into(),
+        redirect_uri: cfg.reddit.redirect_uri.clone(),
+        refresh_skew: Duration::from_secs(30),
+    };
+    let flow = Arc::new(auth::Flow::new(store.clone(), flow_cfg).context("create auth flow")?);
+    let manager =
+        Arc::new(session::Manager::new(store, flow).context("create session manager")?);
+    manager.load_existing().context("load reddit sessions")?;
+    let token_provider = manager
+        .active_token_provider()
+        .context("sign in to a Reddit account from the menu first")?;
+    let client = reddit::Client::new(
+        token_provider,
+        reddit::ClientConfig {
+            user_agent: user_agent.to_string(),
+            base_url: None,
+            http_client: None,
+        },
+    )
+    .context("create reddit client")?;
+    Ok((data::Backend::reddit(Arc::new(client)), manager))
+}
+
+fn lemmy_backend(cfg: &config::Config, user_agent: &str) -> Result<data::Backend> {
+    let client = lemmy::Client::new(lemmy::ClientConfig {
+        instance_url: cfg.lemmy.instance_url.clone(),
+        user_agent: user_agent.to_string(),
+        username: cfg.lemmy.username.clone(),
+        password: lemmy_password(cfg),
+        http_client: None,
+    })
+    .context("create lemmy client")?;
+    Ok(data::Backend::lemmy(Arc::new(client)))
+}
+
+/// The Lemmy password from the keychain. A password still in the config file
+/// is moved there first and blanked in the file.
+fn lemmy_password(cfg: &config::Config) -> String {
+    let username = cfg.lemmy.username.trim();
+    if username.is_empty() {
+        return String::new();
+    }
+    let vault = secrets::Vault::new(secrets::Mode::from_config(&cfg.reddit.token_storage));
+    let instance = lemmy::normalize_instance_url(&cfg.lemmy.instance_url)
+        .unwrap_or_else(|_| cfg.lemmy.instance_url.clone());
+    let host = instance
+        .trim_start_matches("https://")
+        .trim_start_matches("http://");
+    let account = format!("lemmy:{username}@{host}");
+    if !cfg.lemmy.password.is_empty() {
+        match vault.store_password(&account, &cfg.lemmy.password) {
+            Ok(true) => {
+                if let Err(err) = config::clear_lemmy_password(None) {
+                    tracing::warn!("failed to remove lemmy.password from config: {err:#}");
+                }
+            }
+            Ok(false) => {}
+            Err(err) => tracing::warn!("failed to save Lemmy password: {err:#}"),
+        }
+        return cfg.lemmy.password.clone();
+    }
+    match vault.load_password(&account) {
+        Ok(password) => password.unwrap_or_default(),
+        Err(err) => {
+            tracing::warn!("failed to read Lemmy password: {err:#}");
+            String::new()
+        }
+    }
+}
+
 fn friendly_path(path: Option<&std::path::PathBuf>) -> String {
     if let Some(path) = path {
         if let Some(home) = dirs::home_dir() {
@@ -166,6 +392,8 @@ fn placeholder_post(id: &str, title: &str, description: &str) -> ui::PostPreview
             created_utc: 0.0,
             thumbnail: String::new(),
             stickied: false,
+            locked: false,
+            archived: false,
             over_18: false,
             spoiler: false,
             post_hint: String::new(),
@@ -176,6 +404,11 @@ fn placeholder_post(id: &str, title: &str, description: &str) -> ui::PostPreview
             preview: reddit::Preview::default(),
             gallery_data: None,
             media_metadata: None,
+            suggested_sort: None,
+            domain: String::new(),
+            link_flair_text: None,
+            total_awards_received: 0,
+            upvote_ratio: None,
         },
         links,
     }
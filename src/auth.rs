@@ -19,6 +19,7 @@ use sha2::{Digest, Sha256};
 use tiny_http::{Header, Method, Response, Server};
 use url::Url;
 
+use crate::net;
 use crate::reddit::{OAuthToken, TokenProvider};
 use crate::storage::{self, Account, Token};
 
@@ -182,8 +183,7 @@ impl Flow {
         if cfg.user_agent.trim().is_empty() {
             bail!("auth: user agent is required");
         }
-        let client = Client::builder()
-            .timeout(Duration::from_secs(20))
+        let client = net::client_builder()
             .build()
             .context("auth: build http client")?;
 
@@ -493,7 +493,7 @@ impl Flow {
                         current = new_token;
                     }
                     Err(err) => {
-                        eprintln!("token refresh failed: {err:?}");
+                        tracing::warn!(account_id, "token refresh failed: {err:#}");
                         if stop_rx.recv_timeout(Duration::from_secs(5)).is_ok() {
                             break;
                         }
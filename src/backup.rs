@@ -0,0 +1,255 @@
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use rand::RngCore;
+use rusqlite::Connection;
+
+use crate::storage;
+
+/// Environment variable read instead of prompting for a passphrase.
+pub const PASSPHRASE_ENV: &str = "HN_TUI_BACKUP_PASSPHRASE";
+
+/// File extension used for new backups.
+pub const EXTENSION: &str = "hnbak";
+
+/// Archive layout: magic, Argon2 salt, XChaCha20 nonce, then the sealed
+/// SQLite snapshot.
+const MAGIC: &[u8; 8] = b"HNTUIBK1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+/// Writes an encrypted copy of `store` (accounts, bookmarks, history,
+/// drafts, and settings) to `path`.
+pub fn create(store: &storage::Store, path: &Path, passphrase: &str) -> Result<()> {
+    check_passphrase(passphrase)?;
+    let dir = tempfile::tempdir().context("backup: create temporary directory")?;
+    let snapshot = dir.path().join("state.db");
+    store.snapshot(&snapshot)?;
+    let plain = fs::read(&snapshot)
+        .with_context(|| format!("backup: read snapshot {}", snapshot.display()))?;
+    let sealed = seal(&plain, passphrase)?;
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("backup: create directory {}", parent.display()))?;
+    }
+    fs::write(path, sealed).with_context(|| format!("backup: write {}", path.display()))
+}
+
+/// Decrypts the backup at `archive` and places it next to `db`, to replace
+/// it on the next [`apply_staged`]. The open database is left untouched.
+pub fn stage_restore(archive: &Path, passphrase: &str, db: &Path) -> Result<()> {
+    let sealed =
+        fs::read(archive).with_context(|| format!("backup: read {}", archive.display()))?;
+    let plain = open(&sealed, passphrase)?;
+    let dir = db
+        .parent()
+        .ok_or_else(|| anyhow!("backup: {} has no parent directory", db.display()))?;
+    fs::create_dir_all(dir)
+        .with_context(|| format!("backup: create directory {}", dir.display()))?;
+    let mut file = tempfile::NamedTempFile::new_in(dir).context("backup: create staging file")?;
+    file.write_all(&plain)
+        .context("backup: write staging file")?;
+    check_database(file.path())?;
+    file.persist(staged_path(db))
+        .map_err(|err| err.error)
+        .context("backup: stage restore")?;
+    Ok(())
+}
+
+/// Swaps a staged restore into place. Call before the database is opened;
+/// returns whether a restore was applied.
+pub fn apply_staged(db: &Path) -> Result<bool> {
+    let staged = staged_path(db);
+    if !staged.exists() {
+        return Ok(false);
+    }
+    for suffix in ["-wal", "-shm"] {
+        let sidecar = sibling(db, suffix);
+        if sidecar.exists() {
+            fs::remove_file(&sidecar)
+                .with_context(|| format!("backup: remove {}", sidecar.display()))?;
+        }
+    }
+    fs::rename(&staged, db).with_context(|| format!("backup: replace {}", db.display()))?;
+    Ok(true)
+}
+
+fn staged_path(db: &Path) -> PathBuf {
+    sibling(db, ".restore")
+}
+
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn check_database(path: &Path) -> Result<()> {
+    let conn = Connection::open(path).context("backup: open restored database")?;
+    let status: String = conn
+        .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+        .context("backup: check restored database")?;
+    if status != "ok" {
+        bail!("backup: restored database is damaged ({status})");
+    }
+    Ok(())
+}
+
+fn check_passphrase(passphrase: &str) -> Result<()> {
+    if passphrase.is_empty() {
+        bail!("backup: passphrase is empty");
+    }
+    Ok(())
+}
+
+fn seal(plain: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let cipher = cipher(passphrase, &salt)?;
+    let sealed = cipher
+        .encrypt(&XNonce::from(nonce), plain)
+        .map_err(|_| anyhow!("backup: encrypt"))?;
+    let mut out = Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN + sealed.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&sealed);
+    Ok(out)
+}
+
+fn open(sealed: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let rest = sealed
+        .strip_prefix(MAGIC.as_slice())
+        .ok_or_else(|| anyhow!("backup: not an HN-TUI backup"))?;
+    if rest.len() < SALT_LEN + NONCE_LEN {
+        bail!("backup: file is truncated");
+    }
+    let (salt, rest) = rest.split_at(SALT_LEN);
+    let (nonce, body) = rest.split_at(NONCE_LEN);
+    cipher(passphrase, salt)?
+        .decrypt(&XNonce::from(<[u8; NONCE_LEN]>::try_from(nonce)?), body)
+        .map_err(|_| anyhow!("backup: wrong passphrase or damaged file"))
+}
+
+fn cipher(passphrase: &str, salt: &[u8]) -> Result<XChaCha20Poly1305> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|err| anyhow!("backup: derive key: {err}"))?;
+    Ok(XChaCha20Poly1305::new(&key.into()))
+}
+
+/// The passphrase from [`PASSPHRASE_ENV`], or typed at the terminal without
+/// echo. `confirm` asks twice, for new backups.
+pub fn read_passphrase(confirm: bool) -> Result<String> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        check_passphrase(&passphrase)?;
+        return Ok(passphrase);
+    }
+    let passphrase = prompt("Backup passphrase: ")?;
+    check_passphrase(&passphrase)?;
+    if confirm && prompt("Repeat passphrase: ")? != passphrase {
+        bail!("backup: passphrases do not match");
+    }
+    Ok(passphrase)
+}
+
+fn prompt(label: &str) -> Result<String> {
+    eprint!("{label}");
+    io::stderr().flush().ok();
+    let _echo = EchoOff::new();
+    let mut line = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .context("backup: read passphrase")?;
+    eprintln!();
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Turns terminal echo off on stdin until dropped.
+#[cfg(unix)]
+struct EchoOff(Option<libc::termios>);
+
+#[cfg(unix)]
+impl EchoOff {
+    fn new() -> Self {
+        unsafe {
+            let mut term: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut term) != 0 {
+                return Self(None);
+            }
+            let original = term;
+            term.c_lflag &= !libc::ECHO;
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &term);
+            Self(Some(original))
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for EchoOff {
+    fn drop(&mut self) {
+        if let Some(original) = &self.0 {
+            unsafe {
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, original);
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+struct EchoOff;
+
+#[cfg(not(unix))]
+impl EchoOff {
+    fn new() -> Self {
+        Self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn sealed_data_needs_the_passphrase() {
+        let sealed = seal(b"state", "correct horse").unwrap();
+        assert!(sealed.starts_with(MAGIC));
+        assert_eq!(open(&sealed, "correct horse").unwrap(), b"state");
+        assert!(open(&sealed, "wrong").is_err());
+        assert!(open(b"plain sqlite", "correct horse").is_err());
+    }
+
+    #[test]
+    fn backups_restore_into_another_database() {
+        let dir = tempdir().unwrap();
+        let source = storage::Store::open(storage::Options {
+            path: Some(dir.path().join("source.db")),
+        })
+        .unwrap();
+        source.record_search("ratatui").unwrap();
+        let archive = dir.path().join("state.hnbak");
+        create(&source, &archive, "secret").unwrap();
+
+        let target = dir.path().join("target.db");
+        assert!(stage_restore(&archive, "nope", &target).is_err());
+        stage_restore(&archive, "secret", &target).unwrap();
+        assert!(apply_staged(&target).unwrap());
+        assert!(!apply_staged(&target).unwrap());
+
+        let restored = storage::Store::open(storage::Options { path: Some(target) }).unwrap();
+        assert_eq!(restored.recent_searches(5).unwrap(), vec!["ratatui"]);
+    }
+}
@@ -0,0 +1,149 @@
+use std::io;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use std::thread;
+
+use url::Url;
+
+use crate::config::UIConfig;
+
+const URL_PLACEHOLDER: &str = "{url}";
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+#[derive(Default)]
+struct Settings {
+    command: Vec<String>,
+    /// `(domain, command)` pairs, longest domain first so the most specific
+    /// override wins.
+    overrides: Vec<(String, Vec<String>)>,
+}
+
+impl Settings {
+    fn from_config(cfg: &UIConfig) -> Self {
+        let mut overrides: Vec<(String, Vec<String>)> = cfg
+            .browser_overrides
+            .iter()
+            .map(|(domain, command)| (normalize_host(domain), command.clone()))
+            .filter(|(domain, command)| !domain.is_empty() && !command.is_empty())
+            .collect();
+        overrides.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then_with(|| a.0.cmp(&b.0)));
+        Self {
+            command: cfg.browser_command.clone(),
+            overrides,
+        }
+    }
+
+    /// The configured command for `url`, or `None` for the system browser.
+    fn command_for(&self, url: &str) -> Option<&[String]> {
+        let host = Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(normalize_host));
+        let matched = host.and_then(|host| {
+            self.overrides.iter().find(|(domain, _)| {
+                host == *domain
+                    || host
+                        .strip_suffix(domain.as_str())
+                        .is_some_and(|rest| rest.ends_with('.'))
+            })
+        });
+        match matched {
+            Some((_, command)) => Some(command),
+            None if !self.command.is_empty() => Some(&self.command),
+            None => None,
+        }
+    }
+}
+
+/// Applies `ui.browser_command` and `ui.browser_overrides` to every later
+/// [`open`]. Call once at startup; later calls are ignored.
+pub fn configure(cfg: &UIConfig) {
+    let _ = SETTINGS.set(Settings::from_config(cfg));
+}
+
+/// Opens `url` with the command configured for its domain, else
+/// `ui.browser_command`, else the system browser. Configured commands are
+/// started in the background and not waited on.
+pub fn open(url: &str) -> io::Result<()> {
+    let settings = SETTINGS.get_or_init(Settings::default);
+    let Some(command) = settings.command_for(url) else {
+        return webbrowser::open(url);
+    };
+    let args = command_args(command, url);
+    let mut child = Command::new(&args[0])
+        .args(&args[1..])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| io::Error::new(err.kind(), format!("{}: {err}", args[0])))?;
+    thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+/// `command` with `{url}` replaced by `url`, or `url` appended when no
+/// argument mentions it.
+fn command_args(command: &[String], url: &str) -> Vec<String> {
+    let mut args: Vec<String> = command
+        .iter()
+        .map(|arg| arg.replace(URL_PLACEHOLDER, url))
+        .collect();
+    if !command.iter().any(|arg| arg.contains(URL_PLACEHOLDER)) {
+        args.push(url.to_string());
+    }
+    args
+}
+
+fn normalize_host(host: &str) -> String {
+    let host = host.trim().trim_end_matches('.').to_ascii_lowercase();
+    host.strip_prefix("www.")
+        .map(str::to_string)
+        .unwrap_or(host)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn url_replaces_the_placeholder_or_is_appended() {
+        let url = "https://example.com/a?b=c";
+        assert_eq!(
+            command_args(&args(&["firefox", "--private-window", "{url}"]), url),
+            args(&["firefox", "--private-window", url])
+        );
+        assert_eq!(
+            command_args(&args(&["xdg-open"]), url),
+            args(&["xdg-open", url])
+        );
+    }
+
+    #[test]
+    fn most_specific_domain_override_wins() {
+        let cfg = UIConfig {
+            browser_command: args(&["firefox"]),
+            browser_overrides: HashMap::from([
+                ("youtube.com".to_string(), args(&["mpv"])),
+                ("music.youtube.com".to_string(), args(&["chromium"])),
+            ]),
+            ..UIConfig::default()
+        };
+        let settings = Settings::from_config(&cfg);
+        let command = |url| settings.command_for(url).map(|command| command[0].as_str());
+        assert_eq!(command("https://www.youtube.com/watch?v=1"), Some("mpv"));
+        assert_eq!(command("https://music.youtube.com/x"), Some("chromium"));
+        assert_eq!(command("https://notyoutube.com/"), Some("firefox"));
+        assert_eq!(command("https://example.com/"), Some("firefox"));
+
+        let empty = Settings::from_config(&UIConfig::default());
+        assert!(empty.command_for("https://example.com/").is_none());
+    }
+}
@@ -7,18 +7,44 @@ use std::time::Duration;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
+use crate::profile;
+
 const DEFAULT_ENV_PREFIX: &str = "REDDIX";
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 pub struct Config {
+    #[serde(default = "default_backend")]
+    pub backend: String,
     #[serde(default)]
     pub reddit: RedditConfig,
     #[serde(default)]
+    pub lemmy: LemmyConfig,
+    #[serde(default)]
     pub ui: UIConfig,
     #[serde(default)]
     pub media: MediaConfig,
     #[serde(default)]
+    pub cache: CacheConfig,
+    #[serde(default)]
     pub player: PlayerConfig,
+    #[serde(default)]
+    pub update: UpdateConfig,
+    #[serde(default)]
+    pub network: NetworkConfig,
+    /// External JSON-over-stdio plugins, see [`crate::plugins`].
+    #[serde(default)]
+    pub plugins: Vec<PluginConfig>,
+    /// Rhai scripts run on UI events, see [`crate::scripting`].
+    #[serde(default)]
+    pub scripts: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct PluginConfig {
+    /// Shown in menus and used in plugin feed targets (`plugin:<name>/<feed>`).
+    pub name: String,
+    /// Program and arguments; started once per request.
+    pub command: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -33,6 +59,10 @@ pub struct RedditConfig {
     pub scopes: Vec<String>,
     #[serde(default = "default_redirect_uri")]
     pub redirect_uri: String,
+    /// Where OAuth tokens are kept: `auto` (OS keychain when available),
+    /// `keyring`, or `database` for headless systems.
+    #[serde(default = "default_token_storage")]
+    pub token_storage: String,
 }
 
 impl Default for RedditConfig {
@@ -43,10 +73,35 @@ impl Default for RedditConfig {
             user_agent: default_user_agent(),
             scopes: default_scopes(),
             redirect_uri: default_redirect_uri(),
+            token_storage: default_token_storage(),
         }
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LemmyConfig {
+    #[serde(default = "default_lemmy_instance")]
+    pub instance_url: String,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+}
+
+impl Default for LemmyConfig {
+    fn default() -> Self {
+        Self {
+            instance_url: default_lemmy_instance(),
+            username: String::new(),
+            password: String::new(),
+        }
+    }
+}
+
+fn default_lemmy_instance() -> String {
+    "https://lemmy.ml".into()
+}
+
 fn default_user_agent() -> String {
     "hn-tui-dev/0.1 (+https://github.com/danielmerja/hn-tui)".to_string()
 }
@@ -59,6 +114,7 @@ fn default_scopes() -> Vec<String> {
         "vote".into(),
         "submit".into(),
         "subscribe".into(),
+        "privatemessages".into(),
     ]
 }
 
@@ -66,16 +122,130 @@ fn default_redirect_uri() -> String {
     "http://127.0.0.1:65010/hn-tui/callback".into()
 }
 
+fn default_token_storage() -> String {
+    "auto".into()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct UIConfig {
     #[serde(default = "default_theme")]
     pub theme: String,
+    #[serde(default)]
+    pub pipe_command: Vec<String>,
+    /// Command that reads text on stdin and prints its translation; empty
+    /// runs translate-shell (`trans -brief :en`).
+    #[serde(default)]
+    pub translate_command: Vec<String>,
+    /// Command that opens links, with `{url}` replaced by the link (appended
+    /// when absent); empty uses the system browser.
+    #[serde(default)]
+    pub browser_command: Vec<String>,
+    /// Per-domain commands such as `youtube.com: [mpv]`, overriding
+    /// `browser_command`; a domain also covers its subdomains.
+    #[serde(default)]
+    pub browser_overrides: HashMap<String, Vec<String>>,
+    /// `auto` copies through the system clipboard and falls back to the
+    /// terminal's OSC 52 sequence; `osc52` always uses the sequence.
+    #[serde(default = "default_clipboard")]
+    pub clipboard: String,
+    #[serde(default)]
+    pub screen_reader: bool,
+    #[serde(default)]
+    pub reduced_motion: bool,
+    #[serde(default)]
+    pub comment_depth_colors: Vec<String>,
+    #[serde(default)]
+    pub comment_indent_guides: bool,
+    /// Metadata shown after the score in post rows, in order: `domain`,
+    /// `flair`, `age`, `comments`, `awards`, `upvote_ratio`.
+    #[serde(default = "default_post_row_fields")]
+    pub post_row_fields: Vec<String>,
+    /// Keep the default comment sort even when a subreddit suggests another.
+    #[serde(default)]
+    pub ignore_suggested_sort: bool,
+    /// Size of the "Recent" group above the subreddit list; 0 hides it.
+    #[serde(default = "default_recent_subreddits")]
+    pub recent_subreddits: usize,
+    /// Drop the `†` old.reddit puts beside controversial comment scores.
+    #[serde(default)]
+    pub hide_controversial_marker: bool,
+    /// Collapse comments scoring below this. Ignored on Hacker News, which
+    /// reports no comment scores.
+    #[serde(default)]
+    pub collapse_below_score: Option<i64>,
+    #[serde(default)]
+    pub collapse_automoderator: bool,
+    /// Collapse replies nested deeper than this many levels; 0 shows every level.
+    #[serde(default)]
+    pub collapse_depth: usize,
+    /// Refetch the open feed this often and offer its new posts; `0s` is off.
+    #[serde(default, with = "humantime_serde")]
+    pub auto_refresh: Duration,
+    /// Per-feed intervals such as `r/news: 2m`, overriding `auto_refresh`.
+    #[serde(default)]
+    pub auto_refresh_feeds: HashMap<String, String>,
+    /// Raise a desktop notification when a watched thread gets new comments,
+    /// a keyword alert matches a new post, or an inbox message arrives.
+    #[serde(default)]
+    pub desktop_notifications: bool,
+    /// Poll the inbox of every saved Reddit account, not just the active one.
+    #[serde(default)]
+    pub inbox_all_accounts: bool,
+    /// Reopen the last feed, sort, selected post, and scroll position on launch.
+    #[serde(default)]
+    pub restore_session: bool,
+    /// Let only one instance run; later launches hand their target to it.
+    #[serde(default)]
+    pub single_instance: bool,
+    /// Record time spent, posts read, and votes cast per subreddit locally.
+    #[serde(default)]
+    pub usage_stats: bool,
+    /// Start in privacy mode: no history, drafts, or searches are saved.
+    #[serde(default)]
+    pub privacy_mode: bool,
+    /// Keep NSFW posts, subreddits, and media out no matter what is toggled.
+    #[serde(default)]
+    pub sfw_only: bool,
+    /// Keep the comment sort picked for each post across restarts, not just
+    /// for the session.
+    #[serde(default)]
+    pub remember_comment_sorts: bool,
+    /// Leave stickied posts out of feeds instead of pinning them to the top.
+    #[serde(default)]
+    pub hide_stickied: bool,
 }
 
 impl Default for UIConfig {
     fn default() -> Self {
         Self {
             theme: default_theme(),
+            pipe_command: Vec::new(),
+            translate_command: Vec::new(),
+            browser_command: Vec::new(),
+            browser_overrides: HashMap::new(),
+            clipboard: default_clipboard(),
+            screen_reader: false,
+            reduced_motion: false,
+            comment_depth_colors: Vec::new(),
+            comment_indent_guides: false,
+            post_row_fields: default_post_row_fields(),
+            ignore_suggested_sort: false,
+            recent_subreddits: default_recent_subreddits(),
+            hide_controversial_marker: false,
+            collapse_below_score: None,
+            collapse_automoderator: false,
+            collapse_depth: 0,
+            auto_refresh: Duration::ZERO,
+            auto_refresh_feeds: HashMap::new(),
+            desktop_notifications: false,
+            inbox_all_accounts: false,
+            restore_session: false,
+            single_instance: false,
+            usage_stats: false,
+            privacy_mode: false,
+            sfw_only: false,
+            remember_comment_sorts: false,
+            hide_stickied: false,
         }
     }
 }
@@ -84,6 +254,18 @@ fn default_theme() -> String {
     "default".into()
 }
 
+fn default_clipboard() -> String {
+    "auto".into()
+}
+
+fn default_recent_subreddits() -> usize {
+    5
+}
+
+fn default_post_row_fields() -> Vec<String> {
+    vec!["comments".into(), "domain".into()]
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct MediaConfig {
     #[serde(default = "default_cache_dir")]
@@ -96,6 +278,22 @@ pub struct MediaConfig {
     pub workers: usize,
     #[serde(default = "default_media_queue_depth")]
     pub max_queue_depth: usize,
+    #[serde(default = "default_video_ttl_duration", with = "humantime_serde")]
+    pub video_ttl: Duration,
+    /// Where "save media" writes files. Supports a leading `~` and the
+    /// `{subreddit}` and `{author}` placeholders; empty uses the OS download dir.
+    #[serde(default)]
+    pub download_dir: String,
+    /// Name for each saved file, e.g. `{subreddit}_{post_id}_{index}.{ext}`.
+    /// Also accepts `{author}` and `{title}`; empty keeps the name from the URL,
+    /// except that gallery images are saved as `{title}-{index}`.
+    #[serde(default)]
+    pub filename_template: String,
+    /// `auto` treats SSH sessions (`SSH_TTY`, `SSH_CONNECTION`) as remote,
+    /// `on` always and `off` never. Remote sessions draw previews with text
+    /// cells instead of kitty graphics and download only the one in view.
+    #[serde(default = "default_remote_session")]
+    pub remote_session: String,
 }
 
 impl Default for MediaConfig {
@@ -106,12 +304,20 @@ impl Default for MediaConfig {
             default_ttl: default_media_ttl_duration(),
             workers: default_workers(),
             max_queue_depth: default_media_queue_depth(),
+            video_ttl: default_video_ttl_duration(),
+            download_dir: String::new(),
+            filename_template: String::new(),
+            remote_session: default_remote_session(),
         }
     }
 }
 
+fn default_remote_session() -> String {
+    "auto".into()
+}
+
 fn default_cache_dir() -> Option<PathBuf> {
-    dirs::cache_dir().map(|dir| dir.join("hn-tui"))
+    dirs::cache_dir().map(|dir| profile::scope(dir.join("hn-tui")))
 }
 
 fn default_max_size_bytes() -> i64 {
@@ -122,6 +328,10 @@ fn default_media_ttl_duration() -> Duration {
     Duration::from_secs(6 * 60 * 60)
 }
 
+fn default_video_ttl_duration() -> Duration {
+    Duration::from_secs(12 * 60 * 60)
+}
+
 fn default_media_queue_depth() -> usize {
     16
 }
@@ -130,6 +340,54 @@ fn default_workers() -> usize {
     2
 }
 
+/// Limits for the in-memory feed, comment, and rendered-post caches. Each
+/// cache evicts its least recently used entry once full; `0` disables it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CacheConfig {
+    #[serde(default = "default_feed_cache_entries")]
+    pub feed_entries: usize,
+    #[serde(default = "default_comment_cache_entries")]
+    pub comment_entries: usize,
+    #[serde(default = "default_content_cache_entries")]
+    pub content_entries: usize,
+    #[serde(default = "default_feed_cache_ttl", with = "humantime_serde")]
+    pub feed_ttl: Duration,
+    #[serde(default = "default_comment_cache_ttl", with = "humantime_serde")]
+    pub comment_ttl: Duration,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            feed_entries: default_feed_cache_entries(),
+            comment_entries: default_comment_cache_entries(),
+            content_entries: default_content_cache_entries(),
+            feed_ttl: default_feed_cache_ttl(),
+            comment_ttl: default_comment_cache_ttl(),
+        }
+    }
+}
+
+fn default_feed_cache_entries() -> usize {
+    16
+}
+
+fn default_comment_cache_entries() -> usize {
+    64
+}
+
+fn default_content_cache_entries() -> usize {
+    256
+}
+
+fn default_feed_cache_ttl() -> Duration {
+    Duration::from_secs(45)
+}
+
+fn default_comment_cache_ttl() -> Duration {
+    Duration::from_secs(120)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct PlayerConfig {
     #[serde(default = "default_video_command")]
@@ -155,6 +413,97 @@ fn default_video_detach() -> bool {
     true
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UpdateConfig {
+    #[serde(default = "default_update_channel")]
+    pub channel: String,
+    #[serde(default = "default_update_snooze_days")]
+    pub snooze_days: u32,
+}
+
+impl Default for UpdateConfig {
+    fn default() -> Self {
+        Self {
+            channel: default_update_channel(),
+            snooze_days: default_update_snooze_days(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct NetworkConfig {
+    #[serde(default)]
+    pub proxy: ProxyConfig,
+    /// SOCKS5 proxy for all traffic, e.g. `127.0.0.1:9050` for Tor. Takes
+    /// precedence over `proxy`; bare `host:port` resolves DNS through it.
+    #[serde(default)]
+    pub socks_proxy: String,
+    #[serde(default = "default_connect_timeout", with = "humantime_serde")]
+    pub connect_timeout: Duration,
+    /// Whole-request limit, from connecting until the body is read. Media and
+    /// video downloads get a much longer limit instead.
+    #[serde(default = "default_request_timeout", with = "humantime_serde")]
+    pub timeout: Duration,
+    /// Extra attempts after a connection failure, a timeout, or a 502/503/504.
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+    /// Delay before the first retry; each later retry doubles it.
+    #[serde(default = "default_retry_backoff", with = "humantime_serde")]
+    pub retry_backoff: Duration,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            proxy: ProxyConfig::default(),
+            socks_proxy: String::new(),
+            connect_timeout: default_connect_timeout(),
+            timeout: default_request_timeout(),
+            retries: default_retries(),
+            retry_backoff: default_retry_backoff(),
+        }
+    }
+}
+
+fn default_connect_timeout() -> Duration {
+    Duration::from_secs(10)
+}
+
+fn default_request_timeout() -> Duration {
+    Duration::from_secs(30)
+}
+
+fn default_retries() -> u32 {
+    2
+}
+
+fn default_retry_backoff() -> Duration {
+    Duration::from_millis(500)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct ProxyConfig {
+    /// Proxy for every HTTP request, e.g. `http://proxy.corp:3128`. When
+    /// empty, the `HTTP_PROXY`/`HTTPS_PROXY`/`NO_PROXY` environment applies.
+    #[serde(default)]
+    pub url: String,
+    /// Comma-separated hosts or domains that bypass `url`.
+    #[serde(default)]
+    pub no_proxy: String,
+}
+
+fn default_backend() -> String {
+    "hackernews".into()
+}
+
+fn default_update_channel() -> String {
+    "stable".into()
+}
+
+fn default_update_snooze_days() -> u32 {
+    7
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct LoadOptions {
     pub config_file: Option<PathBuf>,
@@ -206,10 +555,81 @@ fn merge_config(mut base: Config, other: Config) -> Config {
     if !other.reddit.redirect_uri.is_empty() {
         base.reddit.redirect_uri = other.reddit.redirect_uri;
     }
+    if !other.reddit.token_storage.is_empty()
+        && other.reddit.token_storage != default_token_storage()
+    {
+        base.reddit.token_storage = other.reddit.token_storage;
+    }
+
+    if !other.lemmy.instance_url.is_empty() && other.lemmy.instance_url != default_lemmy_instance()
+    {
+        base.lemmy.instance_url = other.lemmy.instance_url;
+    }
+    if !other.lemmy.username.is_empty() {
+        base.lemmy.username = other.lemmy.username;
+    }
+    if !other.lemmy.password.is_empty() {
+        base.lemmy.password = other.lemmy.password;
+    }
+
+    if !other.backend.is_empty() && other.backend != default_backend() {
+        base.backend = other.backend;
+    }
 
     if !other.ui.theme.is_empty() {
         base.ui.theme = other.ui.theme;
     }
+    if !other.ui.pipe_command.is_empty() {
+        base.ui.pipe_command = other.ui.pipe_command;
+    }
+    if !other.ui.translate_command.is_empty() {
+        base.ui.translate_command = other.ui.translate_command;
+    }
+    if !other.ui.browser_command.is_empty() {
+        base.ui.browser_command = other.ui.browser_command;
+    }
+    if !other.ui.browser_overrides.is_empty() {
+        base.ui.browser_overrides = other.ui.browser_overrides;
+    }
+    if !other.ui.clipboard.is_empty() && other.ui.clipboard != default_clipboard() {
+        base.ui.clipboard = other.ui.clipboard;
+    }
+    base.ui.screen_reader |= other.ui.screen_reader;
+    base.ui.reduced_motion |= other.ui.reduced_motion;
+    if !other.ui.comment_depth_colors.is_empty() {
+        base.ui.comment_depth_colors = other.ui.comment_depth_colors;
+    }
+    base.ui.comment_indent_guides |= other.ui.comment_indent_guides;
+    base.ui.ignore_suggested_sort |= other.ui.ignore_suggested_sort;
+    if other.ui.post_row_fields != default_post_row_fields() {
+        base.ui.post_row_fields = other.ui.post_row_fields;
+    }
+    if other.ui.recent_subreddits != default_recent_subreddits() {
+        base.ui.recent_subreddits = other.ui.recent_subreddits;
+    }
+    base.ui.hide_controversial_marker |= other.ui.hide_controversial_marker;
+    if other.ui.collapse_below_score.is_some() {
+        base.ui.collapse_below_score = other.ui.collapse_below_score;
+    }
+    base.ui.collapse_automoderator |= other.ui.collapse_automoderator;
+    if other.ui.collapse_depth != 0 {
+        base.ui.collapse_depth = other.ui.collapse_depth;
+    }
+    if !other.ui.auto_refresh.is_zero() {
+        base.ui.auto_refresh = other.ui.auto_refresh;
+    }
+    if !other.ui.auto_refresh_feeds.is_empty() {
+        base.ui.auto_refresh_feeds = other.ui.auto_refresh_feeds;
+    }
+    base.ui.desktop_notifications |= other.ui.desktop_notifications;
+    base.ui.inbox_all_accounts |= other.ui.inbox_all_accounts;
+    base.ui.restore_session |= other.ui.restore_session;
+    base.ui.single_instance |= other.ui.single_instance;
+    base.ui.usage_stats |= other.ui.usage_stats;
+    base.ui.privacy_mode |= other.ui.privacy_mode;
+    base.ui.sfw_only |= other.ui.sfw_only;
+    base.ui.remember_comment_sorts |= other.ui.remember_comment_sorts;
+    base.ui.hide_stickied |= other.ui.hide_stickied;
 
     if other.media.cache_dir.is_some() {
         base.media.cache_dir = other.media.cache_dir;
@@ -224,12 +644,77 @@ fn merge_config(mut base: Config, other: Config) -> Config {
     if other.media.max_queue_depth != 0 {
         base.media.max_queue_depth = other.media.max_queue_depth;
     }
+    if other.media.video_ttl != default_video_ttl_duration() {
+        base.media.video_ttl = other.media.video_ttl;
+    }
+    if !other.media.download_dir.trim().is_empty() {
+        base.media.download_dir = other.media.download_dir;
+    }
+    if !other.media.filename_template.trim().is_empty() {
+        base.media.filename_template = other.media.filename_template;
+    }
+    if !other.media.remote_session.trim().is_empty()
+        && other.media.remote_session != default_remote_session()
+    {
+        base.media.remote_session = other.media.remote_session;
+    }
+
+    if other.cache.feed_entries != default_feed_cache_entries() {
+        base.cache.feed_entries = other.cache.feed_entries;
+    }
+    if other.cache.comment_entries != default_comment_cache_entries() {
+        base.cache.comment_entries = other.cache.comment_entries;
+    }
+    if other.cache.content_entries != default_content_cache_entries() {
+        base.cache.content_entries = other.cache.content_entries;
+    }
+    if other.cache.feed_ttl != default_feed_cache_ttl() {
+        base.cache.feed_ttl = other.cache.feed_ttl;
+    }
+    if other.cache.comment_ttl != default_comment_cache_ttl() {
+        base.cache.comment_ttl = other.cache.comment_ttl;
+    }
 
     if !other.player.video_command.is_empty() {
         base.player.video_command = other.player.video_command;
     }
     base.player.video_detach = other.player.video_detach;
 
+    if !other.update.channel.is_empty() && other.update.channel != default_update_channel() {
+        base.update.channel = other.update.channel;
+    }
+    if other.update.snooze_days != default_update_snooze_days() {
+        base.update.snooze_days = other.update.snooze_days;
+    }
+
+    if !other.network.proxy.url.trim().is_empty() {
+        base.network.proxy.url = other.network.proxy.url;
+    }
+    if !other.network.proxy.no_proxy.trim().is_empty() {
+        base.network.proxy.no_proxy = other.network.proxy.no_proxy;
+    }
+    if !other.network.socks_proxy.trim().is_empty() {
+        base.network.socks_proxy = other.network.socks_proxy;
+    }
+    if other.network.connect_timeout != default_connect_timeout() {
+        base.network.connect_timeout = other.network.connect_timeout;
+    }
+    if other.network.timeout != default_request_timeout() {
+        base.network.timeout = other.network.timeout;
+    }
+    if other.network.retries != default_retries() {
+        base.network.retries = other.network.retries;
+    }
+    if other.network.retry_backoff != default_retry_backoff() {
+        base.network.retry_backoff = other.network.retry_backoff;
+    }
+    if !other.plugins.is_empty() {
+        base.plugins = other.plugins;
+    }
+    if !other.scripts.is_empty() {
+        base.scripts = other.scripts;
+    }
+
     base
 }
 
@@ -263,6 +748,10 @@ fn apply_env_value(cfg: &mut Config, key: &str, value: String) {
         "reddit.client_secret" => cfg.reddit.client_secret = value,
         "reddit.user_agent" => cfg.reddit.user_agent = value,
         "reddit.redirect_uri" => cfg.reddit.redirect_uri = value,
+        "reddit.token_storage" => cfg.reddit.token_storage = value,
+        "lemmy.instance_url" => cfg.lemmy.instance_url = value,
+        "lemmy.username" => cfg.lemmy.username = value,
+        "lemmy.password" => cfg.lemmy.password = value,
         "reddit.scopes" => {
             cfg.reddit.scopes = value
                 .split(',')
@@ -270,7 +759,135 @@ fn apply_env_value(cfg: &mut Config, key: &str, value: String) {
                 .filter(|s| !s.is_empty())
                 .collect();
         }
+        "backend" => cfg.backend = value,
         "ui.theme" => cfg.ui.theme = value,
+        "ui.pipe_command" => {
+            cfg.ui.pipe_command = value
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+        }
+        "ui.translate_command" => {
+            cfg.ui.translate_command = value
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+        }
+        "ui.browser_command" => {
+            cfg.ui.browser_command = value
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+        }
+        "ui.clipboard" => cfg.ui.clipboard = value,
+        "ui.screen_reader" => {
+            cfg.ui.screen_reader = matches!(value.as_str(), "1" | "true" | "TRUE" | "True");
+        }
+        "ui.reduced_motion" => {
+            cfg.ui.reduced_motion = matches!(value.as_str(), "1" | "true" | "TRUE" | "True");
+        }
+        "ui.comment_depth_colors" => {
+            cfg.ui.comment_depth_colors = value
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+        }
+        "ui.post_row_fields" => {
+            cfg.ui.post_row_fields = value
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+        }
+        "ui.comment_indent_guides" => {
+            cfg.ui.comment_indent_guides = matches!(value.as_str(), "1" | "true" | "TRUE" | "True");
+        }
+        "ui.ignore_suggested_sort" => {
+            cfg.ui.ignore_suggested_sort = matches!(value.as_str(), "1" | "true" | "TRUE" | "True");
+        }
+        "ui.recent_subreddits" => {
+            if let Ok(parsed) = value.parse::<usize>() {
+                cfg.ui.recent_subreddits = parsed;
+            }
+        }
+        "ui.hide_controversial_marker" => {
+            cfg.ui.hide_controversial_marker =
+                matches!(value.as_str(), "1" | "true" | "TRUE" | "True");
+        }
+        "ui.collapse_below_score" => {
+            if let Ok(parsed) = value.parse::<i64>() {
+                cfg.ui.collapse_below_score = Some(parsed);
+            }
+        }
+        "ui.collapse_automoderator" => {
+            cfg.ui.collapse_automoderator =
+                matches!(value.as_str(), "1" | "true" | "TRUE" | "True");
+        }
+        "ui.collapse_depth" => {
+            if let Ok(parsed) = value.parse::<usize>() {
+                cfg.ui.collapse_depth = parsed;
+            }
+        }
+        "ui.auto_refresh" => {
+            if let Ok(duration) = humantime::parse_duration(&value) {
+                cfg.ui.auto_refresh = duration;
+            }
+        }
+        "ui.inbox_all_accounts" => {
+            cfg.ui.inbox_all_accounts = matches!(value.as_str(), "1" | "true" | "TRUE" | "True");
+        }
+        "ui.restore_session" => {
+            cfg.ui.restore_session = matches!(value.as_str(), "1" | "true" | "TRUE" | "True");
+        }
+        "ui.single_instance" => {
+            cfg.ui.single_instance = matches!(value.as_str(), "1" | "true" | "TRUE" | "True");
+        }
+        "ui.usage_stats" => {
+            cfg.ui.usage_stats = matches!(value.as_str(), "1" | "true" | "TRUE" | "True");
+        }
+        "ui.privacy_mode" => {
+            cfg.ui.privacy_mode = matches!(value.as_str(), "1" | "true" | "TRUE" | "True");
+        }
+        "ui.sfw_only" => {
+            cfg.ui.sfw_only = matches!(value.as_str(), "1" | "true" | "TRUE" | "True");
+        }
+        "ui.remember_comment_sorts" => {
+            cfg.ui.remember_comment_sorts =
+                matches!(value.as_str(), "1" | "true" | "TRUE" | "True");
+        }
+        "ui.hide_stickied" => {
+            cfg.ui.hide_stickied = matches!(value.as_str(), "1" | "true" | "TRUE" | "True");
+        }
+        "ui.desktop_notifications" => {
+            cfg.ui.desktop_notifications = matches!(value.as_str(), "1" | "true" | "TRUE" | "True");
+        }
+        "network.proxy.url" => cfg.network.proxy.url = value,
+        "network.proxy.no_proxy" => cfg.network.proxy.no_proxy = value,
+        "network.socks_proxy" => cfg.network.socks_proxy = value,
+        "network.connect_timeout" => {
+            if let Ok(duration) = humantime::parse_duration(&value) {
+                cfg.network.connect_timeout = duration;
+            }
+        }
+        "network.timeout" => {
+            if let Ok(duration) = humantime::parse_duration(&value) {
+                cfg.network.timeout = duration;
+            }
+        }
+        "network.retries" => {
+            if let Ok(parsed) = value.parse::<u32>() {
+                cfg.network.retries = parsed;
+            }
+        }
+        "network.retry_backoff" => {
+            if let Ok(duration) = humantime::parse_duration(&value) {
+                cfg.network.retry_backoff = duration;
+            }
+        }
         "media.cache_dir" => cfg.media.cache_dir = Some(PathBuf::from(value)),
         "media.max_size_bytes" => {
             if let Ok(parsed) = value.parse::<i64>() {
@@ -287,6 +904,39 @@ fn apply_env_value(cfg: &mut Config, key: &str, value: String) {
                 cfg.media.workers = parsed;
             }
         }
+        "media.download_dir" => cfg.media.download_dir = value,
+        "media.filename_template" => cfg.media.filename_template = value,
+        "media.remote_session" => cfg.media.remote_session = value,
+        "media.video_ttl" => {
+            if let Ok(duration) = humantime::parse_duration(&value) {
+                cfg.media.video_ttl = duration;
+            }
+        }
+        "cache.feed_entries" => {
+            if let Ok(parsed) = value.parse::<usize>() {
+                cfg.cache.feed_entries = parsed;
+            }
+        }
+        "cache.comment_entries" => {
+            if let Ok(parsed) = value.parse::<usize>() {
+                cfg.cache.comment_entries = parsed;
+            }
+        }
+        "cache.content_entries" => {
+            if let Ok(parsed) = value.parse::<usize>() {
+                cfg.cache.content_entries = parsed;
+            }
+        }
+        "cache.feed_ttl" => {
+            if let Ok(duration) = humantime::parse_duration(&value) {
+                cfg.cache.feed_ttl = duration;
+            }
+        }
+        "cache.comment_ttl" => {
+            if let Ok(duration) = humantime::parse_duration(&value) {
+                cfg.cache.comment_ttl = duration;
+            }
+        }
         "player.video_command" => {
             cfg.player.video_command = value
                 .split(',')
@@ -294,6 +944,12 @@ fn apply_env_value(cfg: &mut Config, key: &str, value: String) {
                 .filter(|s| !s.is_empty())
                 .collect();
         }
+        "update.channel" => cfg.update.channel = value,
+        "update.snooze_days" => {
+            if let Ok(parsed) = value.parse::<u32>() {
+                cfg.update.snooze_days = parsed;
+            }
+        }
         "player.video_detach" => {
             cfg.player.video_detach = matches!(value.as_str(), "1" | "true" | "TRUE" | "True");
         }
@@ -306,7 +962,7 @@ pub fn default_path() -> Option<PathBuf> {
 }
 
 fn default_config_path() -> Option<PathBuf> {
-    dirs::config_dir().map(|dir| dir.join("hn-tui").join("config.yaml"))
+    dirs::config_dir().map(|dir| profile::scope(dir.join("hn-tui")).join("config.yaml"))
 }
 
 pub fn save_reddit_credentials(
@@ -359,6 +1015,27 @@ pub fn save_reddit_credentials(
     Ok(path)
 }
 
+/// Blanks `lemmy.password` in the config file once the keychain holds it.
+/// Returns false when the file had no password to remove.
+pub fn clear_lemmy_password(path: Option<PathBuf>) -> Result<bool> {
+    let path = match path {
+        Some(path) => path,
+        None => default_config_path().context("config: unable to determine default config path")?,
+    };
+    if !path.exists() {
+        return Ok(false);
+    }
+    let mut cfg = read_config_file(&path)?;
+    if cfg.lemmy.password.is_empty() {
+        return Ok(false);
+    }
+    cfg.lemmy.password.clear();
+    let contents = serde_yaml::to_string(&cfg).context("config: failed to serialize config")?;
+    fs::write(&path, contents)
+        .with_context(|| format!("config: failed to write file {}", path.display()))?;
+    Ok(true)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -381,6 +1058,80 @@ mod tests {
         assert_eq!(saved.reddit.client_id, "client");
     }
 
+    #[test]
+    fn lemmy_password_is_cleared_from_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.yaml");
+        fs::write(&path, "lemmy:\n  username: alice\n  password: hunter2\n").unwrap();
+        assert!(clear_lemmy_password(Some(path.clone())).unwrap());
+        let saved = read_config_file(&path).unwrap();
+        assert_eq!(saved.lemmy.username, "alice");
+        assert!(saved.lemmy.password.is_empty());
+        assert!(!clear_lemmy_password(Some(path)).unwrap());
+    }
+
+    #[test]
+    fn cache_limits_merge_from_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.yaml");
+        fs::write(
+            &path,
+            "cache:\n  feed_entries: 0\n  comment_ttl: 10m\nmedia:\n  video_ttl: 1h\n",
+        )
+        .unwrap();
+        let cfg = merge_config(Config::default(), read_config_file(&path).unwrap());
+        assert_eq!(cfg.cache.feed_entries, 0);
+        assert_eq!(cfg.cache.comment_entries, default_comment_cache_entries());
+        assert_eq!(cfg.cache.comment_ttl, Duration::from_secs(600));
+        assert_eq!(cfg.media.video_ttl, Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn auto_refresh_merges_from_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.yaml");
+        fs::write(
+            &path,
+            "ui:\n  auto_refresh: 5m\n  auto_refresh_feeds:\n    r/news: 1m\n",
+        )
+        .unwrap();
+        let cfg = merge_config(Config::default(), read_config_file(&path).unwrap());
+        assert_eq!(cfg.ui.auto_refresh, Duration::from_secs(300));
+        assert_eq!(cfg.ui.auto_refresh_feeds["r/news"], "1m");
+        assert!(Config::default().ui.auto_refresh.is_zero());
+    }
+
+    #[test]
+    fn network_proxy_merges_from_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.yaml");
+        fs::write(
+            &path,
+            "network:\n  proxy:\n    url: http://proxy.corp:3128\n    no_proxy: localhost\n  \
+             socks_proxy: 127.0.0.1:9050\n",
+        )
+        .unwrap();
+        let cfg = merge_config(Config::default(), read_config_file(&path).unwrap());
+        assert_eq!(cfg.network.proxy.url, "http://proxy.corp:3128");
+        assert_eq!(cfg.network.proxy.no_proxy, "localhost");
+        assert_eq!(cfg.network.socks_proxy, "127.0.0.1:9050");
+
+        let kept = merge_config(cfg.clone(), Config::default());
+        assert_eq!(kept.network, cfg.network);
+    }
+
+    #[test]
+    fn network_timeouts_merge_from_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.yaml");
+        fs::write(&path, "network:\n  timeout: 1m\n  retries: 0\n").unwrap();
+        let cfg = merge_config(Config::default(), read_config_file(&path).unwrap());
+        assert_eq!(cfg.network.timeout, Duration::from_secs(60));
+        assert_eq!(cfg.network.retries, 0);
+        assert_eq!(cfg.network.connect_timeout, Duration::from_secs(10));
+        assert_eq!(cfg.network.retry_backoff, Duration::from_millis(500));
+    }
+
     #[test]
     fn env_overrides() {
         env::set_var("REDDIX_UI__THEME", "dracula");
@@ -1,13 +1,44 @@
 use anyhow::{Context, Result};
 use rand::seq::SliceRandom;
 use std::collections::HashSet;
+use std::path::Path;
 use std::sync::Arc;
 
 use crate::hackernews;
+use crate::lemmy;
 use crate::reddit::{self, CommentSortOption, ListingOptions, SortOption};
 
 pub trait SubredditService: Send + Sync {
     fn list_subreddits(&self, source: reddit::SubredditSource) -> Result<Vec<reddit::Subreddit>>;
+
+    /// Icon (or banner) image URL for a community header, if the backend has one.
+    fn subreddit_artwork(&self, _name: &str) -> Result<Option<String>> {
+        Ok(None)
+    }
+
+    /// Live name suggestions for a partially typed community name.
+    fn autocomplete_subreddits(
+        &self,
+        _query: &str,
+        _include_over_18: bool,
+    ) -> Result<Vec<reddit::Subreddit>> {
+        Ok(Vec::new())
+    }
+
+    /// Resolves a random community (`r/random`, or `r/randnsfw` when `nsfw`).
+    fn random_subreddit(&self, _nsfw: bool) -> Result<Option<String>> {
+        Ok(None)
+    }
+
+    /// The first `limit` communities from `source`, for browsing ones you
+    /// haven't joined. Backends without community listings return nothing.
+    fn discover_subreddits(
+        &self,
+        _source: reddit::SubredditSource,
+        _limit: u32,
+    ) -> Result<Vec<reddit::Subreddit>> {
+        Ok(Vec::new())
+    }
 }
 
 pub trait FeedService: Send + Sync {
@@ -34,6 +65,20 @@ pub trait FeedService: Send + Sync {
         sort: SortOption,
         opts: reddit::ListingOptions,
     ) -> Result<reddit::Listing<reddit::Post>>;
+
+    /// The signed-in account's saved, upvoted, or downvoted items.
+    fn load_account_listing(
+        &self,
+        _listing: reddit::AccountListing,
+        _opts: reddit::ListingOptions,
+    ) -> Result<reddit::Listing<reddit::Post>> {
+        anyhow::bail!("Account listings are not available on this backend")
+    }
+
+    /// The post behind a thread link, so the thread can be opened on its own.
+    fn load_post(&self, _subreddit: &str, _article: &str) -> Result<reddit::Post> {
+        anyhow::bail!("Opening threads by link is not available on this backend")
+    }
 }
 
 pub trait CommentService: Send + Sync {
@@ -43,6 +88,12 @@ pub trait CommentService: Send + Sync {
         article: &str,
         sort: CommentSortOption,
     ) -> Result<reddit::PostComments>;
+
+    /// The thread's comment count, for backends that can report it without
+    /// loading the comments. Watched threads check it before refetching.
+    fn comment_count(&self, _subreddit: &str, _article: &str) -> Result<Option<i64>> {
+        Ok(None)
+    }
 }
 
 pub trait InteractionService: Send + Sync {
@@ -54,6 +105,126 @@ pub trait InteractionService: Send + Sync {
     fn reply(&self, parent: &str, text: &str) -> Result<reddit::Comment>;
     fn subscribe(&self, subreddit: &str) -> Result<()>;
     fn is_subscribed(&self, subreddit: &str) -> Result<bool>;
+
+    /// Uploads a local image and submits it to `subreddit` as an image post.
+    /// `resubmit` posts it even if Reddit flags the link as a repost.
+    fn submit_image(
+        &self,
+        _subreddit: &str,
+        _title: &str,
+        _path: &Path,
+        _resubmit: bool,
+        _progress: reddit::UploadProgress,
+    ) -> Result<()> {
+        anyhow::bail!("Image posts are not supported by this backend")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BackendKind {
+    #[default]
+    HackerNews,
+    Reddit,
+    Lemmy,
+}
+
+impl BackendKind {
+    pub fn from_config(value: &str) -> Self {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "reddit" => BackendKind::Reddit,
+            "lemmy" => BackendKind::Lemmy,
+            _ => BackendKind::HackerNews,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BackendKind::HackerNews => "Hacker News",
+            BackendKind::Reddit => "Reddit",
+            BackendKind::Lemmy => "Lemmy",
+        }
+    }
+
+    /// The broadest feed the backend serves, used when nothing narrower is
+    /// open (for example, as the scope of a new keyword alert).
+    pub fn main_feed(self) -> &'static str {
+        match self {
+            BackendKind::HackerNews => "Top",
+            BackendKind::Reddit | BackendKind::Lemmy => "r/all",
+        }
+    }
+
+    /// Whether the backend lists communities to discover and can pick a
+    /// random one. Only Reddit does.
+    pub fn discovers_communities(self) -> bool {
+        matches!(self, BackendKind::Reddit)
+    }
+
+    /// Whether comments carry a real score. The HN API reports none, so
+    /// every HN comment reads as zero.
+    pub fn reports_comment_scores(self) -> bool {
+        !matches!(self, BackendKind::HackerNews)
+    }
+}
+
+/// The services a content source provides to the UI, built from one client.
+#[derive(Clone)]
+pub struct Backend {
+    pub kind: BackendKind,
+    pub subreddits: Arc<dyn SubredditService + Send + Sync>,
+    pub feed: Arc<dyn FeedService + Send + Sync>,
+    pub comments: Arc<dyn CommentService + Send + Sync>,
+    pub interaction: Arc<dyn InteractionService + Send + Sync>,
+    /// API calls recorded by the client, when the backend keeps a log.
+    pub requests: Option<reddit::RequestLog>,
+}
+
+impl Backend {
+    pub fn hacker_news(client: Arc<hackernews::Client>) -> Self {
+        Self {
+            kind: BackendKind::HackerNews,
+            subreddits: Arc::new(HackerNewsCategoryService::new(client.clone())),
+            feed: Arc::new(HackerNewsFeedService::new(client.clone())),
+            comments: Arc::new(HackerNewsCommentService::new(client)),
+            interaction: Arc::new(HackerNewsInteractionService::new()),
+            requests: None,
+        }
+    }
+
+    pub fn reddit(client: Arc<reddit::Client>) -> Self {
+        let requests = Some(client.request_log());
+        Self {
+            kind: BackendKind::Reddit,
+            subreddits: Arc::new(RedditSubredditService::new(client.clone())),
+            feed: Arc::new(RedditFeedService::new(client.clone())),
+            comments: Arc::new(RedditCommentService::new(client.clone())),
+            interaction: Arc::new(RedditInteractionService::new(client)),
+            requests,
+        }
+    }
+
+    pub fn lemmy(client: Arc<lemmy::Client>) -> Self {
+        Self {
+            kind: BackendKind::Lemmy,
+            subreddits: Arc::new(LemmyCommunityService::new(client.clone())),
+            feed: Arc::new(LemmyFeedService::new(client.clone())),
+            comments: Arc::new(LemmyCommentService::new(client.clone())),
+            interaction: Arc::new(LemmyInteractionService::new(client)),
+            requests: None,
+        }
+    }
+
+    /// Categories shown before the backend's own list loads. Reddit and Lemmy
+    /// start empty so the UI falls back to its frontpage/all/popular defaults.
+    pub fn default_categories(&self) -> Vec<String> {
+        match self.kind {
+            BackendKind::HackerNews => ["Top", "New", "Best", "Ask HN", "Show HN", "Jobs"]
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+            BackendKind::Reddit | BackendKind::Lemmy => Vec::new(),
+        }
+    }
 }
 
 pub struct RedditSubredditService {
@@ -105,6 +276,48 @@ impl SubredditService for RedditSubredditService {
 
         Ok(all)
     }
+
+    fn subreddit_artwork(&self, name: &str) -> Result<Option<String>> {
+        let about = self.client.subreddit_about(name)?;
+        Ok(about.icon_url().or_else(|| about.banner_url()))
+    }
+
+    fn autocomplete_subreddits(
+        &self,
+        query: &str,
+        include_over_18: bool,
+    ) -> Result<Vec<reddit::Subreddit>> {
+        self.client
+            .subreddit_autocomplete(query, include_over_18, 10)
+            .context("fetch subreddit suggestions")
+    }
+
+    fn random_subreddit(&self, nsfw: bool) -> Result<Option<String>> {
+        self.client
+            .random_subreddit(nsfw)
+            .context("resolve random subreddit")
+            .map(Some)
+    }
+
+    fn discover_subreddits(
+        &self,
+        source: reddit::SubredditSource,
+        limit: u32,
+    ) -> Result<Vec<reddit::Subreddit>> {
+        let opts = ListingOptions {
+            limit: Some(limit),
+            ..Default::default()
+        };
+        let listing = self
+            .client
+            .subreddits(source, opts)
+            .context("fetch subreddit discovery listing")?;
+        Ok(listing
+            .children
+            .into_iter()
+            .map(|thing| thing.data)
+            .collect())
+    }
 }
 
 pub struct RedditFeedService {
@@ -150,6 +363,16 @@ impl FeedService for RedditFeedService {
             .context("fetch user submissions")
     }
 
+    fn load_account_listing(
+        &self,
+        listing: reddit::AccountListing,
+        opts: ListingOptions,
+    ) -> Result<reddit::Listing<reddit::Post>> {
+        self.client
+            .account_listing(listing, opts)
+            .with_context(|| format!("fetch {} items", listing.as_str()))
+    }
+
     fn search_posts(
         &self,
         query: &str,
@@ -160,6 +383,17 @@ impl FeedService for RedditFeedService {
             .search_posts(query, sort, opts)
             .context("search reddit")
     }
+
+    fn load_post(&self, subreddit: &str, article: &str) -> Result<reddit::Post> {
+        let opts = ListingOptions {
+            limit: Some(1),
+            ..Default::default()
+        };
+        self.client
+            .comments(subreddit, article, CommentSortOption::default(), opts)
+            .map(|thread| thread.post)
+            .context("fetch reddit thread")
+    }
 }
 
 pub struct RedditCommentService {
@@ -227,6 +461,18 @@ impl InteractionService for RedditInteractionService {
     fn reply(&self, parent: &str, text: &str) -> Result<reddit::Comment> {
         self.client.reply(parent, text)
     }
+
+    fn submit_image(
+        &self,
+        subreddit: &str,
+        title: &str,
+        path: &Path,
+        resubmit: bool,
+        progress: reddit::UploadProgress,
+    ) -> Result<()> {
+        self.client
+            .submit_image(subreddit, title, path, resubmit, progress)
+    }
 }
 
 #[derive(Default)]
@@ -319,6 +565,8 @@ impl CommentService for MockCommentService {
                 created_utc: 0.0,
                 thumbnail: String::new(),
                 stickied: false,
+                locked: false,
+                archived: false,
                 over_18: false,
                 spoiler: false,
                 post_hint: String::new(),
@@ -329,6 +577,11 @@ impl CommentService for MockCommentService {
                 preview: reddit::Preview::default(),
                 gallery_data: None,
                 media_metadata: None,
+                suggested_sort: None,
+                domain: String::new(),
+                link_flair_text: None,
+                total_awards_received: 0,
+                upvote_ratio: None,
             },
             comments: reddit::Listing {
                 after: None,
@@ -382,6 +635,11 @@ impl InteractionService for MockInteractionService {
             score_hidden: false,
             depth: 0,
             created_utc: 0.0,
+            controversiality: 0,
+            edited: None,
+            stickied: false,
+            gilded: 0,
+            media_metadata: None,
             replies: None,
         })
     }
@@ -404,6 +662,8 @@ fn mock_listing(title: &str) -> reddit::Listing<reddit::Post> {
         created_utc: 0.0,
         thumbnail: String::new(),
         stickied: false,
+        locked: false,
+        archived: false,
         over_18: false,
         spoiler: false,
         post_hint: String::new(),
@@ -414,6 +674,11 @@ fn mock_listing(title: &str) -> reddit::Listing<reddit::Post> {
         preview: reddit::Preview::default(),
         gallery_data: None,
         media_metadata: None,
+        suggested_sort: None,
+        domain: String::new(),
+        link_flair_text: None,
+        total_awards_received: 0,
+        upvote_ratio: None,
     }];
 
     posts.shuffle(&mut rng);
@@ -441,6 +706,28 @@ pub fn sort_option_from_key(key: &str) -> SortOption {
     }
 }
 
+/// Web address of a post's discussion thread, built from its permalink.
+pub fn post_thread_url(post: &reddit::Post) -> Option<String> {
+    let permalink = post.permalink.trim();
+    if permalink.is_empty() {
+        return None;
+    }
+    let thread_url = if permalink.starts_with("http") {
+        permalink.to_string()
+    } else if permalink.contains("?id=") {
+        // Already has correct format like /item?id=123
+        format!("https://news.ycombinator.com{}", permalink)
+    } else if let Some(id) = permalink.strip_prefix("/item/") {
+        // Old format /item/123, convert to ?id=123
+        format!("https://news.ycombinator.com/item?id={}", id)
+    } else if permalink.starts_with("/r/") {
+        format!("https://www.reddit.com{}", permalink)
+    } else {
+        format!("https://news.ycombinator.com{}", permalink)
+    };
+    Some(thread_url)
+}
+
 // HackerNews service implementations
 pub struct HackerNewsCategoryService {
     _client: Arc<hackernews::Client>,
@@ -621,6 +908,16 @@ impl FeedService for HackerNewsFeedService {
             }).collect(),
         })
     }
+
+    fn load_post(&self, _subreddit: &str, article: &str) -> Result<reddit::Post> {
+        let story_id: i64 = article.parse()
+            .context("parse story ID")?;
+        
+        self.client
+            .story(story_id)
+            .map(hn_story_to_reddit_post)
+            .context("fetch HN story")
+    }
 }
 
 pub struct HackerNewsCommentService {
@@ -661,8 +958,16 @@ impl CommentService for HackerNewsCommentService {
             },
         })
     }
+
+    fn comment_count(&self, _subreddit: &str, article: &str) -> Result<Option<i64>> {
+        let story_id: i64 = article.parse().context("parse story ID")?;
+        self.client
+            .descendants(story_id)
+            .context("fetch HN comment count")
+    }
 }
 
+#[derive(Default)]
 pub struct HackerNewsInteractionService;
 
 impl HackerNewsInteractionService {
@@ -722,3 +1027,224 @@ fn hn_story_to_reddit_post(story: hackernews::Story) -> reddit::Post {
 fn hn_comment_to_reddit_comment(comment: reddit::Comment) -> reddit::Comment {
     comment
 }
+
+// Lemmy service implementations
+pub struct LemmyCommunityService {
+    client: Arc<lemmy::Client>,
+}
+
+impl LemmyCommunityService {
+    pub fn new(client: Arc<lemmy::Client>) -> Self {
+        Self { client }
+    }
+}
+
+impl SubredditService for LemmyCommunityService {
+    fn list_subreddits(&self, source: reddit::SubredditSource) -> Result<Vec<reddit::Subreddit>> {
+        let listing = match source {
+            reddit::SubredditSource::Subscriptions if self.client.has_credentials() => {
+                lemmy::ListingType::Subscribed
+            }
+            _ => lemmy::ListingType::All,
+        };
+        self.client
+            .communities(listing)
+            .context("fetch lemmy communities")
+    }
+}
+
+pub struct LemmyFeedService {
+    client: Arc<lemmy::Client>,
+}
+
+impl LemmyFeedService {
+    pub fn new(client: Arc<lemmy::Client>) -> Self {
+        Self { client }
+    }
+}
+
+fn lemmy_page(opts: &ListingOptions) -> (u32, u32) {
+    let page = opts
+        .after
+        .as_ref()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(1);
+    (page, opts.limit.unwrap_or(25))
+}
+
+fn lemmy_sort(sort: SortOption) -> &'static str {
+    match sort {
+        SortOption::Hot | SortOption::Best => "Hot",
+        SortOption::New => "New",
+        SortOption::Top => "TopDay",
+        SortOption::Rising => "Active",
+    }
+}
+
+impl FeedService for LemmyFeedService {
+    fn load_front_page(
+        &self,
+        sort: SortOption,
+        opts: ListingOptions,
+    ) -> Result<reddit::Listing<reddit::Post>> {
+        let (page, limit) = lemmy_page(&opts);
+        let listing = if self.client.has_credentials() {
+            lemmy::ListingType::Subscribed
+        } else {
+            lemmy::ListingType::Local
+        };
+        self.client
+            .posts(listing, None, lemmy_sort(sort), page, limit)
+            .context("fetch lemmy front page")
+    }
+
+    fn load_subreddit(
+        &self,
+        name: &str,
+        sort: SortOption,
+        opts: ListingOptions,
+    ) -> Result<reddit::Listing<reddit::Post>> {
+        let (page, limit) = lemmy_page(&opts);
+        let name = name
+            .trim()
+            .trim_start_matches("r/")
+            .trim_start_matches("c/");
+        let (listing, community, sort) = match name.to_ascii_lowercase().as_str() {
+            "all" => (lemmy::ListingType::All, None, lemmy_sort(sort)),
+            "popular" => (lemmy::ListingType::All, None, "TopDay"),
+            "local" => (lemmy::ListingType::Local, None, lemmy_sort(sort)),
+            _ => (lemmy::ListingType::All, Some(name), lemmy_sort(sort)),
+        };
+        self.client
+            .posts(listing, community, sort, page, limit)
+            .with_context(|| format!("fetch lemmy community {name}"))
+    }
+
+    fn load_user(
+        &self,
+        name: &str,
+        sort: SortOption,
+        opts: ListingOptions,
+    ) -> Result<reddit::Listing<reddit::Post>> {
+        let (page, limit) = lemmy_page(&opts);
+        self.client
+            .user_posts(name, lemmy_sort(sort), page, limit)
+            .context("fetch lemmy user posts")
+    }
+
+    fn search_posts(
+        &self,
+        query: &str,
+        sort: SortOption,
+        opts: ListingOptions,
+    ) -> Result<reddit::Listing<reddit::Post>> {
+        let (page, limit) = lemmy_page(&opts);
+        self.client
+            .search_posts(query, lemmy_sort(sort), page, limit)
+            .context("search lemmy")
+    }
+}
+
+pub struct LemmyCommentService {
+    client: Arc<lemmy::Client>,
+}
+
+impl LemmyCommentService {
+    pub fn new(client: Arc<lemmy::Client>) -> Self {
+        Self { client }
+    }
+}
+
+impl CommentService for LemmyCommentService {
+    fn load_comments(
+        &self,
+        _subreddit: &str,
+        article: &str,
+        sort: CommentSortOption,
+    ) -> Result<reddit::PostComments> {
+        let post_id: i64 = article.parse().context("parse lemmy post ID")?;
+        let sort = match sort {
+            CommentSortOption::Top => "Top",
+            CommentSortOption::New => "New",
+            CommentSortOption::Old => "Old",
+            CommentSortOption::Controversial => "Controversial",
+            CommentSortOption::Confidence | CommentSortOption::Qa => "Hot",
+        };
+        self.client
+            .comments(post_id, sort)
+            .context("fetch lemmy comments")
+    }
+}
+
+pub struct LemmyInteractionService {
+    client: Arc<lemmy::Client>,
+}
+
+impl LemmyInteractionService {
+    pub fn new(client: Arc<lemmy::Client>) -> Self {
+        Self { client }
+    }
+}
+
+impl InteractionService for LemmyInteractionService {
+    fn vote(&self, fullname: &str, dir: i32) -> Result<()> {
+        let score = dir.signum();
+        match lemmy::parse_fullname(fullname)? {
+            (lemmy::FullnameKind::Post, id) => self.client.vote_post(id, score),
+            (lemmy::FullnameKind::Comment, id) => self.client.vote_comment(id, score),
+        }
+    }
+
+    fn save(&self, fullname: &str, _category: Option<&str>) -> Result<()> {
+        match lemmy::parse_fullname(fullname)? {
+            (lemmy::FullnameKind::Post, id) => self.client.save_post(id, true),
+            (lemmy::FullnameKind::Comment, id) => self.client.save_comment(id, true),
+        }
+    }
+
+    fn unsave(&self, fullname: &str) -> Result<()> {
+        match lemmy::parse_fullname(fullname)? {
+            (lemmy::FullnameKind::Post, id) => self.client.save_post(id, false),
+            (lemmy::FullnameKind::Comment, id) => self.client.save_comment(id, false),
+        }
+    }
+
+    fn hide(&self, fullname: &str) -> Result<()> {
+        match lemmy::parse_fullname(fullname)? {
+            (lemmy::FullnameKind::Post, id) => self.client.hide_post(id, true),
+            (lemmy::FullnameKind::Comment, _) => anyhow::bail!("Lemmy cannot hide comments"),
+        }
+    }
+
+    fn unhide(&self, fullname: &str) -> Result<()> {
+        match lemmy::parse_fullname(fullname)? {
+            (lemmy::FullnameKind::Post, id) => self.client.hide_post(id, false),
+            (lemmy::FullnameKind::Comment, _) => anyhow::bail!("Lemmy cannot hide comments"),
+        }
+    }
+
+    fn subscribe(&self, subreddit: &str) -> Result<()> {
+        let community = self
+            .client
+            .community(subreddit)
+            .context("look up lemmy community")?;
+        self.client.follow_community(community.community.id, true)
+    }
+
+    fn is_subscribed(&self, subreddit: &str) -> Result<bool> {
+        Ok(self.client.community(subreddit)?.is_subscribed())
+    }
+
+    fn reply(&self, parent: &str, text: &str) -> Result<reddit::Comment> {
+        match lemmy::parse_fullname(parent)? {
+            (lemmy::FullnameKind::Post, id) => self.client.reply(id, None, text),
+            (lemmy::FullnameKind::Comment, id) => {
+                let post_id = self
+                    .client
+                    .comment_post_id(id)
+                    .context("look up lemmy comment")?;
+                self.client.reply(post_id, Some(id), text)
+            }
+        }
+    }
+}
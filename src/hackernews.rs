@@ -1,10 +1,9 @@
-use std::time::Duration;
-
 use anyhow::{anyhow, bail, Result};
 use reqwest::blocking::Client as HttpClient;
 use reqwest::header::USER_AGENT;
 use serde::{Deserialize, Serialize};
 
+use crate::net;
 use crate::reddit::{Comment, Listing, Thing};
 
 pub const HN_API_BASE: &str = "https://hacker-news.firebaseio.com/v0";
@@ -79,9 +78,7 @@ impl Client {
         
         let http = match config.http_client {
             Some(client) => client,
-            None => HttpClient::builder()
-                .timeout(Duration::from_secs(20))
-                .build()?,
+            None => net::client_builder().build()?,
         };
 
         Ok(Client {
@@ -94,11 +91,9 @@ impl Client {
     pub fn story_listing(&self, story_type: StoryType, start: usize, limit: usize) -> Result<Listing<Story>> {
         // First get the list of story IDs
         let url = format!("{}/{}.json", self.base_url, story_type.as_str());
-        let ids: Vec<i64> = self.http
-            .get(&url)
-            .header(USER_AGENT, &self.user_agent)
-            .send()?
-            .json()?;
+        tracing::debug!(url = %url, start, limit, "hackernews story listing");
+        let request = self.http.get(&url).header(USER_AGENT, &self.user_agent);
+        let ids: Vec<i64> = net::send(request)?.json()?;
 
         // Fetch stories in the requested range
         let end = std::cmp::min(start + limit, ids.len());
@@ -152,6 +147,17 @@ impl Client {
         bail!("Search is not yet implemented for Hacker News");
     }
 
+    pub fn story(&self, story_id: i64) -> Result<Story> {
+        self.get_item(story_id)?
+            .into_story()
+            .ok_or_else(|| anyhow!("Item {} is not a story", story_id))
+    }
+
+    /// Total comment count of a story, from its item alone.
+    pub fn descendants(&self, story_id: i64) -> Result<Option<i64>> {
+        Ok(self.get_item(story_id)?.descendants)
+    }
+
     pub fn comments(&self, story_id: i64) -> Result<StoryComments> {
         let item = self.get_item(story_id)?;
         
@@ -211,6 +217,11 @@ impl Client {
             score_hidden: false,
             depth,
             created_utc: item.time.unwrap_or(0) as f64,
+            controversiality: 0,
+            edited: None,
+            stickied: false,
+            gilded: 0,
+            media_metadata: None,
             replies: if replies.is_empty() {
                 None
             } else {
@@ -225,21 +236,15 @@ impl Client {
 
     fn get_item(&self, id: i64) -> Result<Item> {
         let url = format!("{}/item/{}.json", self.base_url, id);
-        let item: Item = self.http
-            .get(&url)
-            .header(USER_AGENT, &self.user_agent)
-            .send()?
-            .json()?;
+        let request = self.http.get(&url).header(USER_AGENT, &self.user_agent);
+        let item: Item = net::send(request)?.json()?;
         Ok(item)
     }
 
     fn get_user(&self, username: &str) -> Result<User> {
         let url = format!("{}/user/{}.json", self.base_url, username);
-        let user: User = self.http
-            .get(&url)
-            .header(USER_AGENT, &self.user_agent)
-            .send()?
-            .json()?;
+        let request = self.http.get(&url).header(USER_AGENT, &self.user_agent);
+        let user: User = net::send(request)?.json()?;
         Ok(user)
     }
 }
@@ -309,6 +314,8 @@ impl Item {
             created_utc: self.time.unwrap_or(0) as f64,
             thumbnail: String::new(),
             stickied: false,
+            locked: false,
+            archived: false,
             over_18: false,
             spoiler: false,
             post_hint: String::new(),
@@ -319,6 +326,11 @@ impl Item {
             preview: Default::default(),
             gallery_data: None,
             media_metadata: None,
+            suggested_sort: None,
+            domain: String::new(),
+            link_flair_text: None,
+            total_awards_received: 0,
+            upvote_ratio: None,
         })
     }
 }
@@ -0,0 +1,185 @@
+use std::fs::File;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use crossbeam_channel::Sender;
+
+#[cfg(unix)]
+use std::io::{BufRead, BufReader, Read, Write};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+#[cfg(unix)]
+use std::time::Duration;
+
+#[cfg(unix)]
+use anyhow::{bail, Context};
+
+#[cfg(unix)]
+use crate::profile;
+
+#[cfg(unix)]
+const HANDOFF_TIMEOUT: Duration = Duration::from_secs(2);
+#[cfg(unix)]
+const HANDOFF_ACK: &str = "ok";
+/// Longest target line accepted from another launch.
+#[cfg(unix)]
+const MAX_TARGET_LEN: u64 = 4096;
+
+pub enum Acquired {
+    /// No other instance is running. Keep the guard for the whole session;
+    /// targets handed off by later launches arrive on the channel.
+    Primary(Guard),
+    /// Another instance holds the lock; use [`hand_off`] to reach it.
+    Secondary,
+}
+
+/// Holds the instance lock and removes the handoff socket when dropped.
+pub struct Guard {
+    _lock: Option<File>,
+    socket: Option<PathBuf>,
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        if let Some(path) = &self.socket {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn instance_dir() -> Option<PathBuf> {
+    dirs::runtime_dir()
+        .or_else(dirs::data_local_dir)
+        .map(|dir| profile::scope(dir.join("hn-tui")))
+}
+
+/// Takes the single-instance lock and starts listening for handoffs, which
+/// are forwarded to `targets`. Platforms without Unix sockets always get
+/// [`Acquired::Primary`] and never receive handoffs.
+#[cfg(unix)]
+pub fn acquire(targets: Sender<String>) -> Result<Acquired> {
+    use std::os::unix::io::AsRawFd;
+
+    let dir = instance_dir().context("instance: resolve runtime directory")?;
+    std::fs::create_dir_all(&dir).with_context(|| format!("instance: create {}", dir.display()))?;
+    let lock_path = dir.join("instance.lock");
+    let lock = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .with_context(|| format!("instance: open {}", lock_path.display()))?;
+    if unsafe { libc::flock(lock.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+        let err = std::io::Error::last_os_error();
+        if err.kind() == std::io::ErrorKind::WouldBlock {
+            return Ok(Acquired::Secondary);
+        }
+        return Err(err).context("instance: lock");
+    }
+
+    // Holding the lock means any socket left behind belongs to a dead process.
+    let socket = dir.join("instance.sock");
+    let _ = std::fs::remove_file(&socket);
+    let listener = UnixListener::bind(&socket)
+        .with_context(|| format!("instance: listen on {}", socket.display()))?;
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else {
+                continue;
+            };
+            match receive_target(stream) {
+                Ok(Some(target)) => {
+                    if targets.send(target).is_err() {
+                        break;
+                    }
+                }
+                Ok(None) => {}
+                Err(err) => tracing::debug!("instance: handoff failed: {err:#}"),
+            }
+        }
+    });
+
+    Ok(Acquired::Primary(Guard {
+        _lock: Some(lock),
+        socket: Some(socket),
+    }))
+}
+
+#[cfg(not(unix))]
+pub fn acquire(_targets: Sender<String>) -> Result<Acquired> {
+    tracing::info!("single-instance mode is not supported on this platform");
+    Ok(Acquired::Primary(Guard {
+        _lock: None,
+        socket: None,
+    }))
+}
+
+#[cfg(unix)]
+fn receive_target(mut stream: UnixStream) -> Result<Option<String>> {
+    stream
+        .set_read_timeout(Some(HANDOFF_TIMEOUT))
+        .context("instance: set read timeout")?;
+    let mut line = String::new();
+    BufReader::new((&stream).take(MAX_TARGET_LEN))
+        .read_line(&mut line)
+        .context("instance: read target")?;
+    if !line.ends_with('\n') && line.len() as u64 >= MAX_TARGET_LEN {
+        bail!("instance: target longer than {MAX_TARGET_LEN} bytes");
+    }
+    stream
+        .write_all(format!("{HANDOFF_ACK}\n").as_bytes())
+        .context("instance: acknowledge target")?;
+    let target = line.trim();
+    Ok((!target.is_empty()).then(|| target.to_string()))
+}
+
+/// Sends `target` to the running instance, which opens it there.
+#[cfg(unix)]
+pub fn hand_off(target: &str) -> Result<()> {
+    let dir = instance_dir().context("instance: resolve runtime directory")?;
+    let socket = dir.join("instance.sock");
+    let mut stream = UnixStream::connect(&socket)
+        .with_context(|| format!("instance: connect to {}", socket.display()))?;
+    stream
+        .set_read_timeout(Some(HANDOFF_TIMEOUT))
+        .context("instance: set read timeout")?;
+    stream
+        .write_all(format!("{}\n", target.trim()).as_bytes())
+        .context("instance: send target")?;
+    let mut reply = String::new();
+    BufReader::new(&stream)
+        .read_line(&mut reply)
+        .context("instance: read acknowledgement")?;
+    if reply.trim() != HANDOFF_ACK {
+        bail!("instance: running instance did not accept the target");
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn hand_off(_target: &str) -> Result<()> {
+    anyhow::bail!("instance: handoff is not supported on this platform")
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oversized_targets_are_rejected() {
+        let (mut sender, receiver) = UnixStream::pair().unwrap();
+        let target = "a".repeat(MAX_TARGET_LEN as usize * 2);
+        let _writer = std::thread::spawn(move || {
+            let _ = sender.write_all(format!("{target}\n").as_bytes());
+        });
+        assert!(receive_target(receiver).is_err());
+    }
+
+    #[test]
+    fn targets_are_read_up_to_the_newline() {
+        let (mut sender, receiver) = UnixStream::pair().unwrap();
+        sender.write_all(b"r/rust\n").unwrap();
+        assert_eq!(receive_target(receiver).unwrap().as_deref(), Some("r/rust"));
+    }
+}
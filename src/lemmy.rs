@@ -0,0 +1,851 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, NaiveDateTime};
+use reqwest::blocking::{Client as HttpClient, RequestBuilder, Response};
+use reqwest::header::{AUTHORIZATION, USER_AGENT};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::net;
+use crate::reddit::{self, Comment, Listing, Post, Thing};
+
+pub const DEFAULT_INSTANCE: &str = "https://lemmy.ml";
+const API_PREFIX: &str = "/api/v3";
+const COMMENT_MAX_DEPTH: u32 = 8;
+const COMMENT_LIMIT: u32 = 300;
+
+/// Lemmy post and comment fullnames, mirroring Reddit's `t3_`/`t1_`.
+pub const POST_PREFIX: &str = "lp_";
+pub const COMMENT_PREFIX: &str = "lc_";
+
+#[derive(Debug, Clone, Default)]
+pub struct ClientConfig {
+    pub instance_url: String,
+    pub user_agent: String,
+    pub username: String,
+    pub password: String,
+    pub http_client: Option<HttpClient>,
+}
+
+pub struct Client {
+    http: HttpClient,
+    user_agent: String,
+    instance_url: String,
+    username: String,
+    password: String,
+    jwt: Mutex<Option<String>>,
+}
+
+/// Listing scope for `/post/list` and `/community/list`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListingType {
+    All,
+    Local,
+    Subscribed,
+}
+
+impl ListingType {
+    fn as_str(self) -> &'static str {
+        match self {
+            ListingType::All => "All",
+            ListingType::Local => "Local",
+            ListingType::Subscribed => "Subscribed",
+        }
+    }
+}
+
+impl Client {
+    pub fn new(config: ClientConfig) -> Result<Self> {
+        if config.user_agent.trim().is_empty() {
+            bail!("lemmy client user agent required");
+        }
+        let instance_url = normalize_instance_url(&config.instance_url)?;
+
+        let http = match config.http_client {
+            Some(client) => client,
+            None => net::client_builder().build()?,
+        };
+
+        Ok(Client {
+            http,
+            user_agent: config.user_agent,
+            instance_url,
+            username: config.username.trim().to_string(),
+            password: config.password,
+            jwt: Mutex::new(None),
+        })
+    }
+
+    pub fn instance_url(&self) -> &str {
+        &self.instance_url
+    }
+
+    pub fn has_credentials(&self) -> bool {
+        !self.username.is_empty() && !self.password.is_empty()
+    }
+
+    pub fn posts(
+        &self,
+        listing: ListingType,
+        community: Option<&str>,
+        sort: &str,
+        page: u32,
+        limit: u32,
+    ) -> Result<Listing<Post>> {
+        let mut query = vec![
+            ("sort", sort.to_string()),
+            ("page", page.to_string()),
+            ("limit", limit.to_string()),
+        ];
+        match community {
+            Some(name) => query.push(("community_name", name.to_string())),
+            None => query.push(("type_", listing.as_str().to_string())),
+        }
+        let response: PostListResponse = self.get("/post/list", &query)?;
+        Ok(self.post_listing(response.posts, page, limit))
+    }
+
+    pub fn user_posts(
+        &self,
+        username: &str,
+        sort: &str,
+        page: u32,
+        limit: u32,
+    ) -> Result<Listing<Post>> {
+        let query = [
+            ("username", username.to_string()),
+            ("sort", sort.to_string()),
+            ("page", page.to_string()),
+            ("limit", limit.to_string()),
+        ];
+        let response: PostListResponse = self.get("/user", &query)?;
+        Ok(self.post_listing(response.posts, page, limit))
+    }
+
+    pub fn search_posts(
+        &self,
+        query: &str,
+        sort: &str,
+        page: u32,
+        limit: u32,
+    ) -> Result<Listing<Post>> {
+        let params = [
+            ("q", query.to_string()),
+            ("type_", "Posts".to_string()),
+            ("sort", sort.to_string()),
+            ("page", page.to_string()),
+            ("limit", limit.to_string()),
+        ];
+        let response: PostListResponse = self.get("/search", &params)?;
+        Ok(self.post_listing(response.posts, page, limit))
+    }
+
+    pub fn comments(&self, post_id: i64, sort: &str) -> Result<reddit::PostComments> {
+        let post: PostResponse = self.get("/post", &[("id", post_id.to_string())])?;
+        let query = [
+            ("post_id", post_id.to_string()),
+            ("sort", sort.to_string()),
+            ("type_", "All".to_string()),
+            ("max_depth", COMMENT_MAX_DEPTH.to_string()),
+            ("limit", COMMENT_LIMIT.to_string()),
+        ];
+        let response: CommentListResponse = self.get("/comment/list", &query)?;
+        Ok(reddit::PostComments {
+            post: post.post_view.into_post(&self.instance_url),
+            comments: Listing {
+                after: None,
+                before: None,
+                children: build_comment_tree(response.comments),
+            },
+        })
+    }
+
+    pub fn communities(&self, listing: ListingType) -> Result<Vec<reddit::Subreddit>> {
+        let query = [
+            ("type_", listing.as_str().to_string()),
+            ("sort", "TopAll".to_string()),
+            ("limit", "50".to_string()),
+        ];
+        let response: CommunityListResponse = self.get("/community/list", &query)?;
+        Ok(response
+            .communities
+            .into_iter()
+            .map(|view| reddit::Subreddit {
+                id: view.community.id.to_string(),
+                name: view.community.qualified_name(),
+                title: view.community.title.clone(),
+                subscribers: view.counts.subscribers,
+                over_18: view.community.nsfw,
+            })
+            .collect())
+    }
+
+    pub fn community(&self, name: &str) -> Result<CommunityView> {
+        let response: CommunityResponse = self.get("/community", &[("name", name.to_string())])?;
+        Ok(response.community_view)
+    }
+
+    pub fn follow_community(&self, community_id: i64, follow: bool) -> Result<()> {
+        self.send_json(
+            self.http.post(self.endpoint("/community/follow")),
+            json!({ "community_id": community_id, "follow": follow }),
+        )
+    }
+
+    pub fn vote_post(&self, post_id: i64, score: i32) -> Result<()> {
+        self.send_json(
+            self.http.post(self.endpoint("/post/like")),
+            json!({ "post_id": post_id, "score": score }),
+        )
+    }
+
+    pub fn vote_comment(&self, comment_id: i64, score: i32) -> Result<()> {
+        self.send_json(
+            self.http.post(self.endpoint("/comment/like")),
+            json!({ "comment_id": comment_id, "score": score }),
+        )
+    }
+
+    pub fn save_post(&self, post_id: i64, save: bool) -> Result<()> {
+        self.send_json(
+            self.http.put(self.endpoint("/post/save")),
+            json!({ "post_id": post_id, "save": save }),
+        )
+    }
+
+    pub fn save_comment(&self, comment_id: i64, save: bool) -> Result<()> {
+        self.send_json(
+            self.http.put(self.endpoint("/comment/save")),
+            json!({ "comment_id": comment_id, "save": save }),
+        )
+    }
+
+    pub fn hide_post(&self, post_id: i64, hide: bool) -> Result<()> {
+        self.send_json(
+            self.http.post(self.endpoint("/post/hide")),
+            json!({ "post_ids": [post_id], "hide": hide }),
+        )
+    }
+
+    pub fn reply(&self, post_id: i64, parent_id: Option<i64>, content: &str) -> Result<Comment> {
+        let mut body = json!({ "content": content, "post_id": post_id });
+        if let Some(parent) = parent_id {
+            body["parent_id"] = json!(parent);
+        }
+        let request = self.http.post(self.endpoint("/comment")).json(&body);
+        let response: CommentResponse = parse_response(self.send(request, true)?)?;
+        let view = response.comment_view;
+        let depth = view.comment.depth();
+        Ok(view.into_comment(depth))
+    }
+
+    /// Looks up the post a comment belongs to, which Lemmy needs for replies.
+    pub fn comment_post_id(&self, comment_id: i64) -> Result<i64> {
+        let response: CommentResponse = self.get("/comment", &[("id", comment_id.to_string())])?;
+        Ok(response.comment_view.comment.post_id)
+    }
+
+    fn post_listing(&self, posts: Vec<PostView>, page: u32, limit: u32) -> Listing<Post> {
+        let full_page = posts.len() as u32 >= limit && limit > 0;
+        Listing {
+            after: full_page.then(|| (page + 1).to_string()),
+            before: (page > 1).then(|| (page - 1).to_string()),
+            children: posts
+                .into_iter()
+                .map(|view| Thing {
+                    kind: "post".to_string(),
+                    data: view.into_post(&self.instance_url),
+                })
+                .collect(),
+        }
+    }
+
+    fn endpoint(&self, path: &str) -> String {
+        format!("{}{}{}", self.instance_url, API_PREFIX, path)
+    }
+
+    fn get<T: DeserializeOwned>(&self, path: &str, query: &[(&str, String)]) -> Result<T> {
+        tracing::debug!(path, "lemmy api request");
+        let request = self.http.get(self.endpoint(path)).query(query);
+        parse_response(self.send(request, false)?)
+    }
+
+    fn send_json(&self, request: RequestBuilder, body: serde_json::Value) -> Result<()> {
+        let _: serde_json::Value = parse_response(self.send(request.json(&body), true)?)?;
+        Ok(())
+    }
+
+    /// Sends `request` with the session token. Lemmy answers 401 once the
+    /// token expires or is revoked; the token is then dropped and the
+    /// request retried once after a fresh login.
+    fn send(&self, request: RequestBuilder, required: bool) -> Result<Response> {
+        let retry = request.try_clone();
+        let response = net::send(self.authorize(request, required)?)?;
+        match retry {
+            Some(retry)
+                if response.status() == StatusCode::UNAUTHORIZED && self.has_credentials() =>
+            {
+                tracing::debug!("lemmy session rejected; logging in again");
+                self.forget_token()?;
+                Ok(net::send(self.authorize(retry, required)?)?)
+            }
+            _ => Ok(response),
+        }
+    }
+
+    /// Attaches the session token, logging in on first use. Reads stay
+    /// anonymous when no credentials are configured.
+    fn authorize(&self, request: RequestBuilder, required: bool) -> Result<RequestBuilder> {
+        let request = request.header(USER_AGENT, &self.user_agent);
+        if !self.has_credentials() {
+            if required {
+                bail!("lemmy: set lemmy.username and lemmy.password to vote, save, or reply");
+            }
+            return Ok(request);
+        }
+        let token = self.token()?;
+        Ok(request.header(AUTHORIZATION, format!("Bearer {token}")))
+    }
+
+    fn token(&self) -> Result<String> {
+        let mut guard = self
+            .jwt
+            .lock()
+            .map_err(|_| anyhow!("lemmy: session lock poisoned"))?;
+        if let Some(token) = guard.as_ref() {
+            return Ok(token.clone());
+        }
+        let request = self
+            .http
+            .post(self.endpoint("/user/login"))
+            .header(USER_AGENT, &self.user_agent)
+            .json(&json!({
+                "username_or_email": self.username,
+                "password": self.password,
+            }));
+        let response: LoginResponse =
+            parse_response(net::send(request)?).context("lemmy: log in")?;
+        let token = response
+            .jwt
+            .filter(|token| !token.is_empty())
+            .ok_or_else(|| {
+                anyhow!("lemmy: login returned no token (email verification or approval pending?)")
+            })?;
+        *guard = Some(token.clone());
+        Ok(token)
+    }
+
+    fn forget_token(&self) -> Result<()> {
+        self.jwt
+            .lock()
+            .map_err(|_| anyhow!("lemmy: session lock poisoned"))?
+            .take();
+        Ok(())
+    }
+}
+
+fn parse_response<T: DeserializeOwned>(response: reqwest::blocking::Response) -> Result<T> {
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().unwrap_or_default();
+        let message = serde_json::from_str::<ErrorResponse>(&body)
+            .map(|err| err.error)
+            .unwrap_or(body);
+        bail!("lemmy: request failed with {status}: {}", message.trim());
+    }
+    response.json().context("lemmy: decode response")
+}
+
+pub fn normalize_instance_url(raw: &str) -> Result<String> {
+    let trimmed = raw.trim().trim_end_matches('/');
+    let candidate = if trimmed.is_empty() {
+        DEFAULT_INSTANCE.to_string()
+    } else if trimmed.starts_with("http://") || trimmed.starts_with("https://") {
+        trimmed.to_string()
+    } else {
+        format!("https://{trimmed}")
+    };
+    let parsed = url::Url::parse(&candidate)
+        .with_context(|| format!("lemmy: invalid instance url {raw}"))?;
+    if parsed.host_str().is_none() {
+        bail!("lemmy: instance url {raw} has no host");
+    }
+    Ok(candidate)
+}
+
+/// Parses a `lp_`/`lc_` fullname into its kind and numeric id.
+pub fn parse_fullname(fullname: &str) -> Result<(FullnameKind, i64)> {
+    let (kind, id) = if let Some(id) = fullname.strip_prefix(POST_PREFIX) {
+        (FullnameKind::Post, id)
+    } else if let Some(id) = fullname.strip_prefix(COMMENT_PREFIX) {
+        (FullnameKind::Comment, id)
+    } else {
+        bail!("lemmy: unsupported item {fullname}");
+    };
+    let id = id
+        .parse()
+        .with_context(|| format!("lemmy: invalid item id {fullname}"))?;
+    Ok((kind, id))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FullnameKind {
+    Post,
+    Comment,
+}
+
+/// Rebuilds the reply tree from Lemmy's flat comment list using each
+/// comment's materialized `path` ("0.<ancestor ids>.<id>").
+fn build_comment_tree(views: Vec<CommentView>) -> Vec<Thing<Comment>> {
+    let mut children: HashMap<i64, Vec<CommentView>> = HashMap::new();
+    let mut roots = Vec::new();
+    for view in views {
+        match view.comment.parent_id() {
+            Some(parent) => children.entry(parent).or_default().push(view),
+            None => roots.push(view),
+        }
+    }
+    // Replies whose parent was cut off by max_depth or the limit become roots.
+    let known: std::collections::HashSet<i64> = roots
+        .iter()
+        .map(|view| view.comment.id)
+        .chain(children.values().flatten().map(|view| view.comment.id))
+        .collect();
+    let orphaned: Vec<i64> = children
+        .keys()
+        .filter(|parent| !known.contains(parent))
+        .copied()
+        .collect();
+    for parent in orphaned {
+        if let Some(views) = children.remove(&parent) {
+            roots.extend(views);
+        }
+    }
+
+    fn attach(
+        view: CommentView,
+        depth: i64,
+        children: &mut HashMap<i64, Vec<CommentView>>,
+    ) -> Thing<Comment> {
+        let replies: Vec<Thing<Comment>> = children
+            .remove(&view.comment.id)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|reply| attach(reply, depth + 1, children))
+            .collect();
+        let mut comment = view.into_comment(depth);
+        if !replies.is_empty() {
+            comment.replies = Some(Box::new(Listing {
+                after: None,
+                before: None,
+                children: replies,
+            }));
+        }
+        Thing {
+            kind: "comment".to_string(),
+            data: comment,
+        }
+    }
+
+    roots
+        .into_iter()
+        .map(|view| attach(view, 0, &mut children))
+        .collect()
+}
+
+fn parse_timestamp(raw: &str) -> f64 {
+    if let Ok(parsed) = DateTime::parse_from_rfc3339(raw) {
+        return parsed.timestamp() as f64;
+    }
+    // Instances before 0.19 omit the offset; those timestamps are UTC.
+    NaiveDateTime::parse_from_str(raw, "%Y-%m-%dT%H:%M:%S%.f")
+        .map(|parsed| parsed.and_utc().timestamp() as f64)
+        .unwrap_or(0.0)
+}
+
+fn vote_state(my_vote: Option<i32>) -> Option<bool> {
+    match my_vote {
+        Some(vote) if vote > 0 => Some(true),
+        Some(vote) if vote < 0 => Some(false),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct LoginResponse {
+    #[serde(default)]
+    jwt: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PostListResponse {
+    #[serde(default)]
+    posts: Vec<PostView>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PostResponse {
+    post_view: PostView,
+}
+
+#[derive(Debug, Deserialize)]
+struct CommentListResponse {
+    #[serde(default)]
+    comments: Vec<CommentView>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CommentResponse {
+    comment_view: CommentView,
+}
+
+#[derive(Debug, Deserialize)]
+struct CommunityListResponse {
+    #[serde(default)]
+    communities: Vec<CommunityView>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CommunityResponse {
+    community_view: CommunityView,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostView {
+    pub post: LemmyPost,
+    pub creator: Person,
+    pub community: Community,
+    #[serde(default)]
+    pub counts: PostCounts,
+    #[serde(default)]
+    pub my_vote: Option<i32>,
+}
+
+impl PostView {
+    pub fn into_post(self, instance_url: &str) -> Post {
+        let post = self.post;
+        Post {
+            id: post.id.to_string(),
+            name: format!("{POST_PREFIX}{}", post.id),
+            title: post.name,
+            subreddit: self.community.qualified_name(),
+            author: self.creator.name,
+            selftext: post.body.unwrap_or_default(),
+            url: post.url.unwrap_or_default(),
+            permalink: format!("{instance_url}/post/{}", post.id),
+            score: self.counts.score,
+            likes: vote_state(self.my_vote),
+            num_comments: self.counts.comments,
+            created_utc: parse_timestamp(&post.published),
+            thumbnail: post.thumbnail_url.unwrap_or_default(),
+            stickied: post.featured_community || post.featured_local,
+            locked: post.locked,
+            archived: false,
+            over_18: post.nsfw || self.community.nsfw,
+            spoiler: false,
+            post_hint: String::new(),
+            is_video: false,
+            media: None,
+            secure_media: None,
+            crosspost_parent_list: vec![],
+            preview: Default::default(),
+            gallery_data: None,
+            media_metadata: None,
+            suggested_sort: None,
+            domain: String::new(),
+            link_flair_text: None,
+            total_awards_received: 0,
+            upvote_ratio: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LemmyPost {
+    pub id: i64,
+    pub name: String,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub body: Option<String>,
+    #[serde(default)]
+    pub published: String,
+    #[serde(default)]
+    pub nsfw: bool,
+    #[serde(default)]
+    pub featured_community: bool,
+    #[serde(default)]
+    pub featured_local: bool,
+    #[serde(default)]
+    pub locked: bool,
+    #[serde(default)]
+    pub thumbnail_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PostCounts {
+    #[serde(default)]
+    pub score: i64,
+    #[serde(default)]
+    pub comments: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Person {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Community {
+    pub id: i64,
+    pub name: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub nsfw: bool,
+    #[serde(default)]
+    pub local: bool,
+    #[serde(default)]
+    pub actor_id: String,
+}
+
+impl Community {
+    /// `name` for communities on the configured instance, `name@host` for
+    /// federated ones, matching what `community_name` accepts.
+    pub fn qualified_name(&self) -> String {
+        if self.local {
+            return self.name.clone();
+        }
+        match url::Url::parse(&self.actor_id)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+        {
+            Some(host) => format!("{}@{}", self.name, host),
+            None => self.name.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommunityView {
+    pub community: Community,
+    #[serde(default)]
+    pub counts: CommunityCounts,
+    #[serde(default)]
+    pub subscribed: String,
+}
+
+impl CommunityView {
+    pub fn is_subscribed(&self) -> bool {
+        !self.subscribed.is_empty() && self.subscribed != "NotSubscribed"
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CommunityCounts {
+    #[serde(default)]
+    pub subscribers: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommentView {
+    pub comment: LemmyComment,
+    pub creator: Person,
+    #[serde(default)]
+    pub counts: PostCounts,
+    #[serde(default)]
+    pub my_vote: Option<i32>,
+}
+
+impl CommentView {
+    fn into_comment(self, depth: i64) -> Comment {
+        let comment = self.comment;
+        let body = if comment.deleted {
+            "[deleted]".to_string()
+        } else if comment.removed {
+            "[removed]".to_string()
+        } else {
+            comment.content
+        };
+        Comment {
+            id: comment.id.to_string(),
+            name: format!("{COMMENT_PREFIX}{}", comment.id),
+            body,
+            author: self.creator.name,
+            score: self.counts.score,
+            likes: vote_state(self.my_vote),
+            score_hidden: false,
+            depth,
+            created_utc: parse_timestamp(&comment.published),
+            controversiality: 0,
+            edited: comment
+                .updated
+                .as_deref()
+                .map(parse_timestamp)
+                .filter(|at| *at > 0.0),
+            stickied: comment.distinguished,
+            gilded: 0,
+            media_metadata: None,
+            replies: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LemmyComment {
+    pub id: i64,
+    pub post_id: i64,
+    #[serde(default)]
+    pub content: String,
+    #[serde(default)]
+    pub path: String,
+    #[serde(default)]
+    pub published: String,
+    #[serde(default)]
+    pub deleted: bool,
+    #[serde(default)]
+    pub removed: bool,
+    #[serde(default)]
+    pub updated: Option<String>,
+    #[serde(default)]
+    pub distinguished: bool,
+}
+
+impl LemmyComment {
+    fn parent_id(&self) -> Option<i64> {
+        let segments: Vec<&str> = self.path.split('.').collect();
+        if segments.len() < 3 {
+            return None;
+        }
+        segments[segments.len() - 2].parse().ok()
+    }
+
+    fn depth(&self) -> i64 {
+        (self.path.split('.').count() as i64 - 2).max(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn comment_view(id: i64, path: &str) -> CommentView {
+        CommentView {
+            comment: LemmyComment {
+                id,
+                post_id: 7,
+                content: format!("comment {id}"),
+                path: path.to_string(),
+                published: "2024-01-02T03:04:05.123456Z".to_string(),
+                deleted: false,
+                removed: false,
+                updated: None,
+                distinguished: false,
+            },
+            creator: Person {
+                name: "alice".into(),
+            },
+            counts: PostCounts {
+                score: 3,
+                comments: 0,
+            },
+            my_vote: Some(1),
+        }
+    }
+
+    #[test]
+    fn comment_paths_rebuild_reply_tree() {
+        let tree = build_comment_tree(vec![
+            comment_view(1, "0.1"),
+            comment_view(2, "0.1.2"),
+            comment_view(3, "0.1.2.3"),
+            comment_view(4, "0.4"),
+            comment_view(9, "0.8.9"),
+        ]);
+        let names: Vec<&str> = tree.iter().map(|thing| thing.data.name.as_str()).collect();
+        assert_eq!(names, vec!["lc_1", "lc_4", "lc_9"]);
+
+        let first = &tree[0].data;
+        assert_eq!(first.likes, Some(true));
+        assert_eq!(first.created_utc, 1_704_164_645.0);
+        let reply = &first.replies.as_ref().unwrap().children[0].data;
+        assert_eq!(reply.depth, 1);
+        let nested = &reply.replies.as_ref().unwrap().children[0].data;
+        assert_eq!((nested.id.as_str(), nested.depth), ("3", 2));
+    }
+
+    #[test]
+    fn fullnames_and_instance_urls_parse() {
+        assert_eq!(parse_fullname("lp_42").unwrap(), (FullnameKind::Post, 42));
+        assert_eq!(parse_fullname("lc_7").unwrap(), (FullnameKind::Comment, 7));
+        assert!(parse_fullname("t3_abc").is_err());
+        assert_eq!(
+            normalize_instance_url("lemmy.world/").unwrap(),
+            "https://lemmy.world"
+        );
+        assert_eq!(normalize_instance_url("").unwrap(), DEFAULT_INSTANCE);
+        assert_eq!(
+            parse_timestamp("2024-01-02T03:04:05.123456"),
+            1_704_164_645.0
+        );
+
+        let community = Community {
+            id: 1,
+            name: "rust".into(),
+            title: String::new(),
+            nsfw: false,
+            local: false,
+            actor_id: "https://programming.dev/c/rust".into(),
+        };
+        assert_eq!(community.qualified_name(), "rust@programming.dev");
+    }
+
+    #[test]
+    fn rejected_sessions_log_in_again() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let addr = server.server_addr().to_ip().unwrap();
+        let responder = thread::spawn(move || {
+            let mut logins = 0;
+            for _ in 0..4 {
+                let request = server.recv().unwrap();
+                let (status, body) = if request.url().ends_with("/user/login") {
+                    logins += 1;
+                    (200, format!("{{\"jwt\":\"token{logins}\"}}"))
+                } else {
+                    let current = request.headers().iter().any(|header| {
+                        header.field.equiv("Authorization") && header.value == "Bearer token2"
+                    });
+                    if current {
+                        (200, "{}".to_string())
+                    } else {
+                        (401, "{\"error\":\"not_logged_in\"}".to_string())
+                    }
+                };
+                let response = tiny_http::Response::from_string(body).with_status_code(status);
+                request.respond(response).unwrap();
+            }
+        });
+
+        let client = Client::new(ClientConfig {
+            instance_url: format!("http://{addr}"),
+            user_agent: "hn-tui-test".into(),
+            username: "alice".into(),
+            password: "hunter2".into(),
+            http_client: Some(HttpClient::new()),
+        })
+        .unwrap();
+        let _: serde_json::Value = client.get("/site", &[]).unwrap();
+        responder.join().unwrap();
+        assert_eq!(client.token().unwrap(), "token2");
+    }
+}
@@ -2,19 +2,35 @@
 
 pub mod app;
 pub mod auth;
+pub mod backup;
+pub mod browser;
 pub mod config;
 pub mod data;
 pub mod hackernews;
+pub mod instance;
+pub mod lemmy;
+pub mod logging;
 pub mod markdown;
 pub mod media;
+pub mod net;
+pub mod notifications;
+pub mod plugins;
+pub mod profile;
+pub mod reader;
 pub mod reddit;
 pub mod release_notes;
+pub mod rss;
+pub mod scripting;
+pub mod secrets;
 pub mod session;
+pub mod signals;
 pub mod storage;
+pub mod theme;
+pub mod tmux;
 pub mod ui;
 pub mod update;
 pub mod video;
 
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
-pub use app::run;
+pub use app::{run, run_with, RunOptions};
@@ -0,0 +1,50 @@
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::EnvFilter;
+
+pub const LOG_FILTER_ENV: &str = "HN_TUI_LOG";
+const MAX_LOG_FILES: usize = 7;
+const DEFAULT_FILTER: &str = "hn_tui=warn";
+const VERBOSE_FILTER: &str = "hn_tui=debug";
+
+/// Installs the global `tracing` subscriber, writing to a daily-rotated file in
+/// [`log_dir`]. `HN_TUI_LOG` takes an `EnvFilter` directive and wins over
+/// `--verbose`. The returned guard flushes the writer when dropped, so keep it
+/// alive for the whole session.
+pub fn init(verbose: bool) -> Result<WorkerGuard> {
+    let dir = log_dir().context("logging: resolve data directory")?;
+    fs::create_dir_all(&dir).with_context(|| format!("logging: create {}", dir.display()))?;
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix("hn-tui")
+        .filename_suffix("log")
+        .max_log_files(MAX_LOG_FILES)
+        .build(&dir)
+        .context("logging: open log file")?;
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+
+    let filter = match std::env::var(LOG_FILTER_ENV) {
+        Ok(directives) if !directives.trim().is_empty() => EnvFilter::try_new(directives.trim())
+            .with_context(|| format!("logging: invalid {LOG_FILTER_ENV} filter"))?,
+        _ if verbose => EnvFilter::new(VERBOSE_FILTER),
+        _ => EnvFilter::new(DEFAULT_FILTER),
+    };
+
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(writer)
+        .with_ansi(false)
+        .with_target(true)
+        .try_init()
+        .map_err(|err| anyhow::anyhow!("logging: install subscriber: {err}"))?;
+    tracing::info!(version = crate::VERSION, "logging started");
+    Ok(guard)
+}
+
+pub fn log_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("hn-tui").join("logs"))
+}
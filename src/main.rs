@@ -1,17 +1,59 @@
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let profile = args
+        .iter()
+        .position(|arg| arg == "--profile")
+        .map(|idx| args.get(idx + 1).map(String::as_str).unwrap_or_default());
+    if let Err(err) = hn_tui::profile::select(profile) {
+        eprintln!("error: {err}");
+        std::process::exit(2);
+    }
+
     if handle_cli_flags() {
         return;
     }
 
-    if let Err(err) = hn_tui::run() {
+    let run_opts = hn_tui::RunOptions {
+        screen_reader: std::env::args().any(|arg| arg == "--screen-reader"),
+        verbose: std::env::args().any(|arg| arg == "--verbose" || arg == "-v"),
+        open_target: launch_target(&args),
+    };
+    if let Err(err) = hn_tui::run_with(run_opts) {
         eprintln!("error: {err:?}");
         std::process::exit(1);
     }
 }
 
+/// Flags whose next argument is their value rather than a launch target.
+const VALUE_FLAGS: &[&str] = &[
+    "--profile",
+    "--rss",
+    "--output",
+    "-o",
+    "--backup",
+    "--restore",
+];
+
+/// First argument that is neither a flag nor the value of one.
+fn launch_target(args: &[String]) -> Option<String> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if VALUE_FLAGS.contains(&arg.as_str()) {
+            iter.next();
+        } else if !arg.starts_with('-') {
+            return Some(arg.clone());
+        }
+    }
+    None
+}
+
 fn handle_cli_flags() -> bool {
     let mut saw_flag = false;
-    for arg in std::env::args().skip(1) {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut rss_target = None;
+    let mut rss_output = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
         match arg.as_str() {
             "--version" | "-V" => {
                 println!("HN-TUI {}", hn_tui::VERSION);
@@ -19,10 +61,45 @@ fn handle_cli_flags() -> bool {
             }
             "--help" | "-h" => {
                 println!(
-                    "HN-TUI — Browse Hacker News from the terminal.\n\n  --version, -V        Show version and exit\n  --help,    -h        Show this help message\n  --check-updates      Check for updates and exit"
+                    "HN-TUI — Browse Hacker News from the terminal.\n\nUsage: hn-tui [options] [target]   target: r/<name>, u/<name>, an HN or Reddit link, or a search\n\n  --version, -V        Show version and exit\n  --help,    -h        Show this help message\n  --check-updates      Check for updates and exit\n  --rss <feed>         Print a feed (top, new, best, ask, show, jobs, u/<name>) as RSS\n  --output, -o <file>  Write --rss output to a file instead of stdout\n  --backup <file>      Write an encrypted backup of accounts, bookmarks, history, and drafts\n  --restore <file>     Replace stored data with a backup (quit HN-TUI first)\n  --check-db           Check the local database for damage and exit\n  --vacuum             Drop expired cache entries, compact the database, and exit\n  --profile <name>     Use a named profile with its own config, accounts, and data\n  --screen-reader      Start in screen-reader mode (no borders, icons, or color-only cues)\n  --verbose, -v        Write debug logs (overridden by HN_TUI_LOG)\n  --tmux-setup         Print the tmux settings inline images need"
                 );
                 saw_flag = true;
             }
+            "--backup" | "--restore" => {
+                saw_flag = true;
+                let Some(path) = iter.next() else {
+                    eprintln!("{arg} requires a file path");
+                    std::process::exit(2);
+                };
+                let result = if arg == "--backup" {
+                    backup_to(path)
+                } else {
+                    restore_from(path)
+                };
+                if let Err(err) = result {
+                    eprintln!("{arg} failed: {err:?}");
+                    std::process::exit(1);
+                }
+            }
+            "--check-db" => {
+                saw_flag = true;
+                if let Err(err) = check_database() {
+                    eprintln!("Database check failed: {err:?}");
+                    std::process::exit(1);
+                }
+            }
+            "--vacuum" => {
+                saw_flag = true;
+                if let Err(err) = vacuum_database() {
+                    eprintln!("Database maintenance failed: {err:?}");
+                    std::process::exit(1);
+                }
+            }
+            "--tmux-setup" => {
+                println!("# Add to ~/.tmux.conf, then run: tmux source-file ~/.tmux.conf");
+                println!("{}", hn_tui::tmux::SETUP);
+                saw_flag = true;
+            }
             "--check-updates" => {
                 saw_flag = true;
                 if let Err(err) = check_updates_once() {
@@ -30,12 +107,125 @@ fn handle_cli_flags() -> bool {
                     std::process::exit(1);
                 }
             }
+            "--rss" => {
+                saw_flag = true;
+                let Some(target) = iter.next() else {
+                    eprintln!("--rss requires a feed name, e.g. --rss top");
+                    std::process::exit(2);
+                };
+                rss_target = Some(target);
+            }
+            "--output" | "-o" => {
+                let Some(path) = iter.next() else {
+                    eprintln!("{arg} requires a file path");
+                    std::process::exit(2);
+                };
+                rss_output = Some(path);
+            }
             _ => {}
         }
     }
+    match (rss_target, rss_output) {
+        (Some(target), output) => {
+            if let Err(err) = export_rss(target, output.map(String::as_str)) {
+                eprintln!("RSS export failed: {err:?}");
+                std::process::exit(1);
+            }
+        }
+        (None, Some(_)) => {
+            eprintln!("--output only applies to --rss, e.g. --rss top --output top.xml");
+            std::process::exit(2);
+        }
+        (None, None) => {}
+    }
     saw_flag
 }
 
+fn export_rss(target: &str, output: Option<&str>) -> anyhow::Result<()> {
+    use anyhow::Context;
+
+    let cfg = hn_tui::config::load(hn_tui::config::LoadOptions::default())?;
+    hn_tui::net::configure(&cfg.network)?;
+    let document = hn_tui::rss::export_target(target, &cfg)?;
+    match output {
+        Some(path) => {
+            std::fs::write(path, document).with_context(|| format!("write {path}"))?;
+            eprintln!("Wrote {target} feed to {path}");
+        }
+        None => print!("{document}"),
+    }
+    Ok(())
+}
+
+fn backup_to(path: &str) -> anyhow::Result<()> {
+    use hn_tui::{backup, storage};
+
+    let store = storage::Store::open(storage::Options::default())?;
+    let passphrase = backup::read_passphrase(true)?;
+    backup::create(&store, std::path::Path::new(path), &passphrase)?;
+    eprintln!(
+        "Backed up the {} profile to {path}",
+        hn_tui::profile::active()
+    );
+    Ok(())
+}
+
+fn restore_from(path: &str) -> anyhow::Result<()> {
+    use anyhow::Context;
+    use hn_tui::{backup, storage};
+
+    let db = storage::default_path().context("resolve storage path")?;
+    let passphrase = backup::read_passphrase(false)?;
+    backup::stage_restore(std::path::Path::new(path), &passphrase, &db)?;
+    backup::apply_staged(&db)?;
+    eprintln!("Restored {path} into {}", db.display());
+    Ok(())
+}
+
+fn vacuum_database() -> anyhow::Result<()> {
+    use hn_tui::{media, storage};
+
+    let store = storage::Store::open(storage::Options::default())?;
+    let pruned = media::prune_expired(&store)?;
+    let stale = store.prune_stale(chrono::Utc::now())?;
+    let compaction = store.compact()?;
+    println!(
+        "Removed {pruned} expired media entries, {} old history entries, {} thread visits, and \
+         {} comment sorts.",
+        stale.history_posts, stale.thread_visits, stale.comment_sorts
+    );
+    println!(
+        "Database compacted from {} KB to {} KB.",
+        compaction.bytes_before / 1024,
+        compaction.bytes_after / 1024
+    );
+    Ok(())
+}
+
+fn check_database() -> anyhow::Result<()> {
+    use hn_tui::storage;
+
+    let store = storage::Store::open(storage::Options::default())?;
+    println!(
+        "Schema version {} (this build: {})",
+        store.schema_version()?,
+        storage::latest_schema_version()
+    );
+    let problems = store.check_integrity()?;
+    if problems.is_empty() {
+        println!("No problems found.");
+        return Ok(());
+    }
+    for problem in &problems {
+        println!("- {problem}");
+    }
+    anyhow::bail!(
+        "{} problem{} found; restore a backup with --restore if the app misbehaves",
+        problems.len(),
+        if problems.len() == 1 { "" } else { "s" }
+    )
+}
+
 fn check_updates_once() -> anyhow::Result<()> {
     use semver::Version;
 
@@ -45,8 +235,11 @@ fn check_updates_once() -> anyhow::Result<()> {
         return Ok(());
     }
 
+    let cfg = hn_tui::config::load(hn_tui::config::LoadOptions::default())?;
+    hn_tui::net::configure(&cfg.network)?;
+    let channel = hn_tui::update::Channel::from_config(&cfg.update.channel);
     let current = Version::parse(hn_tui::VERSION)?;
-    match hn_tui::update::check_for_update(&current)? {
+    match hn_tui::update::check_for_update(&current, channel)? {
         Some(info) => {
             let hn_tui::update::UpdateInfo {
                 version,
@@ -54,10 +247,41 @@ fn check_updates_once() -> anyhow::Result<()> {
                 ..
             } = info;
             println!("Update available: {current} -> {version}\n{release_url}");
+            if let Some(hint) = hn_tui::update::InstallMethod::detect().upgrade_hint() {
+                println!("{hint}");
+            }
         }
         None => {
-            println!("HN-TUI {current} is up to date.");
+            println!(
+                "HN-TUI {current} is up to date ({} channel).",
+                channel.label()
+            );
         }
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn flag_values_are_not_launch_targets() {
+        assert_eq!(
+            launch_target(&args(&["--rss", "top", "--output", "top.xml"])),
+            None
+        );
+        assert_eq!(
+            launch_target(&args(&["-o", "feed.xml", "r/rust"])),
+            Some("r/rust".to_string())
+        );
+        assert_eq!(
+            launch_target(&args(&["--profile", "work", "u/pg"])),
+            Some("u/pg".to_string())
+        );
+    }
+}
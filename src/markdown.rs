@@ -1,17 +1,49 @@
+use std::collections::HashMap;
+
+use once_cell::sync::Lazy;
 use pulldown_cmark::{CodeBlockKind, CowStr, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
 use ratatui::layout::Alignment;
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span, Text};
+use regex::{Captures, Regex};
+
+use crate::theme;
 
 #[derive(Default)]
 pub struct Renderer;
 
+/// A heading of a rendered document. `line` indexes the unwrapped lines that
+/// [`Renderer::render`] returns for the same input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Heading {
+    pub level: u8,
+    pub text: String,
+    pub line: usize,
+}
+
 impl Renderer {
     pub fn new() -> Self {
         Self
     }
 
     pub fn render(&self, input: &str) -> Text<'static> {
+        self.write(input).into_text()
+    }
+
+    /// The document's headings in order, for a table of contents.
+    pub fn headings(&self, input: &str) -> Vec<Heading> {
+        self.write(input)
+            .lines
+            .into_iter()
+            .enumerate()
+            .filter_map(|(line, entry)| match entry {
+                RenderLine::Heading { level, text } => Some(Heading { level, text, line }),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn write(&self, input: &str) -> MarkdownWriter {
         let mut opts = Options::empty();
         opts.insert(Options::ENABLE_STRIKETHROUGH);
         opts.insert(Options::ENABLE_TABLES);
@@ -21,7 +53,7 @@ impl Renderer {
         let parser = Parser::new_ext(input, opts);
         let mut writer = MarkdownWriter::default();
         writer.render(parser);
-        writer.into_text()
+        writer
     }
 }
 
@@ -222,16 +254,18 @@ impl MarkdownWriter {
         }
     }
 
+    // Callers already undid the API's escaping and pulldown-cmark resolves
+    // markdown's own entities, so text is only scrubbed, never decoded again.
     fn text<'a>(&mut self, text: CowStr<'a>) {
         if let Some(code) = self.code_block.as_mut() {
             code.buffer.push_str(&text);
         } else {
-            self.append_text(text);
+            self.append_text(scrub_control_chars(&text));
         }
     }
 
     fn inline_code<'a>(&mut self, code: CowStr<'a>) {
-        self.append_text(format!("`{}`", code));
+        self.append_text(format!("`{code}`"));
     }
 
     fn append_text<T: AsRef<str>>(&mut self, text: T) {
@@ -305,7 +339,7 @@ impl MarkdownWriter {
                     spans.push(Span::raw("  ".repeat(indent)));
                     spans.push(Span::styled(
                         format!("{} ", marker),
-                        Style::default().fg(Color::Yellow),
+                        Style::default().fg(theme::tint(Color::Yellow)),
                     ));
                     spans.push(Span::raw(text));
                     styled_lines.push(Line::from(spans));
@@ -313,14 +347,14 @@ impl MarkdownWriter {
                 RenderLine::Quote { depth, text } => {
                     let prefix = ">".repeat(depth.max(1));
                     styled_lines.push(Line::from(vec![
-                        Span::styled(prefix + " ", Style::default().fg(Color::Green)),
-                        Span::styled(text, Style::default().fg(Color::Green)),
+                        Span::styled(prefix + " ", Style::default().fg(theme::tint(Color::Green))),
+                        Span::styled(text, Style::default().fg(theme::tint(Color::Green))),
                     ]));
                 }
                 RenderLine::Code(text) => {
                     styled_lines.push(Line::from(vec![Span::styled(
                         text,
-                        Style::default().fg(Color::Cyan),
+                        Style::default().fg(theme::tint(Color::Cyan)),
                     )]));
                 }
                 RenderLine::Separator => styled_lines.push(Line::default()),
@@ -339,18 +373,303 @@ impl MarkdownWriter {
     }
 }
 
+pub fn decode_html_entities(input: &str) -> String {
+    // Listings are sometimes escaped twice (`&amp;#39;`), so decode until stable.
+    let mut current = input.to_string();
+    for _ in 0..3 {
+        if !current.contains('&') {
+            break;
+        }
+        let decoded = decode_entities_once(&current);
+        if decoded == current {
+            break;
+        }
+        current = decoded;
+    }
+    current
+}
+
+/// Undoes the single layer of escaping the API applies to markdown bodies.
+/// Entities the author wrote are left for the renderer, so `&amp;lt;` in a
+/// body still shows as `&lt;`.
+pub fn decode_body_entities(input: &str) -> String {
+    decode_entities_once(input)
+}
+
+/// Character references like `&#27;` resolve to control characters, which
+/// would reach the terminal as escape sequences.
+fn scrub_control_chars(text: &str) -> String {
+    text.chars()
+        .map(|ch| match ch {
+            '\n' | '\t' => ch,
+            ch if ch.is_control() => char::REPLACEMENT_CHARACTER,
+            ch => ch,
+        })
+        .collect()
+}
+
+/// Replaces known `:shortcode:` names and Reddit emote images with Unicode
+/// emoji, leaving code blocks and code spans as written. `emotes` maps emote
+/// media ids (`emote|<pack>|<id>`) to image URLs from `media_metadata`; an
+/// emote with no emoji becomes a link to its image.
+pub fn replace_emoji_shortcodes(input: &str, emotes: &HashMap<String, String>) -> String {
+    static EMOTE_IMAGE_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"!\[(?:img|gif)\]\((emote\|[^|)\s]+\|([^)\s]+))\)").expect("valid emote regex")
+    });
+    static SHORTCODE_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r":([a-z0-9_+\-]{1,40}):").expect("valid shortcode regex"));
+
+    if !input.contains(':') && !input.contains("](emote|") {
+        return input.to_string();
+    }
+
+    map_outside_code(input, |text| {
+        let unwrapped = EMOTE_IMAGE_RE.replace_all(text, |caps: &Captures| {
+            let name = &caps[2];
+            match emotes.get(&caps[1]) {
+                Some(url) if shortcode_emoji(name).is_none() => format!("[:{name}:]({url})"),
+                _ => format!(":{name}:"),
+            }
+        });
+        SHORTCODE_RE
+            .replace_all(&unwrapped, |caps: &Captures| {
+                shortcode_emoji(&caps[1]).unwrap_or(&caps[0]).to_string()
+            })
+            .into_owned()
+    })
+}
+
+fn shortcode_emoji(name: &str) -> Option<&'static str> {
+    emoji_for_shortcode(name.strip_prefix("snoo_").unwrap_or(name))
+}
+
+/// Applies `replace` to the text outside fenced and indented code blocks and
+/// inline code spans, which markdown shows verbatim.
+fn map_outside_code(input: &str, mut replace: impl FnMut(&str) -> String) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut fence: Option<(char, usize)> = None;
+    let mut after_blank = true;
+    let mut in_indented = false;
+    for line in input.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        let blank = trimmed.trim().is_empty();
+        if let Some((marker, len)) = fence {
+            out.push_str(line);
+            let closing = trimmed.trim_end();
+            if indent < 4 && closing.len() >= len && closing.chars().all(|ch| ch == marker) {
+                fence = None;
+            }
+            after_blank = false;
+            continue;
+        }
+        if let Some(marker @ ('`' | '~')) = trimmed.chars().next().filter(|_| indent < 4) {
+            let len = trimmed.chars().take_while(|ch| *ch == marker).count();
+            if len >= 3 {
+                fence = Some((marker, len));
+                out.push_str(line);
+                after_blank = false;
+                in_indented = false;
+                continue;
+            }
+        }
+        in_indented = !blank
+            && (line.starts_with("    ") || line.starts_with('\t'))
+            && (after_blank || in_indented);
+        if in_indented {
+            out.push_str(line);
+        } else {
+            map_outside_code_spans(line, &mut replace, &mut out);
+        }
+        after_blank = blank;
+    }
+    out
+}
+
+fn map_outside_code_spans(line: &str, replace: &mut impl FnMut(&str) -> String, out: &mut String) {
+    let bytes = line.as_bytes();
+    let mut plain_start = 0;
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] != b'`' {
+            index += 1;
+            continue;
+        }
+        let run = backtick_run(&bytes[index..]);
+        match find_backtick_run(&bytes[index + run..], run) {
+            Some(offset) => {
+                let end = index + run + offset + run;
+                out.push_str(&replace(&line[plain_start..index]));
+                out.push_str(&line[index..end]);
+                index = end;
+                plain_start = end;
+            }
+            None => index += run,
+        }
+    }
+    out.push_str(&replace(&line[plain_start..]));
+}
+
+fn backtick_run(bytes: &[u8]) -> usize {
+    bytes.iter().take_while(|byte| **byte == b'`').count()
+}
+
+/// Offset of the next run of exactly `len` backticks, which closes a span.
+fn find_backtick_run(bytes: &[u8], len: usize) -> Option<usize> {
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'`' {
+            let run = backtick_run(&bytes[index..]);
+            if run == len {
+                return Some(index);
+            }
+            index += run;
+        } else {
+            index += 1;
+        }
+    }
+    None
+}
+
+fn emoji_for_shortcode(name: &str) -> Option<&'static str> {
+    let emoji = match name {
+        "joy" | "laughing" | "tears_of_joy" => "😂",
+        "smile" | "smiley" | "simple_smile" => "😄",
+        "grin" | "grinning" => "😁",
+        "wink" => "😉",
+        "blush" => "😊",
+        "heart_eyes" | "hearteyes" => "😍",
+        "thinking" | "thinking_face" => "🤔",
+        "facepalm" | "facepalm_face" => "🤦",
+        "shrug" => "🤷",
+        "cry" | "sad" | "disappointed" => "😢",
+        "sob" => "😭",
+        "scream" | "scared" => "😱",
+        "angry" | "rage" | "mad" => "😠",
+        "sweat_smile" => "😅",
+        "sunglasses" | "cool" => "😎",
+        "upside_down" | "upside_down_face" => "🙃",
+        "neutral_face" | "neutral" => "😐",
+        "unamused" => "😒",
+        "rolling_eyes" => "🙄",
+        "shocked" | "open_mouth" | "astonished" => "😮",
+        "hug" | "hugs" => "🤗",
+        "snoo" | "alien" => "👽",
+        "skull" => "💀",
+        "poop" | "hankey" => "💩",
+        "+1" | "thumbsup" | "thumbs_up" | "upvote" => "👍",
+        "-1" | "thumbsdown" | "thumbs_down" | "downvote" => "👎",
+        "clap" => "👏",
+        "wave" => "👋",
+        "pray" => "🙏",
+        "muscle" => "💪",
+        "ok_hand" => "👌",
+        "eyes" => "👀",
+        "heart" | "love" => "❤️",
+        "broken_heart" => "💔",
+        "fire" => "🔥",
+        "100" => "💯",
+        "tada" | "party" => "🎉",
+        "rocket" => "🚀",
+        "star" => "⭐",
+        "sparkles" => "✨",
+        "check" | "white_check_mark" | "heavy_check_mark" => "✅",
+        "x" | "cross_mark" => "❌",
+        "warning" => "⚠️",
+        "trophy" => "🏆",
+        "moneybag" | "money" => "💰",
+        "coffee" => "☕",
+        "beer" => "🍺",
+        "pizza" => "🍕",
+        "crab" => "🦀",
+        "bug" => "🐛",
+        _ => return None,
+    };
+    Some(emoji)
+}
+
+/// One decoding pass, for markdown bodies: decoding until stable would also
+/// unescape entities the author wrote inside code.
+fn decode_entities_once(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        let candidate = &rest[start..];
+        let decoded = candidate
+            .find(';')
+            .filter(|end| *end > 1 && *end <= 10)
+            .and_then(|end| decode_entity(&candidate[1..end]).map(|ch| (ch, end)));
+        match decoded {
+            Some((ch, end)) => {
+                out.push(ch);
+                rest = &candidate[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &candidate[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn decode_entity(name: &str) -> Option<char> {
+    if let Some(num) = name.strip_prefix('#') {
+        let code = match num.strip_prefix('x').or_else(|| num.strip_prefix('X')) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => num.parse::<u32>().ok()?,
+        };
+        // Control characters would reach the terminal as escape sequences.
+        return char::from_u32(code).map(|ch| match ch {
+            '\n' | '\t' => ch,
+            ch if ch.is_control() => char::REPLACEMENT_CHARACTER,
+            ch => ch,
+        });
+    }
+    let ch = match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "hellip" => '…',
+        "mdash" => '—',
+        "ndash" => '–',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "bull" => '•',
+        "middot" => '·',
+        "copy" => '©',
+        "reg" => '®',
+        "trade" => '™',
+        "deg" => '°',
+        "times" => '×',
+        "euro" => '€',
+        "pound" => '£',
+        "cent" => '¢',
+        "yen" => '¥',
+        _ => return None,
+    };
+    Some(ch)
+}
+
 fn heading_style(level: u8) -> Style {
     match level {
         1 => Style::default()
-            .fg(Color::Yellow)
+            .fg(theme::tint(Color::Yellow))
             .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
         2 => Style::default()
-            .fg(Color::Yellow)
+            .fg(theme::tint(Color::Yellow))
             .add_modifier(Modifier::BOLD),
         3 => Style::default()
-            .fg(Color::Magenta)
+            .fg(theme::tint(Color::Magenta))
             .add_modifier(Modifier::BOLD),
-        _ => Style::default().fg(Color::Magenta),
+        _ => Style::default().fg(theme::tint(Color::Magenta)),
     }
 }
 
@@ -364,3 +683,78 @@ fn level_to_u8(level: HeadingLevel) -> u8 {
         HeadingLevel::H6 => 6,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(text: &Text<'_>) -> String {
+        text.lines
+            .iter()
+            .map(|line| {
+                line.spans
+                    .iter()
+                    .map(|span| span.content.as_ref())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn numeric_entities_never_decode_to_control_characters() {
+        assert_eq!(decode_html_entities("&#27;[2J"), "\u{fffd}[2J");
+        assert_eq!(decode_html_entities("&#x9b;31m"), "\u{fffd}31m");
+        assert_eq!(decode_html_entities("a&#10;b&#9;c"), "a\nb\tc");
+        assert_eq!(decode_html_entities("&amp;#39;ok&#39;"), "'ok'");
+    }
+
+    #[test]
+    fn code_spans_are_decoded_only_once() {
+        let body = decode_body_entities("Escape it as `&amp;lt;` here");
+        let rendered = plain(&Renderer::new().render(&body));
+        assert!(rendered.contains("`&lt;`"), "{rendered}");
+    }
+
+    #[test]
+    fn prose_entities_are_decoded_only_once() {
+        let body = decode_body_entities("Write &amp;amp;lt; for &amp;lt; &amp;#27;");
+        let rendered = plain(&Renderer::new().render(&body));
+        assert_eq!(rendered.trim(), "Write &lt; for < \u{fffd}");
+    }
+
+    #[test]
+    fn emoji_shortcodes_leave_code_alone() {
+        let none = HashMap::new();
+        assert_eq!(
+            replace_emoji_shortcodes("ok :joy: but `:joy:` and ``a `:joy:` b``", &none),
+            "ok 😂 but `:joy:` and ``a `:joy:` b``"
+        );
+        let fenced = "```\nx = :joy:\n```\n:joy:";
+        assert_eq!(
+            replace_emoji_shortcodes(fenced, &none),
+            "```\nx = :joy:\n```\n😂"
+        );
+        let indented = "text\n\n    y = :joy:\n\n:joy:";
+        assert_eq!(
+            replace_emoji_shortcodes(indented, &none),
+            "text\n\n    y = :joy:\n\n😂"
+        );
+    }
+
+    #[test]
+    fn emotes_without_emoji_link_to_their_media_metadata_image() {
+        let emotes = HashMap::from([(
+            "emote|t5_2qh1i|1234".to_string(),
+            "https://i.redd.it/1234.gif".to_string(),
+        )]);
+        assert_eq!(
+            replace_emoji_shortcodes("wow ![gif](emote|t5_2qh1i|1234)", &emotes),
+            "wow [:1234:](https://i.redd.it/1234.gif)"
+        );
+        assert_eq!(
+            replace_emoji_shortcodes("![img](emote|t5_2qh1i|5678)", &emotes),
+            ":5678:"
+        );
+    }
+}
@@ -14,8 +14,13 @@ use parking_lot::Mutex;
 use reqwest::blocking::Client;
 use sha1::{Digest, Sha1};
 
+use crate::net;
+use crate::profile;
 use crate::storage::{self, MediaEntry};
 
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(10 * 60);
+const MAINTENANCE_BATCH: usize = 500;
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub cache_dir: Option<PathBuf>,
@@ -78,6 +83,14 @@ pub struct ResultEntry {
     pub rejected: bool,
 }
 
+/// Snapshot of the disk cache for display.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CacheStats {
+    pub entries: i64,
+    pub size_bytes: i64,
+    pub quota_bytes: i64,
+}
+
 struct Job {
     request: Request,
     tx: Sender<ResultEntry>,
@@ -91,6 +104,8 @@ struct Inner {
     stop: Sender<()>,
     pruning: Mutex<()>,
     queue_depth: AtomicUsize,
+    /// Downloads made in privacy mode; removed when the manager is dropped.
+    scratch: Mutex<Option<tempfile::TempDir>>,
 }
 
 pub struct Manager {
@@ -123,8 +138,7 @@ impl Manager {
         let client = if let Some(client) = cfg.http_client.clone() {
             client
         } else {
-            Client::builder()
-                .timeout(Duration::from_secs(30))
+            net::download_client_builder()
                 .build()
                 .context("media: build http client")?
         };
@@ -140,6 +154,7 @@ impl Manager {
             stop: stop_tx,
             pruning: Mutex::new(()),
             queue_depth: AtomicUsize::new(0),
+            scratch: Mutex::new(None),
         });
 
         let mut handles = Vec::new();
@@ -149,6 +164,8 @@ impl Manager {
             let worker_inner = inner.clone();
             handles.push(thread::spawn(move || worker_inner.worker(rx_jobs, rx_stop)));
         }
+        let maintainer = inner.clone();
+        handles.push(thread::spawn(move || maintainer.maintenance(stop_rx)));
 
         Ok(Self { inner, handles })
     }
@@ -206,6 +223,15 @@ impl Handle {
         }
         rx
     }
+
+    pub fn stats(&self) -> Result<CacheStats> {
+        let stats = self.inner.store.media_cache_stats()?;
+        Ok(CacheStats {
+            entries: stats.entries,
+            size_bytes: stats.size_bytes,
+            quota_bytes: self.inner.cfg.max_size_bytes,
+        })
+    }
 }
 
 impl Inner {
@@ -226,6 +252,28 @@ impl Inner {
         }
     }
 
+    /// Prunes once at startup and then every `MAINTENANCE_INTERVAL` until the
+    /// manager shuts down.
+    fn maintenance(&self, stop: Receiver<()>) {
+        let ticker = crossbeam_channel::tick(MAINTENANCE_INTERVAL);
+        loop {
+            if let Err(err) = self.run_maintenance() {
+                tracing::warn!("media cache maintenance failed: {err:#}");
+            }
+            crossbeam_channel::select! {
+                recv(stop) -> _ => break,
+                recv(ticker) -> _ => {}
+            }
+        }
+    }
+
+    /// Drops expired entries and rows whose file disappeared, then evicts
+    /// least recently used media until the cache fits its quota.
+    fn run_maintenance(&self) -> Result<()> {
+        prune_expired(&self.store)?;
+        self.prune_if_needed(0)
+    }
+
     fn process(&self, job: Job) {
         let result = match self.fetch(job.request) {
             Ok(entry) => ResultEntry {
@@ -233,11 +281,14 @@ impl Inner {
                 error: None,
                 rejected: false,
             },
-            Err(err) => ResultEntry {
-                entry: None,
-                error: Some(err),
-                rejected: false,
-            },
+            Err(err) => {
+                tracing::warn!("media fetch failed: {err:#}");
+                ResultEntry {
+                    entry: None,
+                    error: Some(err),
+                    rejected: false,
+                }
+            }
         };
         let _ = job.tx.send(result);
     }
@@ -247,20 +298,22 @@ impl Inner {
             return Err(anyhow!("media: url required"));
         }
 
+        // Privacy mode reads the cache but leaves no trace of what was viewed.
+        let private = self.store.is_private();
         if let Some(entry) = self.store.get_media_entry_by_url(&request.url)? {
             if !request.force
                 && self.is_fresh(&entry, request.ttl)
                 && Path::new(&entry.file_path).exists()
             {
+                if !private {
+                    let _ = self.store.touch_media_entry(entry.id);
+                }
                 return Ok(entry);
             }
         }
 
-        let response = self
-            .client
-            .get(&request.url)
-            .send()
-            .context("media: download")?;
+        tracing::debug!(url = %request.url, "media download");
+        let response = net::send(self.client.get(&request.url)).context("media: download")?;
 
         if !response.status().is_success() {
             let status = response.status();
@@ -277,7 +330,11 @@ impl Inner {
             .or(request.media_type.clone())
             .unwrap_or_else(|| detect_mime(&bytes));
 
-        let file_path = self.write_file(&bytes)?;
+        let file_path = if private {
+            self.write_scratch_file(&bytes)?
+        } else {
+            self.write_file(&bytes)?
+        };
         let checksum = sha1_hex(&bytes);
         let width = request.width.unwrap_or_default();
         let height = request.height.unwrap_or_default();
@@ -297,6 +354,9 @@ impl Inner {
             checksum,
         };
 
+        if private {
+            return Ok(media_entry);
+        }
         self.prune_if_needed(media_entry.size_bytes)?;
         let id = self.store.upsert_media_entry(media_entry.clone())?;
         Ok(MediaEntry { id, ..media_entry })
@@ -316,6 +376,26 @@ impl Inner {
         }
     }
 
+    /// Writes outside the cache directory, without an index entry.
+    fn write_scratch_file(&self, data: &[u8]) -> Result<String> {
+        let name = format!("{}.bin", sha1_hex(data));
+        let mut scratch = self.scratch.lock();
+        let path = match scratch.as_ref() {
+            Some(dir) => dir.path().join(&name),
+            None => {
+                let dir = tempfile::Builder::new()
+                    .prefix("hn-tui-private-")
+                    .tempdir()
+                    .context("media: create private cache")?;
+                let path = dir.path().join(&name);
+                *scratch = Some(dir);
+                path
+            }
+        };
+        fs::write(&path, data).context("media: write")?;
+        Ok(path.to_string_lossy().to_string())
+    }
+
     fn write_file(&self, data: &[u8]) -> Result<String> {
         let cache_dir = self.cfg.cache_dir.as_ref().expect("cache dir");
         let filename = format!("{}.bin", sha1_hex(data));
@@ -334,7 +414,7 @@ impl Inner {
         let mut ids = Vec::new();
         let mut paths = Vec::new();
 
-        for entry in self.store.list_oldest_media(100)? {
+        for entry in self.store.list_least_recent_media(100)? {
             total -= entry.size_bytes;
             ids.push(entry.id);
             paths.push(entry.file_path);
@@ -351,8 +431,32 @@ impl Inner {
     }
 }
 
+/// Deletes expired cache entries with their files, and entries whose file
+/// is already gone. Returns how many entries were removed.
+pub fn prune_expired(store: &storage::Store) -> Result<usize> {
+    let mut ids = Vec::new();
+    let mut paths = Vec::new();
+    for entry in store.list_expired_media(Utc::now(), MAINTENANCE_BATCH)? {
+        ids.push(entry.id);
+        paths.push(entry.file_path);
+    }
+    for entry in store.list_all_media()? {
+        if !ids.contains(&entry.id) && !Path::new(&entry.file_path).exists() {
+            ids.push(entry.id);
+        }
+    }
+    if !ids.is_empty() {
+        tracing::debug!(removed = ids.len(), "media cache maintenance");
+    }
+    store.delete_media_entries(&ids)?;
+    for path in paths {
+        let _ = fs::remove_file(path);
+    }
+    Ok(ids.len())
+}
+
 fn default_cache_dir() -> Option<PathBuf> {
-    dirs::cache_dir().map(|dir| dir.join("reddix"))
+    dirs::cache_dir().map(|dir| profile::scope(dir.join("reddix")))
 }
 
 fn sha1_hex(data: &[u8]) -> String {
@@ -0,0 +1,183 @@
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use reqwest::blocking::{Client, ClientBuilder, RequestBuilder, Response};
+use reqwest::{NoProxy, Proxy};
+
+use crate::config::NetworkConfig;
+
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+/// Whole-request limit for media and video downloads, which can take far
+/// longer than `network.timeout` on a slow link.
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+struct Settings {
+    proxy: Option<Proxy>,
+    /// Set when traffic goes through `network.socks_proxy`.
+    socks: Option<String>,
+    /// `network.proxy.url`, handed to external players.
+    http_proxy: Option<String>,
+    connect_timeout: Duration,
+    timeout: Duration,
+    retry: RetryPolicy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub retries: u32,
+    pub backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Exponential backoff: `backoff`, then twice that, and so on, capped at 30s.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        self.backoff
+            .saturating_mul(1 << attempt.min(16))
+            .min(MAX_RETRY_DELAY)
+    }
+}
+
+impl Settings {
+    fn from_config(cfg: &NetworkConfig) -> Result<Self> {
+        let socks = cfg.socks_proxy.trim();
+        let url = cfg.proxy.url.trim();
+        let http_proxy = (socks.is_empty() && !url.is_empty()).then(|| url.to_string());
+        let (proxy, label) = if !socks.is_empty() {
+            // No bypass list here: a SOCKS route (typically Tor) should not leak.
+            let proxy = Proxy::all(socks_url(socks))
+                .with_context(|| format!("network: invalid socks proxy {socks}"))?;
+            let addr = socks.split_once("://").map_or(socks, |(_, addr)| addr);
+            (Some(proxy), Some(addr.to_string()))
+        } else if !url.is_empty() {
+            let proxy = Proxy::all(url).with_context(|| format!("network: invalid proxy {url}"))?;
+            let proxy = proxy.no_proxy(NoProxy::from_string(&cfg.proxy.no_proxy));
+            (Some(proxy), None)
+        } else {
+            (None, None)
+        };
+        Ok(Self {
+            proxy,
+            socks: label,
+            http_proxy,
+            connect_timeout: cfg.connect_timeout,
+            timeout: cfg.timeout,
+            retry: RetryPolicy {
+                retries: cfg.retries,
+                backoff: cfg.retry_backoff,
+            },
+        })
+    }
+}
+
+fn settings() -> &'static Settings {
+    SETTINGS.get_or_init(|| {
+        Settings::from_config(&NetworkConfig::default()).expect("default network settings")
+    })
+}
+
+/// Applies the `network` config to every client built through
+/// [`client_builder`] and every request sent through [`send`] afterwards.
+/// Call once at startup, before the first request; later calls are ignored.
+pub fn configure(cfg: &NetworkConfig) -> Result<()> {
+    let _ = SETTINGS.set(Settings::from_config(cfg)?);
+    Ok(())
+}
+
+/// Bare `host:port` values get `socks5h://`, so hostnames are resolved by the
+/// proxy rather than leaking through local DNS.
+fn socks_url(raw: &str) -> String {
+    if raw.contains("://") {
+        raw.to_string()
+    } else {
+        format!("socks5h://{raw}")
+    }
+}
+
+/// Status bar label while traffic is routed through a SOCKS proxy.
+pub fn route_label() -> Option<String> {
+    let addr = settings().socks.as_deref()?;
+    Some(format!("SOCKS {addr}"))
+}
+
+/// The `network.proxy.url` HTTP proxy, for players that fetch on their own.
+pub fn http_proxy_url() -> Option<String> {
+    settings().http_proxy.clone()
+}
+
+/// A blocking client builder with the configured timeouts and proxy. With no
+/// proxy configured, reqwest's system proxy lookup applies, which honors
+/// `HTTP_PROXY`, `HTTPS_PROXY`, and `NO_PROXY`.
+pub fn client_builder() -> ClientBuilder {
+    base_builder().timeout(settings().timeout)
+}
+
+/// Like [`client_builder`], but for media and video downloads: connecting is
+/// still bounded by `network.connect_timeout`, while the whole request gets
+/// [`DOWNLOAD_TIMEOUT`] instead of `network.timeout`.
+pub fn download_client_builder() -> ClientBuilder {
+    base_builder().timeout(DOWNLOAD_TIMEOUT)
+}
+
+fn base_builder() -> ClientBuilder {
+    let settings = settings();
+    let builder = Client::builder().connect_timeout(settings.connect_timeout);
+    match settings.proxy.clone() {
+        Some(proxy) => builder.proxy(proxy),
+        None => builder,
+    }
+}
+
+/// Sends `request`, retrying with backoff per `network.retries`. Connection
+/// failures are always retried; timeouts and 502/503/504 responses only for
+/// idempotent methods, so a slow vote or comment is never submitted twice.
+pub fn send(request: RequestBuilder) -> reqwest::Result<Response> {
+    let (client, request) = request.build_split();
+    let request = request?;
+    let policy = settings().retry;
+    let idempotent = request.method().is_idempotent();
+    let mut attempt = 0;
+    loop {
+        let retry = if attempt < policy.retries {
+            request.try_clone()
+        } else {
+            None
+        };
+        let Some(copy) = retry else {
+            return client.execute(request);
+        };
+        let outcome = client.execute(copy);
+        let transient = match &outcome {
+            Ok(response) => idempotent && matches!(response.status().as_u16(), 502..=504),
+            Err(err) => err.is_connect() || (idempotent && err.is_timeout()),
+        };
+        if !transient {
+            return outcome;
+        }
+        let delay = policy.delay(attempt);
+        tracing::debug!(url = %request.url(), attempt, "retrying request in {delay:?}");
+        thread::sleep(delay);
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_delay_doubles_up_to_the_cap() {
+        let policy = RetryPolicy {
+            retries: 5,
+            backoff: Duration::from_millis(500),
+        };
+        assert_eq!(policy.delay(0), Duration::from_millis(500));
+        assert_eq!(policy.delay(1), Duration::from_secs(1));
+        assert_eq!(policy.delay(3), Duration::from_secs(4));
+        assert_eq!(policy.delay(10), MAX_RETRY_DELAY);
+        assert_eq!(policy.delay(40), MAX_RETRY_DELAY);
+    }
+}
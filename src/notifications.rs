@@ -0,0 +1,35 @@
+use std::thread;
+
+const APP_NAME: &str = "hn-tui";
+
+/// Shows a desktop notification through the platform notification service
+/// (D-Bus on Linux and the BSDs, Notification Center on macOS, toasts on
+/// Windows), so it is seen even while the terminal is on another workspace.
+/// Delivery happens on a background thread; failures are only logged.
+pub fn send(summary: &str, body: &str) {
+    let notification = notification(summary, body);
+    thread::spawn(move || {
+        if let Err(err) = notification.show() {
+            tracing::warn!("desktop notification failed: {err}");
+        }
+    });
+}
+
+fn notification(summary: &str, body: &str) -> notify_rust::Notification {
+    let mut notification = notify_rust::Notification::new();
+    notification.appname(APP_NAME).summary(summary).body(body);
+    notification
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notifications_carry_the_app_name_summary_and_body() {
+        let built = notification("3 new comments", "Show HN: A thing");
+        assert_eq!(built.appname, APP_NAME);
+        assert_eq!(built.summary, "3 new comments");
+        assert_eq!(built.body, "Show HN: A thing");
+    }
+}
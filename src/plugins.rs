@@ -0,0 +1,289 @@
+use std::io::{self, Read, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::config::PluginConfig;
+use crate::reddit;
+
+/// Prefix of feed targets served by a plugin: `plugin:<name>/<feed>`.
+pub const FEED_PREFIX: &str = "plugin:";
+
+/// How long a plugin may run before it is killed.
+const TIMEOUT: Duration = Duration::from_secs(10);
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+static PLUGINS: OnceLock<Vec<PluginConfig>> = OnceLock::new();
+
+/// One request per plugin run, written to stdin as a single JSON object.
+#[derive(Serialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+enum Request<'a> {
+    Describe,
+    Action {
+        action: &'a str,
+        post: &'a reddit::Post,
+    },
+    Transform {
+        post: &'a reddit::Post,
+        content: &'a str,
+    },
+    Feed {
+        feed: &'a str,
+        after: Option<&'a str>,
+    },
+}
+
+/// What a plugin offers, from its `describe` response.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Manifest {
+    #[serde(default)]
+    pub actions: Vec<Entry>,
+    #[serde(default)]
+    pub feeds: Vec<Entry>,
+    /// Post bodies are passed through the plugin before rendering.
+    #[serde(default)]
+    pub transform: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Entry {
+    pub id: String,
+    pub label: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ActionResult {
+    /// Shown in the status bar.
+    #[serde(default)]
+    pub status: Option<String>,
+    /// Opened in the browser.
+    #[serde(default)]
+    pub open_url: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TransformResult {
+    content: String,
+}
+
+/// Posts use Reddit's listing fields; `id`, `name`, `title`, `subreddit`,
+/// and `author` are required.
+#[derive(Deserialize)]
+pub struct FeedPage {
+    pub posts: Vec<reddit::Post>,
+    #[serde(default)]
+    pub after: Option<String>,
+}
+
+/// Registers the `plugins` config for every later call. Call once at
+/// startup; later calls are ignored.
+pub fn configure(plugins: &[PluginConfig]) {
+    let plugins = plugins
+        .iter()
+        .filter(|plugin| !plugin.name.trim().is_empty() && !plugin.command.is_empty())
+        .cloned()
+        .collect();
+    let _ = PLUGINS.set(plugins);
+}
+
+/// Names of the configured plugins, in config order.
+pub fn names() -> Vec<String> {
+    PLUGINS
+        .get()
+        .map(|plugins| plugins.iter().map(|plugin| plugin.name.clone()).collect())
+        .unwrap_or_default()
+}
+
+pub fn describe(plugin: &str) -> Result<Manifest> {
+    call(plugin, &Request::Describe)
+}
+
+pub fn run_action(plugin: &str, action: &str, post: &reddit::Post) -> Result<ActionResult> {
+    call(plugin, &Request::Action { action, post })
+}
+
+/// Rewrites the markdown body of `post`. Setting `cancel` kills the plugin.
+pub fn transform(
+    plugin: &str,
+    post: &reddit::Post,
+    content: &str,
+    cancel: &AtomicBool,
+) -> Result<String> {
+    let request = Request::Transform { post, content };
+    let result: TransformResult = call_until(plugin, &request, cancel)?;
+    Ok(result.content)
+}
+
+pub fn feed(plugin: &str, feed: &str, after: Option<&str>) -> Result<FeedPage> {
+    call(plugin, &Request::Feed { feed, after })
+}
+
+/// The feed target string for `feed` from `plugin`.
+pub fn feed_target(plugin: &str, feed: &str) -> String {
+    format!("{FEED_PREFIX}{plugin}/{feed}")
+}
+
+/// Splits a `plugin:<name>/<feed>` target into plugin and feed ids.
+pub fn parse_feed_target(target: &str) -> Option<(&str, &str)> {
+    let rest = target.trim().strip_prefix(FEED_PREFIX)?;
+    let (plugin, feed) = rest.split_once('/')?;
+    let (plugin, feed) = (plugin.trim(), feed.trim());
+    (!plugin.is_empty() && !feed.is_empty()).then_some((plugin, feed))
+}
+
+fn call<T: DeserializeOwned>(plugin: &str, request: &Request<'_>) -> Result<T> {
+    call_until(plugin, request, &AtomicBool::new(false))
+}
+
+fn call_until<T: DeserializeOwned>(
+    plugin: &str,
+    request: &Request<'_>,
+    cancel: &AtomicBool,
+) -> Result<T> {
+    let config = PLUGINS
+        .get()
+        .and_then(|plugins| plugins.iter().find(|candidate| candidate.name == plugin))
+        .ok_or_else(|| anyhow!("plugins: {plugin} is not configured"))?;
+    let payload = serde_json::to_string(request).context("plugins: encode request")?;
+    let output =
+        run(&config.command, payload, cancel).with_context(|| format!("plugins: run {plugin}"))?;
+    parse_response(&output).with_context(|| format!("plugins: {plugin} response"))
+}
+
+/// Runs `command` with `payload` on stdin and returns its stdout. The plugin
+/// is killed after [`TIMEOUT`] or as soon as `cancel` is set.
+fn run(command: &[String], payload: String, cancel: &AtomicBool) -> Result<String> {
+    let (program, args) = command
+        .split_first()
+        .ok_or_else(|| anyhow!("empty command"))?;
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("spawn {program}"))?;
+    let mut stdin = child.stdin.take().context("open plugin stdin")?;
+    let writer = thread::spawn(move || {
+        stdin.write_all(payload.as_bytes())?;
+        stdin.write_all(b"\n")
+    });
+    let stdout = child.stdout.take().context("open plugin stdout")?;
+    let stderr = child.stderr.take().context("open plugin stderr")?;
+    let stdout = thread::spawn(move || read_all(stdout));
+    let stderr = thread::spawn(move || read_all(stderr));
+    let deadline = Instant::now() + TIMEOUT;
+    let status = loop {
+        if let Some(status) = child.try_wait().context("wait for plugin")? {
+            break status;
+        }
+        if cancel.load(Ordering::SeqCst) {
+            kill(&mut child);
+            bail!("{program} cancelled");
+        }
+        if Instant::now() >= deadline {
+            kill(&mut child);
+            bail!("{program} timed out after {}s", TIMEOUT.as_secs());
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+    if let Ok(Err(err)) = writer.join() {
+        if err.kind() != io::ErrorKind::BrokenPipe {
+            return Err(err).context("write plugin request");
+        }
+    }
+    let stdout = stdout.join().unwrap_or_default();
+    if !status.success() {
+        let stderr = stderr.join().unwrap_or_default();
+        let stderr = String::from_utf8_lossy(&stderr);
+        let reason = stderr.lines().find(|line| !line.trim().is_empty());
+        bail!(
+            "{program} exited with {}{}",
+            status,
+            reason
+                .map(|line| format!(": {}", line.trim()))
+                .unwrap_or_default()
+        );
+    }
+    Ok(String::from_utf8_lossy(&stdout).into_owned())
+}
+
+fn read_all(mut reader: impl Read) -> Vec<u8> {
+    let mut buf = Vec::new();
+    let _ = reader.read_to_end(&mut buf);
+    buf
+}
+
+fn kill(child: &mut Child) {
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// A response is one JSON object; `{"error": "..."}` reports a failure.
+fn parse_response<T: DeserializeOwned>(output: &str) -> Result<T> {
+    let value: serde_json::Value = serde_json::from_str(output.trim()).context("invalid JSON")?;
+    if let Some(error) = value.get("error").and_then(|error| error.as_str()) {
+        bail!("{error}");
+    }
+    serde_json::from_value(value).context("unexpected fields")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_are_tagged_by_method() {
+        let encoded = serde_json::to_value(Request::Feed {
+            feed: "starred",
+            after: None,
+        })
+        .unwrap();
+        assert_eq!(
+            encoded,
+            serde_json::json!({"method": "feed", "feed": "starred", "after": null})
+        );
+        let encoded = serde_json::to_value(Request::Describe).unwrap();
+        assert_eq!(encoded, serde_json::json!({"method": "describe"}));
+    }
+
+    #[test]
+    fn responses_report_plugin_errors() {
+        let manifest: Manifest = parse_response(
+            r#"{"actions": [{"id": "sum", "label": "Summarize"}], "transform": true}"#,
+        )
+        .unwrap();
+        assert_eq!(manifest.actions[0].label, "Summarize");
+        assert!(manifest.transform && manifest.feeds.is_empty());
+
+        let err = parse_response::<Manifest>(r#"{"error": "rate limited"}"#).unwrap_err();
+        assert_eq!(err.to_string(), "rate limited");
+        assert!(parse_response::<Manifest>("not json").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn cancelled_plugins_are_killed() {
+        let command = ["sleep".to_string(), "5".to_string()];
+        let started = Instant::now();
+        let err = run(&command, String::new(), &AtomicBool::new(true)).unwrap_err();
+        assert!(err.to_string().contains("cancelled"));
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn feed_targets_round_trip() {
+        let target = feed_target("bookmarks", "starred");
+        assert_eq!(target, "plugin:bookmarks/starred");
+        assert_eq!(parse_feed_target(&target), Some(("bookmarks", "starred")));
+        assert_eq!(parse_feed_target("plugin:bookmarks"), None);
+        assert_eq!(parse_feed_target("r/rust"), None);
+    }
+}
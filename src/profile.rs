@@ -0,0 +1,138 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+
+use anyhow::{bail, Context, Result};
+
+/// Name of the profile that keeps the original, unscoped paths.
+pub const DEFAULT: &str = "default";
+
+static ACTIVE: OnceLock<Option<String>> = OnceLock::new();
+
+/// Selects the profile for every later path lookup. Call once at startup,
+/// before loading config; `None` or `default` selects the default profile.
+pub fn select(name: Option<&str>) -> Result<()> {
+    let name = match name.map(str::trim) {
+        None | Some(DEFAULT) => None,
+        Some(name) => {
+            validate(name)?;
+            Some(name.to_string())
+        }
+    };
+    if ACTIVE.set(name).is_err() {
+        bail!("profile: already selected");
+    }
+    Ok(())
+}
+
+/// Name of the running profile.
+pub fn active() -> &'static str {
+    named().unwrap_or(DEFAULT)
+}
+
+fn named() -> Option<&'static str> {
+    ACTIVE.get().and_then(Option::as_deref)
+}
+
+/// `dir` for the running profile: named profiles live in `profiles/<name>`
+/// below it, the default profile uses `dir` itself.
+pub fn scope(dir: PathBuf) -> PathBuf {
+    scoped(dir, named())
+}
+
+fn scoped(dir: PathBuf, name: Option<&str>) -> PathBuf {
+    match name {
+        Some(name) => dir.join("profiles").join(name),
+        None => dir,
+    }
+}
+
+/// The default profile followed by every profile that has a config file or
+/// a database, plus the running one.
+pub fn list() -> Vec<String> {
+    let mut names: Vec<String> = dirs::config_dir()
+        .map(|dir| {
+            ["hn-tui", "reddix"]
+                .iter()
+                .flat_map(|app| profile_names(&dir.join(app).join("profiles")))
+                .collect()
+        })
+        .unwrap_or_default();
+    names.extend(named().map(str::to_string));
+    names.sort();
+    names.dedup();
+    names.insert(0, DEFAULT.to_string());
+    names
+}
+
+fn profile_names(dir: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| name != DEFAULT && validate(name).is_ok())
+        .collect()
+}
+
+/// Profile names become directory names, so only ASCII letters, digits,
+/// `-`, and `_` are allowed.
+pub fn validate(name: &str) -> Result<()> {
+    if name.is_empty() {
+        bail!("profile: name is empty");
+    }
+    if !name
+        .chars()
+        .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_')
+    {
+        bail!("profile: {name:?} may only contain letters, digits, '-' and '_'");
+    }
+    Ok(())
+}
+
+/// Restarts HN-TUI with `--profile name`. On Unix the current process is
+/// replaced; elsewhere the new one runs to completion and this one exits.
+pub fn relaunch(name: &str) -> Result<()> {
+    validate(name)?;
+    let exe = std::env::current_exe().context("profile: locate executable")?;
+    let mut command = Command::new(exe);
+    command.args(["--profile", name]);
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+
+        let err = command.exec();
+        Err(err).context("profile: relaunch")
+    }
+    #[cfg(not(unix))]
+    {
+        let status = command.status().context("profile: relaunch")?;
+        std::process::exit(status.code().unwrap_or(1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn named_profiles_get_their_own_directory() {
+        let base = PathBuf::from("/home/me/.config/hn-tui");
+        assert_eq!(scoped(base.clone(), None), base);
+        assert_eq!(
+            scoped(base.clone(), Some("work")),
+            base.join("profiles").join("work")
+        );
+    }
+
+    #[test]
+    fn names_must_be_safe_directory_names() {
+        assert!(validate("work").is_ok());
+        assert!(validate("side_project-2").is_ok());
+        assert!(validate("").is_err());
+        assert!(validate("../home").is_err());
+        assert!(validate("a b").is_err());
+    }
+}
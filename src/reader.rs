@@ -0,0 +1,670 @@
+use std::io::Read;
+
+use anyhow::{anyhow, bail, Context, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use reqwest::blocking::Client;
+use reqwest::header::{CONTENT_TYPE, LOCATION};
+use serde::Deserialize;
+use url::Url;
+
+use crate::markdown;
+use crate::net;
+
+const MAX_PAGE_BYTES: u64 = 4 * 1024 * 1024;
+/// Short blocks that are mostly link text are navigation, tag lists, or share
+/// bars rather than article prose.
+const MIN_BLOCK_CHARS: usize = 80;
+const MIN_ARTICLE_CHARS: usize = 200;
+const WAYBACK_BASE: &str = "https://web.archive.org";
+const WAYBACK_AVAILABILITY_URL: &str = "https://archive.org/wayback/available";
+/// Hosts whose links only redirect elsewhere; the links menu expands them.
+const SHORTENER_HOSTS: &[&str] = &[
+    "bit.ly",
+    "buff.ly",
+    "cutt.ly",
+    "dlvr.it",
+    "goo.gl",
+    "is.gd",
+    "ow.ly",
+    "rb.gy",
+    "redd.it",
+    "shorturl.at",
+    "t.co",
+    "t.ly",
+    "tiny.cc",
+    "tinyurl.com",
+    "trib.al",
+];
+/// Shorteners sometimes chain (`t.co` to `bit.ly`); stop following after this many.
+const MAX_SHORTENER_HOPS: usize = 5;
+
+/// Elements that never hold article text. Nested elements of the same name are
+/// rare enough here that a lazy match is good enough.
+static NOISE: Lazy<Vec<Regex>> = Lazy::new(|| {
+    let mut patterns = vec![Regex::new(r"(?s)<!--.*?-->").expect("comment regex")];
+    for tag in [
+        "script", "style", "noscript", "template", "svg", "iframe", "form", "nav", "header",
+        "footer", "aside", "figure", "button", "select",
+    ] {
+        let pattern = format!(r"(?is)<{tag}\b[^>]*>.*?</{tag}\s*>");
+        patterns.push(Regex::new(&pattern).expect("noise regex"));
+    }
+    patterns
+});
+
+static TITLE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?is)<title\b[^>]*>(.*?)</title\s*>").expect("title regex"));
+
+static ATTRIBUTE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?is)([a-z-]+)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+))"#)
+        .expect("attribute regex")
+});
+
+/// An article page reduced to markdown for the Content pane.
+#[derive(Debug, Clone)]
+pub struct Article {
+    pub title: String,
+    pub markdown: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Availability {
+    #[serde(default)]
+    archived_snapshots: ArchivedSnapshots,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ArchivedSnapshots {
+    closest: Option<Snapshot>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Snapshot {
+    #[serde(default)]
+    available: bool,
+    timestamp: String,
+}
+
+/// Downloads `url` and extracts its main text. Fails for non-HTML responses and
+/// for pages where no block of prose could be found.
+pub fn fetch(url: &str) -> Result<Article> {
+    let client = client()?;
+    let (html, base) = download(&client, url)?;
+    extract(&html, &base).ok_or_else(|| anyhow!("reader: no article text found at {url}"))
+}
+
+/// Like [`fetch`], but reads the Wayback Machine's most recent snapshot of
+/// `url`, for links that have since gone dead.
+pub fn fetch_archived(url: &str) -> Result<Article> {
+    let client = client()?;
+    let response = net::send(client.get(WAYBACK_AVAILABILITY_URL).query(&[("url", url)]))
+        .and_then(|response| response.error_for_status())
+        .context("reader: ask the Wayback Machine for a snapshot")?;
+    let availability: Availability = response
+        .json()
+        .context("reader: parse the Wayback Machine response")?;
+    let timestamp = snapshot_timestamp(availability)
+        .ok_or_else(|| anyhow!("reader: the Wayback Machine has no copy of {url}"))?;
+    // `id_` asks for the page as captured, without the archive's toolbar.
+    let snapshot = format!("{WAYBACK_BASE}/web/{timestamp}id_/{url}");
+    let (html, _) = download(&client, &snapshot)?;
+    let base = Url::parse(url).with_context(|| format!("reader: invalid URL {url}"))?;
+    let archived = format!(
+        "archived {} by the Wayback Machine",
+        snapshot_date(&timestamp)
+    );
+    build_article(&html, &base, Some(&archived))
+        .ok_or_else(|| anyhow!("reader: no article text found in the archived copy of {url}"))
+}
+
+/// Browser URL for the Wayback Machine's latest snapshot of `url`.
+pub fn wayback_url(url: &str) -> String {
+    format!("{WAYBACK_BASE}/web/{url}")
+}
+
+/// Whether `url` points at a known link shortener.
+pub fn is_short_link(url: &str) -> bool {
+    Url::parse(url).ok().is_some_and(|url| {
+        url.host_str().is_some_and(|host| {
+            let host = host.trim_start_matches("www.");
+            SHORTENER_HOSTS.contains(&host)
+        })
+    })
+}
+
+/// Follows a shortened link's redirects with HEAD requests and returns where
+/// it ends up, without loading the destination page.
+pub fn expand_short_link(url: &str) -> Result<String> {
+    let client = net::client_builder()
+        .user_agent(format!("hn-tui/{} (reader)", crate::VERSION))
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .context("reader: build HTTP client")?;
+    let mut current = Url::parse(url).with_context(|| format!("reader: invalid link {url}"))?;
+    for _ in 0..MAX_SHORTENER_HOPS {
+        if !is_short_link(current.as_str()) {
+            break;
+        }
+        let response = net::send(client.head(current.clone()))
+            .with_context(|| format!("reader: expand {current}"))?;
+        if !response.status().is_redirection() {
+            break;
+        }
+        let Some(location) = response
+            .headers()
+            .get(LOCATION)
+            .and_then(|value| value.to_str().ok())
+        else {
+            break;
+        };
+        current = current
+            .join(location)
+            .with_context(|| format!("reader: invalid redirect from {current}"))?;
+    }
+    Ok(current.to_string())
+}
+
+/// Whether a [`fetch`] error means the page is gone or unreachable (not found,
+/// server errors, timeouts, refused connections), so an archived copy is worth
+/// offering.
+pub fn is_dead_link(err: &anyhow::Error) -> bool {
+    err.chain()
+        .filter_map(|cause| cause.downcast_ref::<reqwest::Error>())
+        .any(|cause| {
+            cause.is_timeout()
+                || cause.is_connect()
+                || cause.status().is_some_and(|status| {
+                    matches!(status.as_u16(), 404 | 410) || status.is_server_error()
+                })
+        })
+}
+
+fn snapshot_timestamp(availability: Availability) -> Option<String> {
+    availability
+        .archived_snapshots
+        .closest
+        .filter(|snapshot| snapshot.available && !snapshot.timestamp.is_empty())
+        .map(|snapshot| snapshot.timestamp)
+}
+
+/// `20240131120000` → `2024-01-31`.
+fn snapshot_date(timestamp: &str) -> String {
+    match (
+        timestamp.get(0..4),
+        timestamp.get(4..6),
+        timestamp.get(6..8),
+    ) {
+        (Some(year), Some(month), Some(day)) => format!("{year}-{month}-{day}"),
+        _ => timestamp.to_string(),
+    }
+}
+
+fn client() -> Result<Client> {
+    net::client_builder()
+        .user_agent(format!("hn-tui/{} (reader)", crate::VERSION))
+        .build()
+        .context("reader: build HTTP client")
+}
+
+/// The page body as text, plus the URL it was served from after redirects.
+fn download(client: &Client, url: &str) -> Result<(String, Url)> {
+    let response = net::send(client.get(url))
+        .and_then(|response| response.error_for_status())
+        .with_context(|| format!("reader: fetch {url}"))?;
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    if !content_type.is_empty() && !content_type.contains("html") {
+        bail!("reader: {url} is not a web page ({content_type})");
+    }
+    let base = response.url().clone();
+    let mut body = Vec::new();
+    response
+        .take(MAX_PAGE_BYTES)
+        .read_to_end(&mut body)
+        .context("reader: read page")?;
+    Ok((String::from_utf8_lossy(&body).into_owned(), base))
+}
+
+/// Picks the page's main content (the longest `<article>`, else `<main>`, else
+/// `<body>`), drops boilerplate, and converts what is left to markdown.
+pub fn extract(html: &str, base: &Url) -> Option<Article> {
+    build_article(html, base, None)
+}
+
+/// [`extract`], with `archived` describing the snapshot when the page came
+/// from the Wayback Machine.
+fn build_article(html: &str, base: &Url, archived: Option<&str>) -> Option<Article> {
+    let mut cleaned = html.to_string();
+    for pattern in NOISE.iter() {
+        cleaned = pattern.replace_all(&cleaned, " ").into_owned();
+    }
+    let lower = cleaned.to_ascii_lowercase();
+    let region = elements(&cleaned, &lower, "article")
+        .into_iter()
+        .max_by_key(|inner| inner.len())
+        .or_else(|| elements(&cleaned, &lower, "main").into_iter().next())
+        .or_else(|| elements(&cleaned, &lower, "body").into_iter().next())
+        .unwrap_or(&cleaned);
+
+    let mut converter = Converter::new(base);
+    converter.convert(region);
+    let mut blocks = converter.finish();
+
+    let title = TITLE
+        .captures(html)
+        .map(|caps| collapse_whitespace(&markdown::decode_html_entities(&caps[1])))
+        .filter(|title| !title.is_empty())
+        .unwrap_or_else(|| base.host_str().unwrap_or("Article").to_string());
+    // Most pages repeat their <title> as the article's first heading.
+    let repeats_title = blocks.first().is_some_and(|first| {
+        first.starts_with('#') && {
+            let heading = first.trim_start_matches('#').trim();
+            heading.eq_ignore_ascii_case(&title) || title.starts_with(heading)
+        }
+    });
+    if repeats_title {
+        blocks.remove(0);
+    }
+
+    let text_chars: usize = blocks.iter().map(|block| block.chars().count()).sum();
+    if text_chars < MIN_ARTICLE_CHARS {
+        return None;
+    }
+    let source = base
+        .host_str()
+        .unwrap_or_default()
+        .trim_start_matches("www.");
+    let note = match archived {
+        Some(archived) => format!("Reader view of {source}, {archived}"),
+        None => format!("Reader view of {source}"),
+    };
+    let markdown = format!(
+        "# {}\n\n_{note}_\n\n{}\n",
+        escape_markdown(&title),
+        blocks.join("\n\n")
+    );
+    Some(Article { title, markdown })
+}
+
+/// Inner HTML of every outermost `<tag>` element, in document order. `lower`
+/// is `html` lowercased, so byte offsets line up.
+fn elements<'a>(html: &'a str, lower: &str, tag: &str) -> Vec<&'a str> {
+    let open = format!("<{tag}");
+    let close = format!("</{tag}");
+    let mut found = Vec::new();
+    let mut pos = 0;
+    while let Some(start) = find_tag(lower, &open, pos) {
+        let Some(gt) = lower[start..].find('>') else {
+            break;
+        };
+        let inner_start = start + gt + 1;
+        let mut depth = 1;
+        let mut cursor = inner_start;
+        let inner_end = loop {
+            let Some(next_close) = lower[cursor..].find(&close).map(|at| cursor + at) else {
+                break lower.len();
+            };
+            match find_tag(lower, &open, cursor) {
+                Some(nested) if nested < next_close => {
+                    depth += 1;
+                    cursor = nested + open.len();
+                }
+                _ => {
+                    depth -= 1;
+                    cursor = next_close + close.len();
+                    if depth == 0 {
+                        break next_close;
+                    }
+                }
+            }
+        };
+        found.push(&html[inner_start..inner_end]);
+        pos = cursor.max(inner_end);
+    }
+    found
+}
+
+/// Next `<tag` at or after `from` that is the whole tag name, so `<a` does not
+/// match `<abbr`.
+fn find_tag(lower: &str, open: &str, from: usize) -> Option<usize> {
+    let mut pos = from;
+    while let Some(at) = lower.get(pos..)?.find(open).map(|at| pos + at) {
+        let next = lower[at + open.len()..].chars().next();
+        if matches!(next, Some(ch) if ch == '>' || ch == '/' || ch.is_whitespace()) {
+            return Some(at);
+        }
+        pos = at + open.len();
+    }
+    None
+}
+
+fn attribute(attrs: &str, name: &str) -> Option<String> {
+    ATTRIBUTE
+        .captures_iter(attrs)
+        .find(|caps| caps[1].eq_ignore_ascii_case(name))
+        .and_then(|caps| caps.get(2).or(caps.get(3)).or(caps.get(4)))
+        .map(|value| markdown::decode_html_entities(value.as_str()))
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        if matches!(ch, '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+/// Walks the HTML as a flat stream of tags and text, building one markdown
+/// block per paragraph, heading, list item, or code block.
+struct Converter<'a> {
+    base: &'a Url,
+    blocks: Vec<String>,
+    text: String,
+    prefix: String,
+    heading: bool,
+    chars: usize,
+    link_chars: usize,
+    link: Option<(usize, Option<String>)>,
+    lists: Vec<Option<usize>>,
+    quote: usize,
+    code: bool,
+    pre: Option<String>,
+}
+
+impl<'a> Converter<'a> {
+    fn new(base: &'a Url) -> Self {
+        Self {
+            base,
+            blocks: Vec::new(),
+            text: String::new(),
+            prefix: String::new(),
+            heading: false,
+            chars: 0,
+            link_chars: 0,
+            link: None,
+            lists: Vec::new(),
+            quote: 0,
+            code: false,
+            pre: None,
+        }
+    }
+
+    fn convert(&mut self, html: &str) {
+        let mut rest = html;
+        while !rest.is_empty() {
+            let Some(lt) = rest.find('<') else {
+                self.push_text(rest);
+                break;
+            };
+            self.push_text(&rest[..lt]);
+            rest = &rest[lt..];
+            let Some(gt) = rest.find('>') else {
+                self.push_text(rest);
+                break;
+            };
+            let tag = &rest[1..gt];
+            rest = &rest[gt + 1..];
+            let (closing, tag) = match tag.strip_prefix('/') {
+                Some(tag) => (true, tag),
+                None => (false, tag),
+            };
+            let name_end = tag
+                .find(|ch: char| ch.is_whitespace() || ch == '/')
+                .unwrap_or(tag.len());
+            let name = tag[..name_end].to_ascii_lowercase();
+            self.handle_tag(&name, &tag[name_end..], closing);
+        }
+    }
+
+    fn handle_tag(&mut self, name: &str, attrs: &str, closing: bool) {
+        if let Some(code) = self.pre.as_mut() {
+            match (name, closing) {
+                ("pre", true) => {
+                    let code = code.trim_matches('\n').to_string();
+                    self.pre = None;
+                    if !code.trim().is_empty() {
+                        self.blocks.push(format!("```\n{code}\n```"));
+                    }
+                }
+                ("br", _) => code.push('\n'),
+                _ => {}
+            }
+            return;
+        }
+        match name {
+            "p" | "div" | "section" | "article" | "main" | "table" | "tr" | "dl" | "dt" | "dd"
+            | "figcaption" | "hr" => self.flush(),
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.flush();
+                if !closing {
+                    let level = name[1..].parse::<usize>().unwrap_or(2);
+                    self.prefix = format!("{} ", "#".repeat(level));
+                    self.heading = true;
+                }
+            }
+            "ul" | "ol" => {
+                self.flush();
+                if closing {
+                    self.lists.pop();
+                } else if name == "ol" {
+                    let start = attribute(attrs, "start").and_then(|start| start.parse().ok());
+                    self.lists.push(Some(start.unwrap_or(1)));
+                } else {
+                    self.lists.push(None);
+                }
+            }
+            "li" => {
+                self.flush();
+                if !closing {
+                    let indent = "  ".repeat(self.lists.len().saturating_sub(1));
+                    let marker = match self.lists.last_mut() {
+                        Some(Some(next)) => {
+                            *next += 1;
+                            format!("{}.", *next - 1)
+                        }
+                        _ => "-".to_string(),
+                    };
+                    self.prefix = format!("{indent}{marker} ");
+                }
+            }
+            "blockquote" => {
+                self.flush();
+                if closing {
+                    self.quote = self.quote.saturating_sub(1);
+                } else {
+                    self.quote += 1;
+                }
+            }
+            "pre" if !closing => {
+                self.flush();
+                self.pre = Some(String::new());
+            }
+            "br" => {
+                if self.prefix.is_empty() && self.quote == 0 {
+                    self.text.push_str("  \n");
+                } else {
+                    self.text.push(' ');
+                }
+            }
+            "code" => {
+                self.code = !closing;
+                self.text.push('`');
+            }
+            "a" if !closing => {
+                let href = attribute(attrs, "href")
+                    .and_then(|href| self.base.join(href.trim()).ok())
+                    .filter(|url| matches!(url.scheme(), "http" | "https"))
+                    .map(|url| url.to_string());
+                self.link = Some((self.text.len(), href));
+            }
+            "a" => {
+                let Some((start, href)) = self.link.take() else {
+                    return;
+                };
+                let label = self.text.split_off(start.min(self.text.len()));
+                let trimmed = label.trim();
+                self.link_chars += trimmed.chars().count();
+                if label.starts_with(char::is_whitespace) {
+                    self.text.push(' ');
+                }
+                match href {
+                    Some(href) if !trimmed.is_empty() => {
+                        self.text.push_str(&format!("[{trimmed}](<{href}>)"));
+                    }
+                    _ => self.text.push_str(trimmed),
+                }
+                if label.ends_with(char::is_whitespace) && !trimmed.is_empty() {
+                    self.text.push(' ');
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn push_text(&mut self, raw: &str) {
+        if raw.is_empty() {
+            return;
+        }
+        let decoded = markdown::decode_html_entities(raw);
+        if let Some(code) = self.pre.as_mut() {
+            code.push_str(&decoded);
+            return;
+        }
+        for ch in decoded.chars() {
+            if ch.is_whitespace() {
+                if !self.text.is_empty() && !self.text.ends_with([' ', '\n']) {
+                    self.text.push(' ');
+                }
+                continue;
+            }
+            if !self.code && matches!(ch, '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '>') {
+                self.text.push('\\');
+            }
+            self.text.push(ch);
+            self.chars += 1;
+        }
+    }
+
+    fn flush(&mut self) {
+        let text = self.text.trim();
+        let link_heavy = self.link_chars * 2 > self.chars && self.chars < MIN_BLOCK_CHARS;
+        if !text.is_empty() && (self.heading || !link_heavy) {
+            let quote = "> ".repeat(self.quote);
+            self.blocks.push(format!("{quote}{}{text}", self.prefix));
+        }
+        self.text.clear();
+        self.prefix.clear();
+        self.heading = false;
+        self.chars = 0;
+        self.link_chars = 0;
+        self.link = None;
+    }
+
+    fn finish(mut self) -> Vec<String> {
+        self.flush();
+        self.blocks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_keeps_article_prose_and_drops_boilerplate() {
+        let html = r#"<html><head><title>Rust &amp; you</title>
+            <script>var tracking = 1;</script></head>
+            <body><nav><a href="/">Home</a> <a href="/about">About</a></nav>
+            <div class="share"><a href="/tw">Tweet</a></div>
+            <article><h1>Rust &amp; you</h1>
+            <p>Ownership is the feature that sets Rust apart. It lets the compiler
+            check memory safety without a garbage collector, as the
+            <a href="/book/ch04">book chapter</a> explains in detail.</p>
+            <h2>Borrowing</h2>
+            <ul><li>Shared references are <code>&amp;T</code> and may be copied freely.</li>
+            <li>Mutable references are exclusive for as long as they live.</li></ul>
+            <pre>fn main() {
+    println!("hi");
+}</pre></article>
+            <footer>Copyright</footer></body></html>"#;
+        let base = Url::parse("https://example.com/posts/rust").unwrap();
+        let article = extract(html, &base).expect("article");
+
+        assert_eq!(article.title, "Rust & you");
+        let markdown = article.markdown;
+        assert!(markdown.starts_with("# Rust & you\n\n_Reader view of example.com_"));
+        assert!(markdown.contains("[book chapter](<https://example.com/book/ch04>) explains"));
+        assert!(markdown.contains("## Borrowing"));
+        assert!(markdown.contains("- Shared references are `&T` and may be copied freely."));
+        assert!(markdown.contains("```\nfn main() {\n    println!(\"hi\");\n}\n```"));
+        for noise in [
+            "tracking",
+            "Home",
+            "Tweet",
+            "Copyright",
+            "# Rust & you\n\n# Rust",
+        ] {
+            assert!(!markdown.contains(noise), "{noise} leaked into {markdown}");
+        }
+    }
+
+    #[test]
+    fn extract_gives_up_on_pages_without_prose() {
+        let base = Url::parse("https://example.com/").unwrap();
+        let html =
+            "<body><ul><li><a href='/a'>One</a></li><li><a href='/b'>Two</a></li></ul></body>";
+        assert!(extract(html, &base).is_none());
+    }
+
+    #[test]
+    fn wayback_availability_picks_the_closest_snapshot() {
+        let json = r#"{"url":"example.com/gone","archived_snapshots":{"closest":{
+            "status":"200","available":true,
+            "url":"http://web.archive.org/web/20240131120000/https://example.com/gone",
+            "timestamp":"20240131120000"}}}"#;
+        let availability: Availability = serde_json::from_str(json).unwrap();
+        let timestamp = snapshot_timestamp(availability).expect("snapshot");
+        assert_eq!(timestamp, "20240131120000");
+        assert_eq!(snapshot_date(&timestamp), "2024-01-31");
+
+        let missing: Availability =
+            serde_json::from_str(r#"{"url":"example.com/x","archived_snapshots":{}}"#).unwrap();
+        assert_eq!(snapshot_timestamp(missing), None);
+        assert_eq!(
+            wayback_url("https://example.com/gone"),
+            "https://web.archive.org/web/https://example.com/gone"
+        );
+    }
+
+    #[test]
+    fn short_links_are_recognised_by_host() {
+        assert!(is_short_link("https://bit.ly/3abcDEF"));
+        assert!(is_short_link("http://www.tinyurl.com/xyz"));
+        assert!(is_short_link("https://t.co/abc"));
+        assert!(!is_short_link("https://example.com/t.co"));
+        assert!(!is_short_link("https://notbit.ly/abc"));
+        assert!(!is_short_link("not a url"));
+    }
+
+    #[test]
+    fn elements_match_nested_tags_of_the_same_name() {
+        let html = "<div><article>a<article>b</article>c</article><articles>x</articles></div>";
+        let lower = html.to_ascii_lowercase();
+        assert_eq!(
+            elements(html, &lower, "article"),
+            ["a<article>b</article>c"]
+        );
+    }
+}
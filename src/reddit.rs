@@ -1,8 +1,12 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, Cursor, Read};
+use std::path::Path;
 use std::sync::Arc;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use anyhow::{anyhow, bail, Context, Result};
-use reqwest::blocking::{Client as HttpClient, Response};
+use reqwest::blocking::{Body, Client as HttpClient, Response};
 use reqwest::header::{HeaderMap, AUTHORIZATION, CONTENT_TYPE, USER_AGENT};
 use reqwest::Method;
 use serde::de::DeserializeOwned;
@@ -11,7 +15,16 @@ use serde_json::{self, Value};
 use std::sync::RwLock;
 use url::Url;
 
+use crate::net;
+
 pub const DEFAULT_BASE_URL: &str = "https://oauth.reddit.com/";
+const REQUEST_LOG_CAPACITY: usize = 200;
+/// Uploads go straight to Reddit's media bucket and can take far longer than
+/// an API call on a slow link.
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Called with bytes sent and total bytes as an upload progresses.
+pub type UploadProgress = Arc<dyn Fn(u64, u64) + Send + Sync>;
 
 pub trait TokenProvider: Send + Sync {
     fn token(&self) -> Result<OAuthToken>;
@@ -68,7 +81,7 @@ pub enum SortOption {
 }
 
 impl SortOption {
-    fn as_str(&self) -> &'static str {
+    pub fn as_str(&self) -> &'static str {
         match self {
             SortOption::Hot => "hot",
             SortOption::New => "new",
@@ -92,7 +105,21 @@ pub enum CommentSortOption {
 }
 
 impl CommentSortOption {
-    fn as_str(&self) -> &'static str {
+    /// Parses a post's `suggested_sort`. Sorts the client can't request, like
+    /// `live` or `random`, yield `None`.
+    pub fn from_suggestion(value: &str) -> Option<Self> {
+        match value.trim() {
+            "confidence" => Some(CommentSortOption::Confidence),
+            "top" => Some(CommentSortOption::Top),
+            "new" => Some(CommentSortOption::New),
+            "controversial" => Some(CommentSortOption::Controversial),
+            "old" => Some(CommentSortOption::Old),
+            "qa" => Some(CommentSortOption::Qa),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
         match self {
             CommentSortOption::Confidence => "confidence",
             CommentSortOption::Top => "top",
@@ -129,6 +156,9 @@ pub struct Client {
     user_agent: String,
     base_url: Url,
     rate: RwLock<RateLimit>,
+    requests: RequestLog,
+    /// The token's account name, looked up once.
+    username: RwLock<Option<String>>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -138,6 +168,47 @@ pub struct RateLimit {
     pub reset_at: Option<SystemTime>,
 }
 
+#[derive(Debug, Clone)]
+pub struct RequestRecord {
+    pub at: SystemTime,
+    pub method: String,
+    pub endpoint: String,
+    /// `None` when the request failed before a response arrived.
+    pub status: Option<u16>,
+    pub latency: Duration,
+    pub rate_remaining: Option<f64>,
+    pub error: Option<String>,
+}
+
+/// Recent API calls made by a client, shared with the UI's network inspector.
+#[derive(Debug, Clone, Default)]
+pub struct RequestLog {
+    entries: Arc<RwLock<VecDeque<RequestRecord>>>,
+}
+
+impl RequestLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, record: RequestRecord) {
+        let mut entries = self.entries.write().unwrap();
+        if entries.len() >= REQUEST_LOG_CAPACITY {
+            entries.pop_front();
+        }
+        entries.push_back(record);
+    }
+
+    /// Returns the recorded requests, newest first.
+    pub fn snapshot(&self) -> Vec<RequestRecord> {
+        self.entries.read().unwrap().iter().rev().cloned().collect()
+    }
+
+    pub fn clear(&self) {
+        self.entries.write().unwrap().clear();
+    }
+}
+
 impl Client {
     pub fn new(token_provider: Arc<dyn TokenProvider>, config: ClientConfig) -> Result<Self> {
         if config.user_agent.trim().is_empty() {
@@ -149,9 +220,7 @@ impl Client {
         let base_url = Url::parse(&base)?;
         let http = match config.http_client {
             Some(client) => client,
-            None => HttpClient::builder()
-                .timeout(Duration::from_secs(20))
-                .build()?,
+            None => net::client_builder().build()?,
         };
 
         Ok(Client {
@@ -160,6 +229,8 @@ impl Client {
             user_agent: config.user_agent,
             base_url,
             rate: RwLock::new(RateLimit::default()),
+            requests: RequestLog::new(),
+            username: RwLock::new(None),
         })
     }
 
@@ -167,6 +238,10 @@ impl Client {
         self.rate.read().unwrap().clone()
     }
 
+    pub fn request_log(&self) -> RequestLog {
+        self.requests.clone()
+    }
+
     pub fn subreddit_listing(
         &self,
         subreddit: &str,
@@ -201,6 +276,71 @@ impl Client {
         self.fetch_listing(&path, opts)
     }
 
+    /// One of the signed-in account's own listings, newest first. Saved
+    /// comments arrive as [`Post`]s built by [`SavedComment::into_post`].
+    pub fn account_listing(
+        &self,
+        listing: AccountListing,
+        opts: ListingOptions,
+    ) -> Result<Listing<Post>> {
+        let username = self.username()?;
+        let path = format!("/user/{}/{}.json", username, listing.as_str());
+        let params = opts.into_params();
+        let resp = self.request(Method::GET, &path, &params, None)?;
+        let listing: ListingEnvelope<Value> = resp.json()?;
+        let children = listing
+            .data
+            .children
+            .into_iter()
+            .filter_map(account_thing)
+            .collect();
+        Ok(Listing {
+            after: listing.data.after,
+            before: listing.data.before,
+            children,
+        })
+    }
+
+    /// Name of the account the token belongs to.
+    pub fn username(&self) -> Result<String> {
+        if let Some(name) = self.username.read().unwrap().clone() {
+            return Ok(name);
+        }
+        self.me().and_then(|me| sanitize_username(&me.name))
+    }
+
+    /// Karma, cake day, and trophies of the account the token belongs to.
+    pub fn account_overview(&self) -> Result<AccountOverview> {
+        let me = self.me().context("reddit: parse account")?;
+        let resp = self.request(Method::GET, "/api/v1/me/trophies", &[], None)?;
+        let trophies: TrophyListEnvelope = resp.json().context("reddit: parse trophies")?;
+        Ok(AccountOverview {
+            name: sanitize_username(&me.name)?,
+            link_karma: me.link_karma,
+            comment_karma: me.comment_karma,
+            created_utc: me.created_utc,
+            trophies: trophies
+                .data
+                .trophies
+                .into_iter()
+                .map(|trophy| trophy.data.name)
+                .filter(|name| !name.trim().is_empty())
+                .collect(),
+        })
+    }
+
+    /// Fetches `/api/v1/me`, remembering the account name for [`username`].
+    ///
+    /// [`username`]: Client::username
+    fn me(&self) -> Result<Me> {
+        let resp = self.request(Method::GET, "/api/v1/me", &[], None)?;
+        let me: Me = resp.json()?;
+        if let Ok(name) = sanitize_username(&me.name) {
+            *self.username.write().unwrap() = Some(name);
+        }
+        Ok(me)
+    }
+
     pub fn search_posts(
         &self,
         query: &str,
@@ -263,6 +403,30 @@ impl Client {
         self.fetch_listing(&path, opts)
     }
 
+    /// Subreddit names matching a typed prefix, as suggested by Reddit's search box.
+    pub fn subreddit_autocomplete(
+        &self,
+        query: &str,
+        include_over_18: bool,
+        limit: u32,
+    ) -> Result<Vec<Subreddit>> {
+        let params = vec![
+            ("query".to_string(), query.to_string()),
+            ("limit".to_string(), limit.to_string()),
+            ("include_profiles".to_string(), "false".to_string()),
+            ("include_over_18".to_string(), include_over_18.to_string()),
+            ("typeahead_active".to_string(), "true".to_string()),
+        ];
+        let resp = self.request(Method::GET, "/api/subreddit_autocomplete_v2", &params, None)?;
+        let listing: ListingEnvelope<Subreddit> = resp.json()?;
+        Ok(listing
+            .data
+            .children
+            .into_iter()
+            .map(|thing| thing.data)
+            .collect())
+    }
+
     pub fn vote(&self, fullname: &str, dir: i32) -> Result<()> {
         if !(-1..=1).contains(&dir) {
             bail!("reddit: vote direction must be -1, 0, or 1");
@@ -318,6 +482,10 @@ impl Client {
     }
 
     pub fn is_subscribed(&self, subreddit: &str) -> Result<bool> {
+        Ok(self.subreddit_about(subreddit)?.user_is_subscriber)
+    }
+
+    pub fn subreddit_about(&self, subreddit: &str) -> Result<SubredditAbout> {
         let name = subreddit.trim().trim_start_matches("r/");
         if name.is_empty() {
             bail!("reddit: subreddit name required");
@@ -325,7 +493,49 @@ impl Client {
         let path = format!("/r/{}/about.json", name);
         let resp = self.request(Method::GET, &path, &[], None)?;
         let about: SubredditAboutEnvelope = resp.json()?;
-        Ok(about.data.user_is_subscriber)
+        Ok(about.data)
+    }
+
+    /// Resolves `/r/random` (or `/r/randnsfw`) to the subreddit Reddit redirects to.
+    pub fn random_subreddit(&self, nsfw: bool) -> Result<String> {
+        let path = if nsfw {
+            "/r/randnsfw/about.json"
+        } else {
+            "/r/random/about.json"
+        };
+        let resp = self.request(Method::GET, path, &[], None)?;
+        if let Some(name) = subreddit_from_url(resp.url()) {
+            return Ok(name);
+        }
+        let about: SubredditAboutEnvelope = resp.json()?;
+        let name = about.data.display_name.trim();
+        if name.is_empty() {
+            bail!("reddit: random subreddit did not resolve");
+        }
+        Ok(format!("r/{name}"))
+    }
+
+    /// Unread private messages and comment replies, newest first.
+    pub fn unread_messages(&self, limit: u32) -> Result<Vec<Message>> {
+        let opts = ListingOptions {
+            limit: Some(limit),
+            ..Default::default()
+        };
+        let listing: Listing<Message> = self.fetch_listing("/message/unread", opts)?;
+        Ok(listing
+            .children
+            .into_iter()
+            .map(|thing| thing.data)
+            .collect())
+    }
+
+    pub fn mark_messages_read(&self, fullnames: &[String]) -> Result<()> {
+        if fullnames.is_empty() {
+            return Ok(());
+        }
+        let form = vec![("id".to_string(), fullnames.join(","))];
+        self.request(Method::POST, "/api/read_message", &[], Some(form))?;
+        Ok(())
     }
 
     pub fn reply(&self, parent: &str, text: &str) -> Result<Comment> {
@@ -361,6 +571,137 @@ impl Client {
         Ok(comment)
     }
 
+    /// Uploads `path` through a media asset lease and submits it to
+    /// `subreddit` as an image post titled `title`. Reddit rejects a link
+    /// that was already posted there unless `resubmit` is set.
+    pub fn submit_image(
+        &self,
+        subreddit: &str,
+        title: &str,
+        path: &Path,
+        resubmit: bool,
+        progress: UploadProgress,
+    ) -> Result<()> {
+        let name = subreddit.trim().trim_start_matches("r/");
+        if name.is_empty() {
+            bail!("reddit: subreddit name required");
+        }
+        if title.trim().is_empty() {
+            bail!("reddit: post title is required");
+        }
+        let image_url = self.upload_media(path, progress)?;
+        let form = vec![
+            ("sr".to_string(), name.to_string()),
+            ("kind".to_string(), "image".to_string()),
+            ("title".to_string(), title.trim().to_string()),
+            ("url".to_string(), image_url),
+            ("resubmit".to_string(), resubmit.to_string()),
+            ("sendreplies".to_string(), "true".to_string()),
+            ("api_type".to_string(), "json".to_string()),
+        ];
+        let resp = self.request(Method::POST, "/api/submit", &[], Some(form))?;
+        let payload: SubmitResponse = resp.json()?;
+        if let Some(err) = payload.json.errors.first() {
+            let joined = err
+                .iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            bail!("reddit: submit error: {}", joined);
+        }
+        Ok(())
+    }
+
+    /// Leases an upload slot for `path`, sends the file to it, and returns
+    /// the URL Reddit expects in an image submission.
+    fn upload_media(&self, path: &Path, progress: UploadProgress) -> Result<String> {
+        let mime = image_mime_type(path).ok_or_else(|| {
+            anyhow!(
+                "reddit: {} is not a PNG, JPEG, GIF, or WebP image",
+                path.display()
+            )
+        })?;
+        let file_name = path
+            .file_name()
+            .map(|name| multipart_file_name(&name.to_string_lossy()))
+            .ok_or_else(|| anyhow!("reddit: {} has no file name", path.display()))?;
+        let file = File::open(path).with_context(|| format!("reddit: open {}", path.display()))?;
+        let size = file
+            .metadata()
+            .with_context(|| format!("reddit: read {}", path.display()))?
+            .len();
+
+        let form = vec![
+            ("filepath".to_string(), file_name.clone()),
+            ("mimetype".to_string(), mime.to_string()),
+        ];
+        let resp = self.request(Method::POST, "/api/media/asset.json", &[], Some(form))?;
+        let lease: MediaLease = resp.json().context("reddit: parse media lease")?;
+        let action = if lease.args.action.starts_with("//") {
+            format!("https:{}", lease.args.action)
+        } else {
+            lease.args.action.clone()
+        };
+        let key = lease
+            .args
+            .fields
+            .iter()
+            .find(|field| field.name == "key")
+            .map(|field| field.value.clone())
+            .ok_or_else(|| anyhow!("reddit: media lease has no upload key"))?;
+
+        let boundary = format!(
+            "hn-tui-{:x}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos()
+        );
+        let mut head = Vec::new();
+        for field in &lease.args.fields {
+            head.extend_from_slice(
+                format!(
+                    "--{boundary}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                    field.name, field.value
+                )
+                .as_bytes(),
+            );
+        }
+        head.extend_from_slice(
+            format!(
+                "--{boundary}\r\nContent-Disposition: form-data; name=\"file\"; \
+                 filename=\"{file_name}\"\r\nContent-Type: {mime}\r\n\r\n"
+            )
+            .as_bytes(),
+        );
+        let tail = format!("\r\n--{boundary}--\r\n").into_bytes();
+        let total = head.len() as u64 + size + tail.len() as u64;
+        let reader = ProgressReader {
+            inner: Cursor::new(head).chain(file).chain(Cursor::new(tail)),
+            sent: 0,
+            total,
+            reported: None,
+            progress,
+        };
+
+        let req = self
+            .http
+            .post(&action)
+            .timeout(UPLOAD_TIMEOUT)
+            .header(USER_AGENT, self.user_agent.clone())
+            .header(
+                CONTENT_TYPE,
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(Body::sized(reader, total));
+        let resp = net::send(req).context("reddit: upload image")?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            bail!("reddit: image upload rejected ({status})");
+        }
+        Ok(format!("{action}/{key}"))
+    }
+
     fn fetch_listing<T>(&self, path: &str, opts: ListingOptions) -> Result<Listing<T>>
     where
         T: DeserializeOwned,
@@ -389,6 +730,8 @@ impl Client {
             }
         }
 
+        let method_label = method.to_string();
+        let endpoint = url.path().to_string();
         let mut req = self.http.request(method, url);
         let auth_value = format!("Bearer {}", token.access_token);
         req = req.header(USER_AGENT, self.user_agent.clone());
@@ -398,8 +741,48 @@ impl Client {
             req = req.form(&form_data);
         }
 
-        let resp = req.send()?;
+        let started = Instant::now();
+        let resp = match net::send(req) {
+            Ok(resp) => resp,
+            Err(err) => {
+                tracing::warn!(
+                    method = %method_label,
+                    endpoint = %endpoint,
+                    "reddit api request failed: {err}"
+                );
+                self.requests.record(RequestRecord {
+                    at: SystemTime::now(),
+                    method: method_label,
+                    endpoint,
+                    status: None,
+                    latency: started.elapsed(),
+                    rate_remaining: None,
+                    error: Some(err.to_string()),
+                });
+                return Err(err.into());
+            }
+        };
         self.capture_rate(resp.headers());
+        tracing::debug!(
+            method = %method_label,
+            endpoint = %endpoint,
+            status = resp.status().as_u16(),
+            latency_ms = started.elapsed().as_millis() as u64,
+            "reddit api request"
+        );
+        self.requests.record(RequestRecord {
+            at: SystemTime::now(),
+            method: method_label,
+            endpoint,
+            status: Some(resp.status().as_u16()),
+            latency: started.elapsed(),
+            rate_remaining: resp
+                .headers()
+                .get("x-ratelimit-remaining")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|s| s.parse::<f64>().ok()),
+            error: None,
+        });
         if resp.status().is_success() {
             Ok(resp)
         } else {
@@ -475,6 +858,12 @@ pub struct Post {
     pub thumbnail: String,
     #[serde(default)]
     pub stickied: bool,
+    /// Moderators closed the thread to new comments and votes.
+    #[serde(default)]
+    pub locked: bool,
+    /// Older than Reddit's archive cutoff; no new comments or votes.
+    #[serde(default)]
+    pub archived: bool,
     #[serde(default)]
     pub over_18: bool,
     #[serde(default)]
@@ -495,6 +884,19 @@ pub struct Post {
     pub gallery_data: Option<GalleryData>,
     #[serde(default)]
     pub media_metadata: Option<std::collections::HashMap<String, MediaMetadata>>,
+    /// Comment sort chosen by the subreddit's moderators, e.g. `"new"` or `"qa"`.
+    #[serde(default)]
+    pub suggested_sort: Option<String>,
+    /// Link host such as `github.com`, or `self.<subreddit>` for text posts.
+    #[serde(default)]
+    pub domain: String,
+    #[serde(default)]
+    pub link_flair_text: Option<String>,
+    #[serde(default)]
+    pub total_awards_received: i64,
+    /// Share of votes that are upvotes, from 0.0 to 1.0.
+    #[serde(default)]
+    pub upvote_ratio: Option<f64>,
 }
 
 impl Post {
@@ -601,6 +1003,10 @@ pub struct GalleryItem {
     pub id: i64,
     #[serde(rename = "media_id")]
     pub media_id: String,
+    #[serde(default)]
+    pub caption: Option<String>,
+    #[serde(default)]
+    pub outbound_url: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -651,6 +1057,21 @@ pub struct Comment {
     pub depth: i64,
     #[serde(default)]
     pub created_utc: f64,
+    /// 1 when Reddit flags the comment as controversial (many up and down votes).
+    #[serde(default)]
+    pub controversiality: i64,
+    /// When the comment was last edited. Reddit sends `false` for unedited
+    /// comments and `true` (no timestamp, stored as 0) for some very old ones.
+    #[serde(default)]
+    pub edited: Option<f64>,
+    /// Pinned to the top of the thread by a moderator.
+    #[serde(default)]
+    pub stickied: bool,
+    #[serde(default)]
+    pub gilded: i64,
+    /// Images the body refers to, such as emotes, keyed by media id.
+    #[serde(default)]
+    pub media_metadata: Option<std::collections::HashMap<String, MediaMetadata>>,
     #[serde(default)]
     pub replies: Option<Box<Listing<Comment>>>,
 }
@@ -679,6 +1100,16 @@ impl<'de> Deserialize<'de> for Comment {
             #[serde(default)]
             created_utc: f64,
             #[serde(default)]
+            controversiality: i64,
+            #[serde(default)]
+            edited: serde_json::Value,
+            #[serde(default)]
+            stickied: bool,
+            #[serde(default)]
+            gilded: i64,
+            #[serde(default)]
+            media_metadata: Option<std::collections::HashMap<String, MediaMetadata>>,
+            #[serde(default)]
             replies: serde_json::Value,
         }
 
@@ -690,6 +1121,11 @@ impl<'de> Deserialize<'de> for Comment {
                 .ok()
                 .map(|listing| Box::new(listing.data))
         };
+        let edited = match helper.edited {
+            serde_json::Value::Number(at) => at.as_f64(),
+            serde_json::Value::Bool(true) => Some(0.0),
+            _ => None,
+        };
         Ok(Comment {
             id: helper.id,
             name: helper.name,
@@ -700,6 +1136,11 @@ impl<'de> Deserialize<'de> for Comment {
             score_hidden: helper.score_hidden,
             depth: helper.depth,
             created_utc: helper.created_utc,
+            controversiality: helper.controversiality,
+            edited,
+            stickied: helper.stickied,
+            gilded: helper.gilded,
+            media_metadata: helper.media_metadata,
             replies,
         })
     }
@@ -729,10 +1170,77 @@ struct SubredditAboutEnvelope {
     data: SubredditAbout,
 }
 
+/// An inbox item: a private message (`t4_`) or a reply to the user (`t1_`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Message {
+    pub name: String,
+    #[serde(default)]
+    pub author: String,
+    #[serde(default)]
+    pub subject: String,
+    #[serde(default)]
+    pub body: String,
+    #[serde(default)]
+    pub created_utc: f64,
+    /// Permalink to the comment in its thread; empty for private messages.
+    #[serde(default)]
+    pub context: String,
+    #[serde(default)]
+    pub was_comment: bool,
+    #[serde(default)]
+    pub subreddit: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SubredditAbout {
+    #[serde(default)]
+    pub display_name: String,
     #[serde(default)]
     pub user_is_subscriber: bool,
+    #[serde(default)]
+    pub icon_img: Option<String>,
+    #[serde(default)]
+    pub community_icon: Option<String>,
+    #[serde(default)]
+    pub banner_img: Option<String>,
+    #[serde(default)]
+    pub banner_background_image: Option<String>,
+}
+
+/// Extracts `r/<name>` from a redirected `/r/<name>/...` URL, ignoring the
+/// `random` aliases themselves.
+fn subreddit_from_url(url: &Url) -> Option<String> {
+    let mut segments = url.path_segments()?;
+    if segments.next()? != "r" {
+        return None;
+    }
+    let name = segments.next()?.trim();
+    if name.is_empty()
+        || name.eq_ignore_ascii_case("random")
+        || name.eq_ignore_ascii_case("randnsfw")
+    {
+        return None;
+    }
+    Some(format!("r/{name}"))
+}
+
+impl SubredditAbout {
+    /// The community icon, falling back to the legacy `icon_img`.
+    pub fn icon_url(&self) -> Option<String> {
+        first_image_url([&self.community_icon, &self.icon_img])
+    }
+
+    pub fn banner_url(&self) -> Option<String> {
+        first_image_url([&self.banner_background_image, &self.banner_img])
+    }
+}
+
+fn first_image_url<const N: usize>(candidates: [&Option<String>; N]) -> Option<String> {
+    candidates
+        .into_iter()
+        .filter_map(|value| value.as_deref())
+        .map(|value| crate::markdown::decode_html_entities(value.trim()))
+        .find(|value| value.starts_with("http"))
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -752,12 +1260,267 @@ impl SubredditSource {
     }
 }
 
+/// Listings only the account owner can read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AccountListing {
+    Submitted,
+    Comments,
+    Saved,
+    Upvoted,
+    Downvoted,
+}
+
+impl AccountListing {
+    pub const ALL: [AccountListing; 5] = [
+        AccountListing::Submitted,
+        AccountListing::Comments,
+        AccountListing::Saved,
+        AccountListing::Upvoted,
+        AccountListing::Downvoted,
+    ];
+
+    /// Path segment under `/user/<name>/`, also used as the feed target.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AccountListing::Submitted => "submitted",
+            AccountListing::Comments => "comments",
+            AccountListing::Saved => "saved",
+            AccountListing::Upvoted => "upvoted",
+            AccountListing::Downvoted => "downvoted",
+        }
+    }
+
+    pub fn from_target(target: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|listing| target.trim().eq_ignore_ascii_case(listing.as_str()))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct ListingEnvelope<T> {
     kind: String,
     data: Listing<T>,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+struct Me {
+    name: String,
+    #[serde(default)]
+    link_karma: i64,
+    #[serde(default)]
+    comment_karma: i64,
+    #[serde(default)]
+    created_utc: f64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TrophyListEnvelope {
+    data: TrophyList,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct TrophyList {
+    #[serde(default)]
+    trophies: Vec<TrophyEnvelope>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TrophyEnvelope {
+    data: Trophy,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Trophy {
+    #[serde(default)]
+    name: String,
+}
+
+/// Profile summary shown next to a saved account in the accounts menu.
+#[derive(Debug, Clone, Default)]
+pub struct AccountOverview {
+    pub name: String,
+    pub link_karma: i64,
+    pub comment_karma: i64,
+    pub created_utc: f64,
+    pub trophies: Vec<String>,
+}
+
+/// A comment in the saved listing, which carries its thread's title and link.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SavedComment {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub body: String,
+    #[serde(default)]
+    pub author: String,
+    #[serde(default)]
+    pub subreddit: String,
+    #[serde(default)]
+    pub permalink: String,
+    #[serde(default)]
+    pub link_id: String,
+    #[serde(default)]
+    pub link_title: String,
+    #[serde(default)]
+    pub link_permalink: String,
+    #[serde(default)]
+    pub score: i64,
+    #[serde(default)]
+    pub likes: Option<bool>,
+    #[serde(default)]
+    pub num_comments: i64,
+    #[serde(default)]
+    pub created_utc: f64,
+    #[serde(default)]
+    pub over_18: bool,
+}
+
+impl SavedComment {
+    /// Shows the comment as a text post titled after its thread. The post
+    /// keeps the comment's fullname, so voting and unsaving act on the
+    /// comment, while its id is the thread's so the comments pane loads the
+    /// discussion it came from.
+    pub fn into_post(self) -> Post {
+        let thread = if self.link_permalink.is_empty() {
+            let path = self.permalink.trim_end_matches('/');
+            let parent = path.rsplit_once('/').map_or(path, |(parent, _)| parent);
+            format!("https://www.reddit.com{parent}/")
+        } else {
+            self.link_permalink
+        };
+        let link_title = self.link_title.trim().to_string();
+        Post {
+            id: self.link_id.trim_start_matches("t3_").to_string(),
+            name: self.name,
+            title: format!("Comment on \"{link_title}\""),
+            subreddit: self.subreddit,
+            author: self.author,
+            selftext: format!(
+                "{}\n\n[Parent post: {link_title}]({thread})",
+                self.body.trim()
+            ),
+            url: thread,
+            permalink: self.permalink,
+            score: self.score,
+            likes: self.likes,
+            num_comments: self.num_comments,
+            created_utc: self.created_utc,
+            thumbnail: String::new(),
+            stickied: false,
+            locked: false,
+            archived: false,
+            over_18: self.over_18,
+            spoiler: false,
+            post_hint: String::new(),
+            is_video: false,
+            media: None,
+            secure_media: None,
+            crosspost_parent_list: vec![],
+            preview: Default::default(),
+            gallery_data: None,
+            media_metadata: None,
+            suggested_sort: None,
+            domain: "reddit.com".to_string(),
+            link_flair_text: None,
+            total_awards_received: 0,
+            upvote_ratio: None,
+        }
+    }
+}
+
+/// Keeps posts as they are and turns saved comments into posts; anything
+/// that fails to parse is skipped rather than failing the page.
+fn account_thing(thing: Thing<Value>) -> Option<Thing<Post>> {
+    let data = match thing.kind.as_str() {
+        "t3" => serde_json::from_value::<Post>(thing.data),
+        "t1" => serde_json::from_value::<SavedComment>(thing.data).map(SavedComment::into_post),
+        _ => return None,
+    };
+    match data {
+        Ok(data) => Some(Thing {
+            kind: "t3".to_string(),
+            data,
+        }),
+        Err(err) => {
+            tracing::debug!(kind = %thing.kind, "skipping account listing item: {err}");
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct SubmitResponse {
+    json: SubmitResponseBody,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct SubmitResponseBody {
+    #[serde(default)]
+    errors: Vec<Vec<serde_json::Value>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MediaLease {
+    args: UploadArgs,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct UploadArgs {
+    action: String,
+    fields: Vec<UploadField>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct UploadField {
+    name: String,
+    value: String,
+}
+
+/// Counts bytes as reqwest pulls the upload body and reports each whole
+/// percent of progress once.
+struct ProgressReader<R> {
+    inner: R,
+    sent: u64,
+    total: u64,
+    reported: Option<u64>,
+    progress: UploadProgress,
+}
+
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.sent += read as u64;
+        let percent = self.sent * 100 / self.total.max(1);
+        if self.reported != Some(percent) {
+            self.reported = Some(percent);
+            (self.progress)(self.sent, self.total);
+        }
+        Ok(read)
+    }
+}
+
+/// MIME type Reddit accepts for an image post, judged by file extension.
+pub fn image_mime_type(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    match extension.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
+
+/// `name` with the characters that would end a quoted multipart header
+/// value removed.
+fn multipart_file_name(name: &str) -> String {
+    name.chars()
+        .filter(|ch| !matches!(ch, '"' | '\r' | '\n'))
+        .collect()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct CommentResponse {
     json: CommentResponseBody,
@@ -773,3 +1536,29 @@ struct CommentResponseBody {
 struct CommentResponseData {
     things: Vec<Thing<Comment>>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_redirects_resolve_to_the_landing_subreddit() {
+        let url = |raw: &str| Url::parse(raw).unwrap();
+        assert_eq!(
+            subreddit_from_url(&url("https://oauth.reddit.com/r/rust/about.json")),
+            Some("r/rust".to_string())
+        );
+        assert_eq!(
+            subreddit_from_url(&url("https://oauth.reddit.com/r/random/about.json")),
+            None
+        );
+        assert_eq!(
+            subreddit_from_url(&url("https://oauth.reddit.com/r/RandNSFW/about.json")),
+            None
+        );
+        assert_eq!(
+            subreddit_from_url(&url("https://oauth.reddit.com/user/someone")),
+            None
+        );
+    }
+}
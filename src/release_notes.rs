@@ -1,7 +1,13 @@
+use anyhow::{bail, Context, Result};
 use once_cell::sync::Lazy;
 use semver::Version;
 use serde::Deserialize;
 
+use crate::net;
+
+const RELEASE_LIST_URL: &str =
+    "https://api.github.com/repos/danielmerja/hn-tui/releases?per_page=50";
+
 #[derive(Clone, Debug)]
 pub struct ReleaseNote {
     pub version: Version,
@@ -12,6 +18,27 @@ pub struct ReleaseNote {
     pub release_url: String,
 }
 
+impl ReleaseNote {
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("## {} — v{}\n\n", self.title, self.version);
+        if !self.banner.is_empty() {
+            out.push_str(&format!("**{}**\n\n", self.banner));
+        }
+        if !self.summary.is_empty() {
+            out.push_str(&self.summary);
+            out.push_str("\n\n");
+        }
+        for detail in &self.details {
+            out.push_str(&format!("- {}\n", detail));
+        }
+        if !self.details.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&format!("Full release notes: {}\n", self.release_url));
+        out
+    }
+}
+
 #[derive(Deserialize)]
 struct FileNotes {
     #[serde(default)]
@@ -93,3 +120,132 @@ pub fn latest_for(version: &Version) -> Option<ReleaseNote> {
 pub fn by_version(version: &Version) -> Option<ReleaseNote> {
     NOTES.iter().find(|note| &note.version == version).cloned()
 }
+
+/// Notes newer than `after` (all when `None`) up to and including `upto`, newest first.
+pub fn between(after: Option<&Version>, upto: &Version) -> Vec<ReleaseNote> {
+    let mut notes: Vec<ReleaseNote> = NOTES
+        .iter()
+        .filter(|note| &note.version <= upto)
+        .filter(|note| after.is_none_or(|seen| &note.version > seen))
+        .cloned()
+        .collect();
+    notes.sort_by(|a, b| b.version.cmp(&a.version));
+    notes
+}
+
+#[derive(Deserialize)]
+struct PublishedRelease {
+    tag_name: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    body: Option<String>,
+    html_url: String,
+    #[serde(default)]
+    draft: bool,
+}
+
+/// Fetches the published notes for the same range as [`between`] from
+/// GitHub, newest first. Callers fall back to the bundled notes on error.
+pub fn fetch_between(after: Option<&Version>, upto: &Version) -> Result<Vec<ReleaseNote>> {
+    let client = net::client_builder()
+        .user_agent(format!(
+            "hn-tui/{version} (release-notes)",
+            version = crate::VERSION
+        ))
+        .build()
+        .context("release_notes: build HTTP client")?;
+    let request = client
+        .get(RELEASE_LIST_URL)
+        .header("Accept", "application/vnd.github+json");
+    let response = net::send(request).context("release_notes: request release list")?;
+    if !response.status().is_success() {
+        bail!(
+            "release_notes: GitHub answered with status {}",
+            response.status()
+        );
+    }
+    let releases: Vec<PublishedRelease> = response
+        .json()
+        .context("release_notes: decode release list")?;
+    Ok(select_published(releases, after, upto))
+}
+
+fn select_published(
+    releases: Vec<PublishedRelease>,
+    after: Option<&Version>,
+    upto: &Version,
+) -> Vec<ReleaseNote> {
+    let mut notes: Vec<ReleaseNote> = releases
+        .into_iter()
+        .filter(|release| !release.draft)
+        .filter_map(|release| {
+            let tag = release.tag_name.trim();
+            let version = Version::parse(tag.strip_prefix('v').unwrap_or(tag)).ok()?;
+            let title = release
+                .name
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| "HN-TUI".to_string());
+            Some(ReleaseNote {
+                version,
+                title,
+                banner: String::new(),
+                summary: release.body.unwrap_or_default().trim().to_string(),
+                details: Vec::new(),
+                release_url: release.html_url,
+            })
+        })
+        .filter(|note| &note.version <= upto)
+        .filter(|note| after.is_none_or(|seen| &note.version > seen))
+        .collect();
+    notes.sort_by(|a, b| b.version.cmp(&a.version));
+    notes
+}
+
+pub fn changelog_markdown(notes: &[ReleaseNote]) -> String {
+    let mut out = String::from("# What's new in HN-TUI\n\n");
+    for (index, note) in notes.iter().enumerate() {
+        if index > 0 {
+            out.push_str("---\n\n");
+        }
+        out.push_str(&note.to_markdown());
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(tag: &str, draft: bool) -> PublishedRelease {
+        PublishedRelease {
+            tag_name: tag.to_string(),
+            name: Some(format!("Release {tag}")),
+            body: Some(format!("  Notes for {tag}\n")),
+            html_url: format!("https://example.com/{tag}"),
+            draft,
+        }
+    }
+
+    #[test]
+    fn published_notes_cover_the_range_newest_first() {
+        let releases = vec![
+            release("v0.2.0", false),
+            release("v0.4.0", false),
+            release("v0.3.0", false),
+            release("v0.3.5", true),
+            release("nightly", false),
+            release("v0.5.0", false),
+        ];
+        let after = Version::parse("0.2.0").unwrap();
+        let upto = Version::parse("0.4.0").unwrap();
+        let notes = select_published(releases, Some(&after), &upto);
+        let versions: Vec<String> = notes.iter().map(|note| note.version.to_string()).collect();
+        assert_eq!(versions, ["0.4.0", "0.3.0"]);
+        assert_eq!(notes[0].title, "Release v0.4.0");
+        assert_eq!(notes[0].summary, "Notes for v0.4.0");
+        assert_eq!(notes[0].release_url, "https://example.com/v0.4.0");
+    }
+}
@@ -0,0 +1,231 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::{TimeZone, Utc};
+
+use crate::config;
+use crate::data::{self, BackendKind, FeedService};
+use crate::hackernews;
+use crate::markdown;
+use crate::reddit;
+
+const HN_BASE_URL: &str = "https://news.ycombinator.com";
+const EXPORT_LIMIT: u32 = 30;
+
+pub fn render(title: &str, link: &str, posts: &[reddit::Post]) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<rss version=\"2.0\">\n<channel>\n");
+    out.push_str(&format!("  <title>{}</title>\n", escape_xml(title)));
+    out.push_str(&format!("  <link>{}</link>\n", escape_xml(link)));
+    out.push_str(&format!(
+        "  <description>{} exported by HN-TUI</description>\n",
+        escape_xml(title)
+    ));
+    out.push_str(&format!(
+        "  <generator>HN-TUI {}</generator>\n",
+        crate::VERSION
+    ));
+
+    for post in posts {
+        let thread = thread_url(post);
+        let url = post.url.trim();
+        let item_link = if url.is_empty() { thread.as_str() } else { url };
+        let title = markdown::decode_html_entities(&post.title);
+
+        out.push_str("  <item>\n");
+        out.push_str(&format!(
+            "    <title>{}</title>\n",
+            escape_xml(title.trim())
+        ));
+        out.push_str(&format!("    <link>{}</link>\n", escape_xml(item_link)));
+        out.push_str(&format!(
+            "    <guid isPermaLink=\"true\">{}</guid>\n",
+            escape_xml(&thread)
+        ));
+        out.push_str(&format!(
+            "    <comments>{}</comments>\n",
+            escape_xml(&thread)
+        ));
+        if !post.author.trim().is_empty() {
+            out.push_str(&format!(
+                "    <author>{}</author>\n",
+                escape_xml(post.author.trim())
+            ));
+        }
+        if let Some(date) = Utc
+            .timestamp_opt(post.created_utc.trunc() as i64, 0)
+            .single()
+            .filter(|_| post.created_utc > 0.0)
+        {
+            out.push_str(&format!("    <pubDate>{}</pubDate>\n", date.to_rfc2822()));
+        }
+        let mut description = format!("{} points · {} comments", post.score, post.num_comments);
+        let selftext = markdown::decode_html_entities(&post.selftext);
+        if !selftext.trim().is_empty() {
+            description.push_str("\n\n");
+            description.push_str(selftext.trim());
+        }
+        out.push_str(&format!(
+            "    <description>{}</description>\n",
+            escape_xml(&description)
+        ));
+        out.push_str("  </item>\n");
+    }
+
+    out.push_str("</channel>\n</rss>\n");
+    out
+}
+
+/// Fetches `target` from Hacker News as RSS. Reddit and Lemmy feeds need a
+/// signed-in session, so those backends are refused rather than silently
+/// exporting Hacker News instead.
+pub fn export_target(target: &str, cfg: &config::Config) -> Result<String> {
+    let backend = BackendKind::from_config(&cfg.backend);
+    if backend != BackendKind::HackerNews {
+        anyhow::bail!(
+            "rss: --rss only exports Hacker News feeds, but the configured backend is {}",
+            backend.label()
+        );
+    }
+    let client = hackernews::Client::new(hackernews::ClientConfig {
+        user_agent: cfg.reddit.user_agent.clone(),
+        http_client: None,
+    })
+    .context("rss: create Hacker News client")?;
+    let service = data::HackerNewsFeedService::new(Arc::new(client));
+    let opts = reddit::ListingOptions {
+        limit: Some(EXPORT_LIMIT),
+        ..Default::default()
+    };
+
+    let trimmed = target.trim();
+    let (title, link, listing) = if let Some(user) = trimmed
+        .strip_prefix("u/")
+        .or_else(|| trimmed.strip_prefix("user:"))
+    {
+        let listing = service
+            .load_user(user, reddit::SortOption::New, opts)
+            .with_context(|| format!("rss: load stories for {user}"))?;
+        (
+            format!("Hacker News: {user}"),
+            format!("{HN_BASE_URL}/submitted?id={user}"),
+            listing,
+        )
+    } else {
+        let name = if trimmed.is_empty() { "top" } else { trimmed };
+        let listing = service
+            .load_subreddit(name, reddit::SortOption::Hot, opts)
+            .with_context(|| format!("rss: load {name} stories"))?;
+        (
+            format!("Hacker News: {name}"),
+            HN_BASE_URL.to_string(),
+            listing,
+        )
+    };
+
+    let posts: Vec<reddit::Post> = listing
+        .children
+        .into_iter()
+        .map(|thing| thing.data)
+        .collect();
+    Ok(render(&title, &link, &posts))
+}
+
+fn thread_url(post: &reddit::Post) -> String {
+    data::post_thread_url(post).unwrap_or_else(|| format!("{HN_BASE_URL}/item?id={}", post.id))
+}
+
+fn escape_xml(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    for ch in input.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            ch if ch.is_control() && ch != '\n' && ch != '\t' => {}
+            ch => out.push(ch),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn story(id: &str, permalink: &str) -> reddit::Post {
+        reddit::Post {
+            id: id.to_string(),
+            name: format!("s_{id}"),
+            title: "Rust &amp; <friends>".to_string(),
+            subreddit: "top".to_string(),
+            author: "pg".to_string(),
+            selftext: String::new(),
+            url: String::new(),
+            permalink: permalink.to_string(),
+            score: 42,
+            likes: None,
+            num_comments: 7,
+            created_utc: 1_700_000_000.0,
+            thumbnail: String::new(),
+            stickied: false,
+            locked: false,
+            archived: false,
+            over_18: false,
+            spoiler: false,
+            post_hint: String::new(),
+            is_video: false,
+            media: None,
+            secure_media: None,
+            crosspost_parent_list: Vec::new(),
+            preview: reddit::Preview::default(),
+            gallery_data: None,
+            media_metadata: None,
+            suggested_sort: None,
+            domain: String::new(),
+            link_flair_text: None,
+            total_awards_received: 0,
+            upvote_ratio: None,
+        }
+    }
+
+    #[test]
+    fn items_link_to_their_thread_and_escape_text() {
+        let mut linked = story("1", "/item?id=1");
+        linked.url = "https://example.com/a?b=1&c=2".to_string();
+        let document = render("Hacker News: top", HN_BASE_URL, &[linked, story("2", "")]);
+        assert!(document.contains("<title>Rust &amp; &lt;friends&gt;</title>"));
+        assert!(document.contains("<link>https://example.com/a?b=1&amp;c=2</link>"));
+        assert!(document.contains("<comments>https://news.ycombinator.com/item?id=1</comments>"));
+        assert!(document.contains("<link>https://news.ycombinator.com/item?id=2</link>"));
+        assert!(document.contains("<description>42 points · 7 comments</description>"));
+        assert!(document.contains("<pubDate>Tue, 14 Nov 2023 22:13:20 +0000</pubDate>"));
+    }
+
+    #[test]
+    fn thread_urls_match_the_rest_of_the_app() {
+        for permalink in ["/item/9", "/item?id=9", "/r/rust/comments/9/x/"] {
+            let post = story("9", permalink);
+            assert_eq!(Some(thread_url(&post)), data::post_thread_url(&post));
+        }
+        assert_eq!(
+            thread_url(&story("9", "")),
+            "https://news.ycombinator.com/item?id=9"
+        );
+    }
+
+    #[test]
+    fn exports_refuse_other_backends() {
+        for backend in ["reddit", "Lemmy"] {
+            let cfg = config::Config {
+                backend: backend.to_string(),
+                ..Default::default()
+            };
+            let err = export_target("top", &cfg).unwrap_err().to_string();
+            assert!(err.contains("only exports Hacker News feeds"), "{err}");
+        }
+    }
+}
@@ -0,0 +1,380 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Context, Result};
+use rhai::{CallFnOptions, Dynamic, Engine, FuncArgs, Map, Scope, AST};
+
+use crate::profile;
+use crate::reddit;
+
+/// Hooks a script may define; each is a plain Rhai function.
+pub const ON_POST_SELECTED: &str = "on_post_selected";
+pub const ON_COMMENTS_OPENED: &str = "on_comments_opened";
+pub const KEEP_POST: &str = "keep_post";
+pub const POST_RANK: &str = "post_rank";
+pub const COLLAPSE_COMMENT: &str = "collapse_comment";
+
+/// Work allowed per hook call, so a runaway loop can't hang the UI.
+const MAX_OPERATIONS: u64 = 200_000;
+/// Size limits, so a script can't exhaust memory within its operations.
+const MAX_STRING_SIZE: usize = 64 * 1024;
+const MAX_ARRAY_SIZE: usize = 10_000;
+const MAX_MAP_SIZE: usize = 10_000;
+const MAX_CALL_LEVELS: usize = 32;
+
+/// Something a script asked for through `vote`, `open`, or `status`; the UI
+/// applies them after the hook returns.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// Vote on the selected post: 1, 0, or -1.
+    Vote(i32),
+    Open(String),
+    Status(String),
+}
+
+/// A comment as scripts see it.
+pub struct Comment<'a> {
+    pub author: &'a str,
+    pub body: &'a str,
+    pub score: i64,
+    pub depth: usize,
+}
+
+struct Script {
+    path: PathBuf,
+    ast: AST,
+}
+
+/// Compiled user scripts sharing one engine.
+pub struct Scripts {
+    engine: Engine,
+    scripts: Vec<Script>,
+    actions: Arc<Mutex<Vec<Action>>>,
+}
+
+impl Default for Scripts {
+    fn default() -> Self {
+        let actions = Arc::new(Mutex::new(Vec::new()));
+        Self {
+            engine: engine(Arc::clone(&actions)),
+            scripts: Vec::new(),
+            actions,
+        }
+    }
+}
+
+impl Scripts {
+    /// Compiles the scripts listed under `scripts`. Relative paths are taken
+    /// from the config directory.
+    pub fn load(paths: &[PathBuf]) -> Result<Self> {
+        let mut scripts = Self::default();
+        for path in paths {
+            let path = resolve(path);
+            let source = std::fs::read_to_string(&path)
+                .with_context(|| format!("scripting: read {}", path.display()))?;
+            scripts.add(path, &source)?;
+        }
+        Ok(scripts)
+    }
+
+    fn add(&mut self, path: PathBuf, source: &str) -> Result<()> {
+        let ast = self
+            .engine
+            .compile(source)
+            .map_err(|err| anyhow!("scripting: {}: {err}", path.display()))?;
+        self.scripts.push(Script { path, ast });
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scripts.is_empty()
+    }
+
+    /// True when any script defines `hook`.
+    pub fn defines(&self, hook: &str) -> bool {
+        self.with_hook(hook).next().is_some()
+    }
+
+    pub fn post_selected(&self, post: &reddit::Post) -> Vec<Action> {
+        self.notify(ON_POST_SELECTED, post)
+    }
+
+    pub fn comments_opened(&self, post: &reddit::Post) -> Vec<Action> {
+        self.notify(ON_COMMENTS_OPENED, post)
+    }
+
+    /// False when any `keep_post` hook rejects `post`.
+    pub fn keeps_post(&self, post: &reddit::Post) -> bool {
+        let keep = self.with_hook(KEEP_POST).all(|script| {
+            self.call(script, KEEP_POST, (post_map(post),))
+                .unwrap_or(true)
+        });
+        self.take_actions();
+        keep
+    }
+
+    /// True when any `collapse_comment` hook folds `comment`.
+    pub fn collapses_comment(&self, comment: &Comment<'_>) -> bool {
+        let collapse = self.with_hook(COLLAPSE_COMMENT).any(|script| {
+            self.call(script, COLLAPSE_COMMENT, (comment_map(comment),))
+                .unwrap_or(false)
+        });
+        self.take_actions();
+        collapse
+    }
+
+    /// Stable-sorts `items` by descending `post_rank`, from the first script
+    /// that defines it. Posts it fails to rank go last.
+    pub fn rank_posts<T>(&self, items: &mut Vec<T>, post: impl Fn(&T) -> &reddit::Post) {
+        let Some(script) = self.with_hook(POST_RANK).next() else {
+            return;
+        };
+        let mut ranked: Vec<(f64, T)> = items
+            .drain(..)
+            .map(|item| {
+                let rank = self
+                    .call::<Dynamic>(script, POST_RANK, (post_map(post(&item)),))
+                    .and_then(|rank| number(&rank))
+                    .unwrap_or(f64::NEG_INFINITY);
+                (rank, item)
+            })
+            .collect();
+        self.take_actions();
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+        items.extend(ranked.into_iter().map(|(_, item)| item));
+    }
+
+    /// Runs an event hook in every script that defines it and returns the
+    /// actions they queued. A failing script is reported through
+    /// [`Action::Status`].
+    fn notify(&self, hook: &str, post: &reddit::Post) -> Vec<Action> {
+        let mut errors = Vec::new();
+        for script in self.with_hook(hook) {
+            if let Err(err) = self.try_call::<Dynamic>(script, hook, (post_map(post),)) {
+                errors.push(Action::Status(err));
+            }
+        }
+        let mut actions = self.take_actions();
+        actions.extend(errors);
+        actions
+    }
+
+    fn with_hook<'a>(&'a self, hook: &'a str) -> impl Iterator<Item = &'a Script> + 'a {
+        self.scripts
+            .iter()
+            .filter(move |script| script.ast.iter_functions().any(|f| f.name == hook))
+    }
+
+    fn call<T: Clone + Send + Sync + 'static>(
+        &self,
+        script: &Script,
+        hook: &str,
+        args: impl FuncArgs,
+    ) -> Option<T> {
+        self.try_call(script, hook, args)
+            .map_err(|err| tracing::warn!("{err}"))
+            .ok()
+    }
+
+    fn try_call<T: Clone + Send + Sync + 'static>(
+        &self,
+        script: &Script,
+        hook: &str,
+        args: impl FuncArgs,
+    ) -> Result<T, String> {
+        let options = CallFnOptions::new().eval_ast(false);
+        self.engine
+            .call_fn_with_options(options, &mut Scope::new(), &script.ast, hook, args)
+            .map_err(|err| format!("Script {} failed in {hook}: {err}", file_name(&script.path)))
+    }
+
+    fn take_actions(&self) -> Vec<Action> {
+        std::mem::take(&mut *self.actions.lock().unwrap_or_else(|err| err.into_inner()))
+    }
+}
+
+fn engine(actions: Arc<Mutex<Vec<Action>>>) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_string_size(MAX_STRING_SIZE);
+    engine.set_max_array_size(MAX_ARRAY_SIZE);
+    engine.set_max_map_size(MAX_MAP_SIZE);
+    engine.set_max_call_levels(MAX_CALL_LEVELS);
+    engine.on_print(|text| tracing::info!("script: {text}"));
+    let queue = Arc::clone(&actions);
+    engine.register_fn("vote", move |dir: i64| {
+        push(&queue, Action::Vote(dir.clamp(-1, 1) as i32));
+    });
+    let queue = Arc::clone(&actions);
+    engine.register_fn("open", move |url: &str| {
+        push(&queue, Action::Open(url.to_string()));
+    });
+    engine.register_fn("status", move |text: &str| {
+        push(&actions, Action::Status(text.to_string()));
+    });
+    engine
+}
+
+fn push(actions: &Mutex<Vec<Action>>, action: Action) {
+    actions
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .push(action);
+}
+
+fn post_map(post: &reddit::Post) -> Map {
+    let mut map = Map::new();
+    for (key, value) in [
+        ("id", &post.id),
+        ("name", &post.name),
+        ("title", &post.title),
+        ("subreddit", &post.subreddit),
+        ("author", &post.author),
+        ("url", &post.url),
+        ("domain", &post.domain),
+        ("permalink", &post.permalink),
+        ("selftext", &post.selftext),
+    ] {
+        map.insert(key.into(), Dynamic::from(value.clone()));
+    }
+    map.insert("score".into(), Dynamic::from(post.score));
+    map.insert("num_comments".into(), Dynamic::from(post.num_comments));
+    map.insert("created_utc".into(), Dynamic::from(post.created_utc));
+    map.insert("over_18".into(), Dynamic::from(post.over_18));
+    map.insert("stickied".into(), Dynamic::from(post.stickied));
+    map
+}
+
+fn comment_map(comment: &Comment<'_>) -> Map {
+    let mut map = Map::new();
+    map.insert("author".into(), Dynamic::from(comment.author.to_string()));
+    map.insert("body".into(), Dynamic::from(comment.body.to_string()));
+    map.insert("score".into(), Dynamic::from(comment.score));
+    map.insert("depth".into(), Dynamic::from(comment.depth as i64));
+    map
+}
+
+fn number(value: &Dynamic) -> Option<f64> {
+    value
+        .as_float()
+        .ok()
+        .or_else(|| value.as_int().ok().map(|value| value as f64))
+}
+
+fn resolve(path: &Path) -> PathBuf {
+    if let Ok(rest) = path.strip_prefix("~") {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(rest);
+        }
+    }
+    if path.is_relative() {
+        if let Some(dir) = dirs::config_dir() {
+            return profile::scope(dir.join("hn-tui")).join(path);
+        }
+    }
+    path.to_path_buf()
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scripts(source: &str) -> Scripts {
+        let mut scripts = Scripts::default();
+        scripts.add(PathBuf::from("test.rhai"), source).unwrap();
+        scripts
+    }
+
+    fn post(author: &str, score: i64) -> reddit::Post {
+        serde_json::from_value(serde_json::json!({
+            "id": author,
+            "name": format!("t3_{author}"),
+            "title": "A post",
+            "subreddit": "rust",
+            "author": author,
+            "score": score,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn event_hooks_queue_actions() {
+        let scripts = scripts(
+            r#"
+            fn on_post_selected(post) {
+                if post.author == "friend" { vote(5); }
+                status(`Selected ${post.title}`);
+            }
+            "#,
+        );
+        assert_eq!(
+            scripts.post_selected(&post("friend", 1)),
+            vec![
+                Action::Vote(1),
+                Action::Status("Selected A post".to_string())
+            ]
+        );
+        assert!(scripts.comments_opened(&post("friend", 1)).is_empty());
+    }
+
+    #[test]
+    fn filters_sorts_and_collapses() {
+        let scripts = scripts(
+            r#"
+            fn keep_post(post) { post.author != "spammer" }
+            fn post_rank(post) { -post.score }
+            fn collapse_comment(comment) { comment.author == "bot" && comment.depth > 0 }
+            "#,
+        );
+        assert!(!scripts.keeps_post(&post("spammer", 1)));
+        assert!(scripts.keeps_post(&post("friend", 1)));
+
+        let mut posts = vec![post("a", 10), post("b", -3), post("c", 4)];
+        scripts.rank_posts(&mut posts, |post| post);
+        let order: Vec<_> = posts.iter().map(|post| post.author.as_str()).collect();
+        assert_eq!(order, ["b", "c", "a"]);
+
+        let comment = |depth| Comment {
+            author: "bot",
+            body: "beep",
+            score: 1,
+            depth,
+        };
+        assert!(scripts.collapses_comment(&comment(2)));
+        assert!(!scripts.collapses_comment(&comment(0)));
+    }
+
+    #[test]
+    fn failures_are_reported_not_fatal() {
+        let scripts = scripts(
+            r#"
+            fn on_post_selected(post) { no_such_function(post) }
+            fn keep_post(post) { loop {} }
+            fn post_rank(post) { let s = "x"; loop { s += s; } }
+            fn collapse_comment(comment) { collapse_comment(comment) }
+            "#,
+        );
+        let actions = scripts.post_selected(&post("a", 1));
+        assert!(matches!(&actions[..], [Action::Status(message)] if message.contains("test.rhai")));
+        assert!(scripts.keeps_post(&post("a", 1)));
+        let mut posts = vec![post("a", 1), post("b", 2)];
+        scripts.rank_posts(&mut posts, |post| post);
+        assert_eq!(posts.len(), 2);
+        let comment = Comment {
+            author: "a",
+            body: "b",
+            score: 1,
+            depth: 0,
+        };
+        assert!(!scripts.collapses_comment(&comment));
+        assert!(Scripts::default()
+            .add(PathBuf::from("bad.rhai"), "fn (")
+            .is_err());
+    }
+}
@@ -0,0 +1,185 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::profile;
+
+/// Service name the keychain entries are filed under.
+const SERVICE: &str = "hn-tui";
+
+/// Where OAuth tokens and the Lemmy password are kept, from
+/// `reddit.token_storage`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mode {
+    /// The OS keychain when one answers, otherwise the database.
+    Auto,
+    /// Only the OS keychain; saving fails without one.
+    Keyring,
+    /// Plaintext in the database, for headless systems without a keychain.
+    #[default]
+    Database,
+}
+
+impl Mode {
+    pub fn from_config(value: &str) -> Self {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "keyring" | "keychain" => Self::Keyring,
+            "database" | "db" | "sqlite" => Self::Database,
+            _ => Self::Auto,
+        }
+    }
+}
+
+/// The secret half of a token; expiry and scope stay in the database.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenSecret {
+    pub access_token: String,
+    pub refresh_token: String,
+}
+
+/// Reads and writes token secrets in the OS keychain (Secret Service,
+/// macOS Keychain, or Windows Credential Manager).
+#[derive(Debug)]
+pub struct Vault {
+    mode: Mode,
+    /// Cleared after the first keychain failure in [`Mode::Auto`], so a
+    /// missing keychain is only waited on once.
+    available: AtomicBool,
+}
+
+impl Default for Vault {
+    fn default() -> Self {
+        Self::new(Mode::Database)
+    }
+}
+
+impl Vault {
+    pub fn new(mode: Mode) -> Self {
+        Self {
+            mode,
+            available: AtomicBool::new(mode != Mode::Database),
+        }
+    }
+
+    /// True when new tokens should go to the keychain.
+    pub fn enabled(&self) -> bool {
+        self.available.load(Ordering::SeqCst)
+    }
+
+    /// Saves `secret` for the Reddit account `reddit_id`. Returns false when
+    /// the database has to keep it instead.
+    pub fn store(&self, reddit_id: &str, secret: &TokenSecret) -> Result<bool> {
+        if !self.enabled() {
+            return Ok(false);
+        }
+        let payload = serde_json::to_string(secret).context("secrets: encode token")?;
+        let result = entry(reddit_id).and_then(|entry| {
+            entry
+                .set_password(&payload)
+                .map_err(|err| anyhow!("secrets: save token: {err}"))
+        });
+        self.settle(result.map(|()| true))
+    }
+
+    /// The secret saved for `reddit_id`, if the keychain has one.
+    pub fn load(&self, reddit_id: &str) -> Result<Option<TokenSecret>> {
+        match self.mode {
+            // Still looked up, so tokens move back after switching modes.
+            Mode::Database => Ok(read(reddit_id).unwrap_or(None)),
+            Mode::Auto | Mode::Keyring => self.settle(read(reddit_id)),
+        }
+    }
+
+    /// Saves the password for `account` (for example `lemmy:alice@lemmy.ml`).
+    /// Returns false when the keychain is off and the config has to keep it.
+    pub fn store_password(&self, account: &str, password: &str) -> Result<bool> {
+        if !self.enabled() {
+            return Ok(false);
+        }
+        let result = entry(account).and_then(|entry| {
+            entry
+                .set_password(password)
+                .map_err(|err| anyhow!("secrets: save password: {err}"))
+        });
+        self.settle(result.map(|()| true))
+    }
+
+    /// The password saved for `account`, if the keychain has one.
+    pub fn load_password(&self, account: &str) -> Result<Option<String>> {
+        let result = entry(account).and_then(|entry| match entry.get_password() {
+            Ok(password) => Ok(Some(password)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(err) => Err(anyhow!("secrets: read password: {err}")),
+        });
+        match self.mode {
+            Mode::Database => Ok(result.unwrap_or(None)),
+            Mode::Auto | Mode::Keyring => self.settle(result),
+        }
+    }
+
+    pub fn delete(&self, reddit_id: &str) -> Result<()> {
+        match entry(reddit_id)?.delete_password() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(err) => Err(anyhow!("secrets: delete token: {err}")),
+        }
+    }
+
+    /// In [`Mode::Auto`] a failing keychain turns the vault off instead of
+    /// failing the caller.
+    fn settle<T: Default>(&self, result: Result<T>) -> Result<T> {
+        match result {
+            Err(err) if self.mode == Mode::Auto => {
+                tracing::warn!("{err:#}; keeping tokens in the database");
+                self.available.store(false, Ordering::SeqCst);
+                Ok(T::default())
+            }
+            other => other,
+        }
+    }
+}
+
+fn read(reddit_id: &str) -> Result<Option<TokenSecret>> {
+    match entry(reddit_id)?.get_password() {
+        Ok(payload) => serde_json::from_str(&payload)
+            .map(Some)
+            .context("secrets: decode token"),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(err) => Err(anyhow!("secrets: read token: {err}")),
+    }
+}
+
+/// Entries are per profile and per Reddit account. Local account ids are not
+/// used: another database, such as a restored backup, reuses them for other
+/// accounts.
+fn entry(reddit_id: &str) -> Result<keyring::Entry> {
+    let user = format!("{}/{reddit_id}", profile::active());
+    keyring::Entry::new(SERVICE, &user).map_err(|err| anyhow!("secrets: open keychain: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modes_parse_with_auto_as_fallback() {
+        assert_eq!(Mode::from_config("keyring"), Mode::Keyring);
+        assert_eq!(Mode::from_config(" Database "), Mode::Database);
+        assert_eq!(Mode::from_config("auto"), Mode::Auto);
+        assert_eq!(Mode::from_config("something"), Mode::Auto);
+    }
+
+    #[test]
+    fn database_mode_never_writes_the_keychain() {
+        let vault = Vault::new(Mode::Database);
+        assert!(!vault.enabled());
+        let secret = TokenSecret {
+            access_token: "a".to_string(),
+            refresh_token: "r".to_string(),
+        };
+        assert!(!vault.store("t2_me", &secret).unwrap());
+        assert!(!vault
+            .store_password("lemmy:alice@lemmy.ml", "hunter2")
+            .unwrap());
+    }
+}
@@ -0,0 +1,94 @@
+use std::sync::OnceLock;
+
+use crossbeam_channel::{never, Receiver};
+
+#[cfg(unix)]
+use std::sync::atomic::{AtomicI32, Ordering};
+
+static RECEIVER: OnceLock<Receiver<i32>> = OnceLock::new();
+
+/// Write end of the self-pipe. The handler may only make async-signal-safe
+/// calls, so it just writes the signal number here.
+#[cfg(unix)]
+static WRITE_FD: AtomicI32 = AtomicI32::new(-1);
+
+/// A channel that receives SIGTERM, SIGINT, and SIGHUP, so the UI can leave
+/// through its normal quit path instead of dying mid-draw. Handlers are
+/// installed on the first call; without Unix signals the channel never fires.
+pub fn shutdown_requests() -> Receiver<i32> {
+    RECEIVER.get_or_init(install).clone()
+}
+
+/// Name of a signal delivered through [`shutdown_requests`], for logging.
+#[cfg(unix)]
+pub fn name(signal: i32) -> &'static str {
+    match signal {
+        libc::SIGTERM => "SIGTERM",
+        libc::SIGINT => "SIGINT",
+        libc::SIGHUP => "SIGHUP",
+        _ => "signal",
+    }
+}
+
+#[cfg(not(unix))]
+pub fn name(_signal: i32) -> &'static str {
+    "signal"
+}
+
+#[cfg(unix)]
+extern "C" fn on_signal(signal: libc::c_int) {
+    let fd = WRITE_FD.load(Ordering::Relaxed);
+    if fd >= 0 {
+        let byte = signal as u8;
+        unsafe {
+            libc::write(fd, (&byte as *const u8).cast(), 1);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn install() -> Receiver<i32> {
+    use std::fs::File;
+    use std::io::{self, Read};
+    use std::os::unix::io::FromRawFd;
+    use std::thread;
+
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        tracing::warn!("signals: create pipe: {}", io::Error::last_os_error());
+        return never();
+    }
+    let [read_fd, write_fd] = fds;
+    WRITE_FD.store(write_fd, Ordering::SeqCst);
+
+    for signal in [libc::SIGTERM, libc::SIGINT, libc::SIGHUP] {
+        let installed = unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = on_signal as *const () as libc::sighandler_t;
+            action.sa_flags = libc::SA_RESTART;
+            libc::sigemptyset(&mut action.sa_mask);
+            libc::sigaction(signal, &action, std::ptr::null_mut()) == 0
+        };
+        if !installed {
+            let err = io::Error::last_os_error();
+            tracing::warn!("signals: install {} handler: {err}", name(signal));
+        }
+    }
+
+    let (tx, rx) = crossbeam_channel::unbounded();
+    thread::spawn(move || {
+        let mut pipe = unsafe { File::from_raw_fd(read_fd) };
+        let mut byte = [0u8; 1];
+        while pipe.read_exact(&mut byte).is_ok() {
+            if tx.send(i32::from(byte[0])).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+#[cfg(not(unix))]
+fn install() -> Receiver<i32> {
+    never()
+}
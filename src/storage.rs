@@ -1,4 +1,6 @@
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
@@ -7,12 +9,32 @@ use chrono::{DateTime, TimeZone, Utc};
 use parking_lot::Mutex;
 use rusqlite::{params, Connection, OptionalExtension, Row};
 
+use crate::profile;
+use crate::secrets::{self, TokenSecret};
+
+/// Prepared statements kept per connection: room for every cached query the
+/// store runs (rusqlite keeps 16 by default).
+const STATEMENT_CACHE_CAPACITY: usize = 32;
+
+/// Comment sorts not picked again, and threads not visited again, for this
+/// long are dropped on compaction.
+const POST_STATE_RETENTION: Duration = Duration::from_secs(180 * 24 * 60 * 60);
+/// Keyword alert matches kept; older ones are dropped as new ones arrive.
+const ALERT_MATCH_LIMIT: usize = 500;
+/// Posts not seen again for this long leave the history search index on
+/// compaction, along with their indexed comments.
+const INDEX_RETENTION: Duration = Duration::from_secs(90 * 24 * 60 * 60);
+
 const KEY_SHOW_NSFW: &str = "show_nsfw_posts";
 const KEY_LAST_SEEN_RELEASE: &str = "last_seen_release_version";
+const KEY_SKIPPED_UPDATE: &str = "skipped_update_version";
+const KEY_UPDATE_SNOOZED_UNTIL: &str = "update_snoozed_until";
 
 #[derive(Debug, Clone)]
 pub struct Store {
     conn: Arc<Mutex<Connection>>,
+    private: Arc<AtomicBool>,
+    vault: Arc<secrets::Vault>,
 }
 
 #[derive(Debug, Clone)]
@@ -49,6 +71,97 @@ pub struct MediaEntry {
     pub checksum: String,
 }
 
+/// Totals for the on-disk media cache, as recorded in `media_cache`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MediaCacheStats {
+    pub entries: i64,
+    pub size_bytes: i64,
+}
+
+/// Rows dropped by [`Store::prune_stale`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Pruned {
+    pub comment_sorts: usize,
+    pub thread_visits: usize,
+    pub history_posts: usize,
+}
+
+/// Database size before and after [`Store::compact`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Compaction {
+    pub bytes_before: i64,
+    pub bytes_after: i64,
+}
+
+/// A post snapshot kept so history search can rebuild results offline.
+#[derive(Debug, Clone)]
+pub struct CachedPost {
+    pub name: String,
+    pub payload: String,
+}
+
+/// One searchable unit: a post's title/body or a single comment.
+#[derive(Debug, Clone)]
+pub struct IndexedItem {
+    pub item_name: String,
+    pub post_name: String,
+    pub title: String,
+    pub body: String,
+}
+
+/// A keyword the alert monitor looks for in new posts of one subreddit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeywordAlert {
+    pub id: i64,
+    pub keyword: String,
+    pub subreddit: String,
+    /// Unix seconds; posts older than the rule never match it.
+    pub created_at: i64,
+}
+
+/// A post that matched a [`KeywordAlert`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlertMatch {
+    pub alert_id: i64,
+    pub keyword: String,
+    pub post_name: String,
+    pub title: String,
+    pub subreddit: String,
+    pub permalink: String,
+    pub matched_at: i64,
+}
+
+/// A named set of pinned feeds plus the feed and sort to open with them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Workspace {
+    pub name: String,
+    pub feeds: Vec<String>,
+    pub active_feed: String,
+    pub sort: String,
+}
+
+/// Where the last session left off, saved on exit and restored on launch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionState {
+    pub feed: String,
+    pub sort: String,
+    pub post_name: Option<String>,
+    pub post_offset: usize,
+    pub selected_comment: usize,
+    pub comment_offset: usize,
+    pub content_scroll: u16,
+}
+
+/// Time spent, posts read, and votes cast in one subreddit, recorded when
+/// `ui.usage_stats` is on.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UsageStat {
+    pub subreddit: String,
+    pub seconds: i64,
+    pub posts_read: i64,
+    pub votes: i64,
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct Options {
     pub path: Option<PathBuf>,
@@ -75,13 +188,41 @@ impl Store {
             .context("storage: enable foreign keys")?;
         conn.pragma_update(None, "busy_timeout", 5000)
             .context("storage: set busy timeout")?;
+        // Safe with WAL: a crash can only lose the last commits, never
+        // corrupt the database.
+        conn.pragma_update(None, "synchronous", "NORMAL")
+            .context("storage: set synchronous")?;
+        conn.pragma_update(None, "temp_store", "MEMORY")
+            .context("storage: set temp store")?;
+        conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
         migrate(&conn)?;
 
         Ok(Self {
             conn: Arc::new(Mutex::new(conn)),
+            private: Arc::new(AtomicBool::new(false)),
+            vault: Arc::new(secrets::Vault::default()),
         })
     }
 
+    /// Where token secrets go (`reddit.token_storage`). Without this they
+    /// stay in the database.
+    pub fn with_token_storage(mut self, mode: secrets::Mode) -> Self {
+        self.vault = Arc::new(secrets::Vault::new(mode));
+        self
+    }
+
+    /// In private mode nothing that records browsing is written: the search
+    /// index, comment drafts, search history, subreddit and thread visits,
+    /// session state, and usage totals. Reads, accounts, and explicit settings still work.
+    /// Shared by every clone of the store.
+    pub fn set_private(&self, private: bool) {
+        self.private.store(private, Ordering::SeqCst);
+    }
+
+    pub fn is_private(&self) -> bool {
+        self.private.load(Ordering::SeqCst)
+    }
+
     pub fn close(self) -> Result<()> {
         let conn = Arc::try_unwrap(self.conn)
             .map_err(|_| anyhow!("storage: connection still in use"))?
@@ -91,6 +232,80 @@ impl Store {
             .context("storage: close connection")
     }
 
+    /// Schema version of the open database.
+    pub fn schema_version(&self) -> Result<i64> {
+        applied_version(&self.conn.lock())
+    }
+
+    /// Runs SQLite's integrity and foreign key checks and looks for gaps in
+    /// the applied migrations. An empty list means the database is healthy.
+    pub fn check_integrity(&self) -> Result<Vec<String>> {
+        let conn = self.conn.lock();
+        let mut problems = Vec::new();
+
+        let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        for row in rows {
+            let row = row.context("storage: integrity check")?;
+            if row != "ok" {
+                problems.push(row);
+            }
+        }
+
+        let mut stmt = conn.prepare("PRAGMA foreign_key_check")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(format!(
+                "{} row {} points to a missing {} row",
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<i64>>(1)?.unwrap_or_default(),
+                row.get::<_, String>(2)?
+            ))
+        })?;
+        for row in rows {
+            problems.push(row.context("storage: foreign key check")?);
+        }
+
+        let mut stmt = conn.prepare("SELECT version FROM schema_migrations ORDER BY version")?;
+        let applied = stmt
+            .query_map([], |row| row.get::<_, i64>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("storage: list migrations")?;
+        for (expected, version) in (1..).zip(&applied) {
+            if *version != expected {
+                problems.push(format!("schema migration {expected} was never applied"));
+                break;
+            }
+        }
+        Ok(problems)
+    }
+
+    /// Writes a consistent copy of the database to `path`, which must not
+    /// exist yet.
+    pub fn snapshot(&self, path: &Path) -> Result<()> {
+        let conn = self.conn.lock();
+        conn.execute("VACUUM INTO ?1", params![path.to_string_lossy()])
+            .with_context(|| format!("storage: snapshot to {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Size of the database in bytes, not counting the write-ahead log.
+    pub fn size_bytes(&self) -> Result<i64> {
+        database_size(&self.conn.lock())
+    }
+
+    /// Folds the write-ahead log into the database, rebuilds it without free
+    /// pages, and refreshes the query planner's statistics.
+    pub fn compact(&self) -> Result<Compaction> {
+        let conn = self.conn.lock();
+        let bytes_before = database_size(&conn)?;
+        conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE); VACUUM; PRAGMA optimize;")
+            .context("storage: compact database")?;
+        Ok(Compaction {
+            bytes_before,
+            bytes_after: database_size(&conn)?,
+        })
+    }
+
     pub fn upsert_account(&self, mut account: Account) -> Result<i64> {
         if account.reddit_id.is_empty() {
             bail!("storage: reddit id required");
@@ -156,7 +371,7 @@ WHERE id = ?1
 
     pub fn list_accounts(&self) -> Result<Vec<Account>> {
         let conn = self.conn.lock();
-        let mut stmt = conn.prepare(
+        let mut stmt = conn.prepare_cached(
             r#"
 SELECT id, reddit_id, username, display_name, created_at, updated_at
 FROM accounts
@@ -276,10 +491,100 @@ ON CONFLICT(key) DO UPDATE SET value = excluded.value
         Ok(())
     }
 
+    pub fn skipped_update_version(&self) -> Result<Option<String>> {
+        let value = self
+            .app_state_value(KEY_SKIPPED_UPDATE)
+            .context("storage: query skipped update version")?;
+        Ok(value
+            .map(|raw| raw.trim().to_string())
+            .filter(|value| !value.is_empty()))
+    }
+
+    pub fn set_skipped_update_version(&self, version: Option<&str>) -> Result<()> {
+        self.set_app_state_value(KEY_SKIPPED_UPDATE, version.map(str::trim))
+            .context("storage: persist skipped update version")
+    }
+
+    pub fn update_snoozed_until(&self) -> Result<Option<i64>> {
+        let value = self
+            .app_state_value(KEY_UPDATE_SNOOZED_UNTIL)
+            .context("storage: query update snooze")?;
+        Ok(value.and_then(|raw| raw.trim().parse().ok()))
+    }
+
+    pub fn set_update_snoozed_until(&self, until: Option<i64>) -> Result<()> {
+        let value = until.map(|ts| ts.to_string());
+        self.set_app_state_value(KEY_UPDATE_SNOOZED_UNTIL, value.as_deref())
+            .context("storage: persist update snooze")
+    }
+
+    fn app_state_value(&self, key: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock();
+        let value = conn
+            .query_row(
+                "SELECT value FROM app_state WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(value)
+    }
+
+    fn set_app_state_value(&self, key: &str, value: Option<&str>) -> Result<()> {
+        let conn = self.conn.lock();
+        match value.filter(|value| !value.is_empty()) {
+            Some(value) => {
+                conn.execute(
+                    r#"
+INSERT INTO app_state (key, value)
+VALUES (?1, ?2)
+ON CONFLICT(key) DO UPDATE SET value = excluded.value
+"#,
+                    params![key, value],
+                )?;
+            }
+            None => {
+                conn.execute("DELETE FROM app_state WHERE key = ?1", params![key])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Saves `token`, with its secrets in the OS keychain when the vault is
+    /// enabled and in the `tokens` table otherwise.
     pub fn upsert_token(&self, token: Token) -> Result<()> {
         if token.account_id == 0 {
             bail!("storage: account id required for token");
         }
+        let secret = TokenSecret {
+            access_token: token.access_token.clone(),
+            refresh_token: token.refresh_token.clone(),
+        };
+        let key = self.vault_key(token.account_id)?;
+        let in_keychain = self.vault.store(&key, &secret)?;
+        self.write_token(&token, !in_keychain)
+    }
+
+    /// The keychain key for `account_id`: its Reddit id, which stays the same
+    /// when a restored backup numbers its accounts differently.
+    fn vault_key(&self, account_id: i64) -> Result<String> {
+        let conn = self.conn.lock();
+        conn.query_row(
+            "SELECT reddit_id FROM accounts WHERE id = ?1",
+            params![account_id],
+            |row| row.get(0),
+        )
+        .context("storage: look up account for token")
+    }
+
+    /// Writes the `tokens` row; without `with_secrets` the token columns are
+    /// left blank because the keychain holds them.
+    fn write_token(&self, token: &Token, with_secrets: bool) -> Result<()> {
+        let (access_token, refresh_token) = if with_secrets {
+            (token.access_token.as_str(), token.refresh_token.as_str())
+        } else {
+            ("", "")
+        };
         let scope = token.scope.join(" ");
         let conn = self.conn.lock();
         conn.execute(
@@ -295,8 +600,8 @@ ON CONFLICT(account_id) DO UPDATE SET
 "#,
             params![
                 token.account_id,
-                token.access_token,
-                token.refresh_token,
+                access_token,
+                refresh_token,
                 token.token_type,
                 scope,
                 token.expires_at.timestamp(),
@@ -305,7 +610,33 @@ ON CONFLICT(account_id) DO UPDATE SET
         Ok(())
     }
 
+    /// Loads the token for `account_id`, moving its secrets between the
+    /// database and the keychain when `reddit.token_storage` changed.
     pub fn get_token(&self, account_id: i64) -> Result<Option<Token>> {
+        let Some(mut token) = self.read_token(account_id)? else {
+            return Ok(None);
+        };
+        if token.access_token.is_empty() && token.refresh_token.is_empty() {
+            let key = self.vault_key(account_id)?;
+            let Some(secret) = self.vault.load(&key)? else {
+                return Ok(None);
+            };
+            token.access_token = secret.access_token;
+            token.refresh_token = secret.refresh_token;
+            if !self.vault.enabled() {
+                self.write_token(&token, true)?;
+                if let Err(err) = self.vault.delete(&key) {
+                    tracing::warn!("{err:#}");
+                }
+            }
+        } else if self.vault.enabled() {
+            // Plaintext tokens from before the keychain was used.
+            self.upsert_token(token.clone())?;
+        }
+        Ok(Some(token))
+    }
+
+    fn read_token(&self, account_id: i64) -> Result<Option<Token>> {
         let conn = self.conn.lock();
         conn.query_row(
             r#"
@@ -338,140 +669,843 @@ WHERE account_id = ?1
         .context("storage: query token")
     }
 
-    pub fn upsert_media_entry(&self, mut entry: MediaEntry) -> Result<i64> {
-        if entry.url.is_empty() {
-            bail!("storage: media url required");
+    pub fn index_content(&self, posts: &[CachedPost], items: &[IndexedItem]) -> Result<()> {
+        if self.is_private() || (posts.is_empty() && items.is_empty()) {
+            return Ok(());
         }
-        if entry.fetched_at.timestamp() == 0 {
-            entry.fetched_at = Utc::now();
+        let now = Utc::now().timestamp();
+        let mut conn = self.conn.lock();
+        let tx = conn.transaction().context("storage: begin index")?;
+        {
+            let mut cache_post = tx.prepare_cached(
+                r#"
+INSERT INTO cached_posts (name, payload, indexed_at)
+VALUES (?1, ?2, ?3)
+ON CONFLICT(name) DO UPDATE SET
+  payload = excluded.payload,
+  indexed_at = excluded.indexed_at
+"#,
+            )?;
+            for post in posts {
+                cache_post
+                    .execute(params![post.name, post.payload, now])
+                    .context("storage: cache post")?;
+            }
+            let mut index_item = tx.prepare_cached(
+                r#"
+INSERT INTO content_items (item_name, post_name, title, body)
+VALUES (?1, ?2, ?3, ?4)
+ON CONFLICT(item_name) DO UPDATE SET
+  post_name = excluded.post_name,
+  title = excluded.title,
+  body = excluded.body
+"#,
+            )?;
+            for item in items {
+                index_item
+                    .execute(params![
+                        item.item_name,
+                        item.post_name,
+                        item.title,
+                        item.body
+                    ])
+                    .context("storage: index content")?;
+            }
         }
-        let expires = entry.expires_at.map(|dt| dt.timestamp());
+        tx.commit().context("storage: commit index")
+    }
+
+    /// Returns cached post payloads whose title, body, or comments match
+    /// `query`, best match first.
+    pub fn search_cached_posts(&self, query: &str, limit: usize) -> Result<Vec<String>> {
+        let Some(expr) = fts_query(query) else {
+            return Ok(Vec::new());
+        };
         let conn = self.conn.lock();
-        let id: i64 = conn.query_row(
+        let mut stmt = conn.prepare_cached(
             r#"
-INSERT INTO media_cache (url, media_type, file_path, width, height, size_bytes, fetched_at, expires_at, checksum)
-VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
-ON CONFLICT(url) DO UPDATE SET
-  media_type = excluded.media_type,
-  file_path = excluded.file_path,
-  width = excluded.width,
-  height = excluded.height,
-  size_bytes = excluded.size_bytes,
-  fetched_at = excluded.fetched_at,
-  expires_at = excluded.expires_at,
-  checksum = excluded.checksum
-RETURNING id
+SELECT p.payload
+FROM content_fts
+JOIN content_items i ON i.id = content_fts.rowid
+JOIN cached_posts p ON p.name = i.post_name
+WHERE content_fts MATCH ?1
+GROUP BY p.name
+ORDER BY MIN(content_fts.rank), MAX(p.indexed_at) DESC
+LIMIT ?2
 "#,
-            params![
-                entry.url,
-                entry.media_type,
-                entry.file_path,
-                entry.width,
-                entry.height,
-                entry.size_bytes,
-                entry.fetched_at.timestamp(),
-                expires,
-                entry.checksum,
-            ],
-            |row| row.get(0),
         )?;
-        Ok(id)
+        let rows = stmt
+            .query_map(params![expr, limit as i64], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()
+            .context("storage: search cached posts")?;
+        Ok(rows)
     }
 
-    pub fn get_media_entry_by_url(&self, url: &str) -> Result<Option<MediaEntry>> {
-        let conn = self.conn.lock();
-        conn.query_row(
+    /// Drops cached posts indexed before `now` minus the retention period,
+    /// with every item indexed under them. Returns how many posts were removed.
+    pub fn prune_index(&self, now: DateTime<Utc>) -> Result<usize> {
+        let cutoff = now.timestamp() - INDEX_RETENTION.as_secs() as i64;
+        let mut conn = self.conn.lock();
+        let tx = conn.transaction().context("storage: begin index prune")?;
+        tx.execute(
             r#"
-SELECT id, url, media_type, file_path, width, height, size_bytes, fetched_at, expires_at, checksum
-FROM media_cache
-WHERE url = ?1
+DELETE FROM content_items
+WHERE post_name IN (SELECT name FROM cached_posts WHERE indexed_at < ?1)
 "#,
-            params![url],
-            media_entry_from_row,
+            params![cutoff],
         )
-        .optional()
-        .context("storage: query media entry")
+        .context("storage: prune indexed items")?;
+        let removed = tx
+            .execute(
+                "DELETE FROM cached_posts WHERE indexed_at < ?1",
+                params![cutoff],
+            )
+            .context("storage: prune cached posts")?;
+        tx.commit().context("storage: commit index prune")?;
+        Ok(removed)
     }
 
-    pub fn total_media_size(&self) -> Result<i64> {
+    pub fn comment_draft(&self, parent: &str) -> Result<Option<String>> {
         let conn = self.conn.lock();
-        let total: Option<i64> = conn.query_row(
-            "SELECT COALESCE(SUM(size_bytes), 0) FROM media_cache",
-            [],
+        conn.query_row(
+            "SELECT body FROM comment_drafts WHERE parent = ?1",
+            params![parent],
             |row| row.get(0),
-        )?;
-        Ok(total.unwrap_or(0))
+        )
+        .optional()
+        .context("storage: query comment draft")
     }
 
-    pub fn list_expired_media(
-        &self,
-        cutoff: DateTime<Utc>,
-        limit: usize,
-    ) -> Result<Vec<MediaEntry>> {
+    /// Stores the draft for `parent`; an empty body deletes it.
+    pub fn save_comment_draft(&self, parent: &str, body: &str) -> Result<()> {
+        if self.is_private() {
+            return Ok(());
+        }
+        if body.trim().is_empty() {
+            return self.delete_comment_draft(parent);
+        }
         let conn = self.conn.lock();
-        let mut stmt = conn.prepare(
+        conn.execute(
             r#"
-SELECT id, url, media_type, file_path, width, height, size_bytes, fetched_at, expires_at, checksum
-FROM media_cache
-WHERE expires_at IS NOT NULL AND expires_at <= ?1
-ORDER BY expires_at ASC
-LIMIT ?2
+INSERT INTO comment_drafts (parent, body, updated_at)
+VALUES (?1, ?2, ?3)
+ON CONFLICT(parent) DO UPDATE SET
+  body = excluded.body,
+  updated_at = excluded.updated_at
 "#,
+            params![parent, body, Utc::now().timestamp()],
+        )
+        .context("storage: save comment draft")?;
+        Ok(())
+    }
+
+    pub fn delete_comment_draft(&self, parent: &str) -> Result<()> {
+        let conn = self.conn.lock();
+        conn.execute(
+            "DELETE FROM comment_drafts WHERE parent = ?1",
+            params![parent],
+        )
+        .context("storage: delete comment draft")?;
+        Ok(())
+    }
+
+    /// Moves `query` to the top of the search history, adding it if new.
+    pub fn record_search(&self, query: &str) -> Result<()> {
+        let query = query.trim();
+        if query.is_empty() || self.is_private() {
+            return Ok(());
+        }
+        let conn = self.conn.lock();
+        // Replacing rather than updating gives the row a fresh rowid, which
+        // orders searches made within the same millisecond.
+        conn.execute(
+            "INSERT OR REPLACE INTO search_history (query, last_used) VALUES (?1, ?2)",
+            params![query, Utc::now().timestamp_millis()],
+        )
+        .context("storage: record search")?;
+        Ok(())
+    }
+
+    /// The most recently executed searches, newest first.
+    pub fn recent_searches(&self, limit: usize) -> Result<Vec<String>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare_cached(
+            "SELECT query FROM search_history ORDER BY last_used DESC, rowid DESC LIMIT ?1",
         )?;
         let rows = stmt
-            .query_map(
-                params![cutoff.timestamp(), limit as i64],
-                media_entry_from_row,
-            )?
-            .collect::<rusqlite::Result<Vec<_>>>()?;
+            .query_map(params![limit as i64], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()
+            .context("storage: list recent searches")?;
         Ok(rows)
     }
 
-    pub fn list_oldest_media(&self, limit: usize) -> Result<Vec<MediaEntry>> {
+    pub fn delete_search(&self, query: &str) -> Result<()> {
+        let conn = self.conn.lock();
+        conn.execute(
+            "DELETE FROM search_history WHERE query = ?1",
+            params![query.trim()],
+        )
+        .context("storage: delete search")?;
+        Ok(())
+    }
+
+    pub fn record_subreddit_visit(&self, name: &str) -> Result<()> {
+        let name = name.trim();
+        if name.is_empty() || self.is_private() {
+            return Ok(());
+        }
         let conn = self.conn.lock();
-        let mut stmt = conn.prepare(
+        conn.execute(
             r#"
-SELECT id, url, media_type, file_path, width, height, size_bytes, fetched_at, expires_at, checksum
-FROM media_cache
-ORDER BY fetched_at ASC
-LIMIT ?1
+INSERT INTO subreddit_visits (name, visits, last_visited)
+VALUES (?1, 1, ?2)
+ON CONFLICT(name) DO UPDATE SET
+  visits = visits + 1,
+  last_visited = excluded.last_visited
+"#,
+            params![name, Utc::now().timestamp()],
+        )
+        .context("storage: record subreddit visit")?;
+        Ok(())
+    }
+
+    /// Visited subreddits ranked by frecency: visit count, decayed by the
+    /// number of days since the last visit.
+    pub fn recent_subreddits(&self, limit: usize) -> Result<Vec<String>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare_cached(
+            r#"
+SELECT name FROM subreddit_visits
+ORDER BY visits / (1.0 + (?1 - last_visited) / 86400.0) DESC, last_visited DESC
+LIMIT ?2
 "#,
         )?;
         let rows = stmt
-            .query_map(params![limit as i64], media_entry_from_row)?
-            .collect::<rusqlite::Result<Vec<_>>>()?;
+            .query_map(params![Utc::now().timestamp(), limit as i64], |row| {
+                row.get(0)
+            })?
+            .collect::<rusqlite::Result<Vec<String>>>()
+            .context("storage: list recent subreddits")?;
         Ok(rows)
     }
 
-    pub fn delete_media_entries(&self, ids: &[i64]) -> Result<()> {
-        if ids.is_empty() {
-            return Ok(());
-        }
-        let placeholders = ids
-            .iter()
-            .enumerate()
-            .map(|(i, _)| format!("?{}", i + 1))
-            .collect::<Vec<_>>()
-            .join(",");
+    /// Pinned subreddits in the order the user arranged them.
+    pub fn pinned_subreddits(&self) -> Result<Vec<String>> {
         let conn = self.conn.lock();
-        let mut stmt = conn.prepare(&format!(
-            "DELETE FROM media_cache WHERE id IN ({})",
-            placeholders
-        ))?;
-        let params_vec = ids
-            .iter()
-            .map(|id| id as &dyn rusqlite::ToSql)
-            .collect::<Vec<_>>();
-        stmt.execute(rusqlite::params_from_iter(params_vec))?;
-        Ok(())
+        let mut stmt =
+            conn.prepare_cached("SELECT name FROM pinned_subreddits ORDER BY position")?;
+        let rows = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()
+            .context("storage: list pinned subreddits")?;
+        Ok(rows)
     }
-}
 
-fn account_from_row(row: &Row<'_>) -> rusqlite::Result<Account> {
-    let created: i64 = row.get(4)?;
-    let updated: i64 = row.get(5)?;
-    Ok(Account {
-        id: row.get(0)?,
+    /// Replaces the pinned list; `names` is stored in order.
+    pub fn set_pinned_subreddits(&self, names: &[String]) -> Result<()> {
+        let mut conn = self.conn.lock();
+        let tx = conn.transaction().context("storage: begin pin update")?;
+        tx.execute("DELETE FROM pinned_subreddits", [])
+            .context("storage: clear pinned subreddits")?;
+        for (position, name) in names.iter().enumerate() {
+            tx.execute(
+                "INSERT OR IGNORE INTO pinned_subreddits (name, position) VALUES (?1, ?2)",
+                params![name, position as i64],
+            )
+            .context("storage: pin subreddit")?;
+        }
+        tx.commit().context("storage: commit pin update")
+    }
+
+    /// Registers `keyword` for `subreddit`, returning the existing rule's id
+    /// if the pair is already watched.
+    pub fn add_keyword_alert(&self, keyword: &str, subreddit: &str) -> Result<i64> {
+        let conn = self.conn.lock();
+        conn.execute(
+            r#"
+INSERT OR IGNORE INTO keyword_alerts (keyword, subreddit, created_at)
+VALUES (?1, ?2, ?3)
+"#,
+            params![keyword.trim(), subreddit.trim(), Utc::now().timestamp()],
+        )
+        .context("storage: add keyword alert")?;
+        conn.query_row(
+            "SELECT id FROM keyword_alerts WHERE keyword = ?1 AND subreddit = ?2",
+            params![keyword.trim(), subreddit.trim()],
+            |row| row.get(0),
+        )
+        .context("storage: look up keyword alert")
+    }
+
+    pub fn keyword_alerts(&self) -> Result<Vec<KeywordAlert>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare_cached(
+            r#"
+SELECT id, keyword, subreddit, created_at FROM keyword_alerts
+ORDER BY subreddit, keyword
+"#,
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(KeywordAlert {
+                    id: row.get(0)?,
+                    keyword: row.get(1)?,
+                    subreddit: row.get(2)?,
+                    created_at: row.get(3)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("storage: list keyword alerts")?;
+        Ok(rows)
+    }
+
+    /// Removes a rule along with the matches it found.
+    pub fn delete_keyword_alert(&self, id: i64) -> Result<()> {
+        let mut conn = self.conn.lock();
+        let tx = conn.transaction().context("storage: begin alert delete")?;
+        tx.execute("DELETE FROM alert_matches WHERE alert_id = ?1", params![id])
+            .context("storage: delete alert matches")?;
+        tx.execute("DELETE FROM keyword_alerts WHERE id = ?1", params![id])
+            .context("storage: delete keyword alert")?;
+        tx.commit().context("storage: commit alert delete")
+    }
+
+    /// Stores a match, returning `false` when the rule already matched that post.
+    pub fn record_alert_match(&self, found: &AlertMatch) -> Result<bool> {
+        let conn = self.conn.lock();
+        let inserted = conn
+            .execute(
+                r#"
+INSERT OR IGNORE INTO alert_matches
+  (alert_id, post_name, title, subreddit, permalink, matched_at)
+VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+"#,
+                params![
+                    found.alert_id,
+                    found.post_name,
+                    found.title,
+                    found.subreddit,
+                    found.permalink,
+                    found.matched_at,
+                ],
+            )
+            .context("storage: record alert match")?;
+        if inserted > 0 {
+            conn.execute(
+                r#"
+DELETE FROM alert_matches WHERE rowid NOT IN (
+  SELECT rowid FROM alert_matches ORDER BY matched_at DESC, rowid DESC LIMIT ?1
+)
+"#,
+                params![ALERT_MATCH_LIMIT as i64],
+            )
+            .context("storage: trim alert matches")?;
+        }
+        Ok(inserted > 0)
+    }
+
+    /// The most recent matches across all rules, newest first.
+    pub fn alert_matches(&self, limit: usize) -> Result<Vec<AlertMatch>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare_cached(
+            r#"
+SELECT m.alert_id, a.keyword, m.post_name, m.title, m.subreddit, m.permalink, m.matched_at
+FROM alert_matches m
+JOIN keyword_alerts a ON a.id = m.alert_id
+ORDER BY m.matched_at DESC, m.rowid DESC
+LIMIT ?1
+"#,
+        )?;
+        let rows = stmt
+            .query_map(params![limit as i64], |row| {
+                Ok(AlertMatch {
+                    alert_id: row.get(0)?,
+                    keyword: row.get(1)?,
+                    post_name: row.get(2)?,
+                    title: row.get(3)?,
+                    subreddit: row.get(4)?,
+                    permalink: row.get(5)?,
+                    matched_at: row.get(6)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("storage: list alert matches")?;
+        Ok(rows)
+    }
+
+    pub fn session_state(&self) -> Result<Option<SessionState>> {
+        let conn = self.conn.lock();
+        conn.query_row(
+            r#"
+SELECT feed, sort, post_name, post_offset, selected_comment, comment_offset, content_scroll
+FROM session_state
+WHERE id = 1
+"#,
+            [],
+            |row| {
+                Ok(SessionState {
+                    feed: row.get(0)?,
+                    sort: row.get(1)?,
+                    post_name: row.get(2)?,
+                    post_offset: row.get::<_, i64>(3)?.max(0) as usize,
+                    selected_comment: row.get::<_, i64>(4)?.max(0) as usize,
+                    comment_offset: row.get::<_, i64>(5)?.max(0) as usize,
+                    content_scroll: row.get::<_, i64>(6)?.clamp(0, u16::MAX as i64) as u16,
+                })
+            },
+        )
+        .optional()
+        .context("storage: query session state")
+    }
+
+    pub fn save_session_state(&self, state: &SessionState) -> Result<()> {
+        if self.is_private() {
+            return Ok(());
+        }
+        let conn = self.conn.lock();
+        conn.execute(
+            r#"
+INSERT INTO session_state (id, feed, sort, post_name, post_offset, selected_comment, comment_offset, content_scroll, saved_at)
+VALUES (1, ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+ON CONFLICT(id) DO UPDATE SET
+  feed = excluded.feed,
+  sort = excluded.sort,
+  post_name = excluded.post_name,
+  post_offset = excluded.post_offset,
+  selected_comment = excluded.selected_comment,
+  comment_offset = excluded.comment_offset,
+  content_scroll = excluded.content_scroll,
+  saved_at = excluded.saved_at
+"#,
+            params![
+                state.feed,
+                state.sort,
+                state.post_name,
+                state.post_offset as i64,
+                state.selected_comment as i64,
+                state.comment_offset as i64,
+                state.content_scroll as i64,
+                Utc::now().timestamp(),
+            ],
+        )
+        .context("storage: save session state")?;
+        Ok(())
+    }
+
+    /// Saves `workspace`, replacing any workspace with the same name.
+    pub fn save_workspace(&self, workspace: &Workspace) -> Result<()> {
+        let mut conn = self.conn.lock();
+        let tx = conn
+            .transaction()
+            .context("storage: begin workspace save")?;
+        tx.execute(
+            r#"
+INSERT INTO workspaces (name, active_feed, sort, updated_at)
+VALUES (?1, ?2, ?3, ?4)
+ON CONFLICT(name) DO UPDATE SET
+  active_feed = excluded.active_feed,
+  sort = excluded.sort,
+  updated_at = excluded.updated_at
+"#,
+            params![
+                workspace.name.trim(),
+                workspace.active_feed,
+                workspace.sort,
+                Utc::now().timestamp(),
+            ],
+        )
+        .context("storage: save workspace")?;
+        tx.execute(
+            "DELETE FROM workspace_feeds WHERE workspace = ?1",
+            params![workspace.name.trim()],
+        )
+        .context("storage: clear workspace feeds")?;
+        for (position, feed) in workspace.feeds.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO workspace_feeds (workspace, position, feed) VALUES (?1, ?2, ?3)",
+                params![workspace.name.trim(), position as i64, feed],
+            )
+            .context("storage: save workspace feed")?;
+        }
+        tx.commit().context("storage: commit workspace save")
+    }
+
+    pub fn workspaces(&self) -> Result<Vec<Workspace>> {
+        let conn = self.conn.lock();
+        let mut stmt =
+            conn.prepare_cached("SELECT name, active_feed, sort FROM workspaces ORDER BY name")?;
+        let mut workspaces = stmt
+            .query_map([], |row| {
+                Ok(Workspace {
+                    name: row.get(0)?,
+                    feeds: Vec::new(),
+                    active_feed: row.get(1)?,
+                    sort: row.get(2)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("storage: list workspaces")?;
+        let mut feeds = conn
+            .prepare("SELECT feed FROM workspace_feeds WHERE workspace = ?1 ORDER BY position")?;
+        for workspace in &mut workspaces {
+            workspace.feeds = feeds
+                .query_map(params![workspace.name], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()
+                .context("storage: list workspace feeds")?;
+        }
+        Ok(workspaces)
+    }
+
+    pub fn delete_workspace(&self, name: &str) -> Result<()> {
+        let mut conn = self.conn.lock();
+        let tx = conn
+            .transaction()
+            .context("storage: begin workspace delete")?;
+        tx.execute(
+            "DELETE FROM workspace_feeds WHERE workspace = ?1",
+            params![name.trim()],
+        )
+        .context("storage: delete workspace feeds")?;
+        tx.execute(
+            "DELETE FROM workspaces WHERE name = ?1",
+            params![name.trim()],
+        )
+        .context("storage: delete workspace")?;
+        tx.commit().context("storage: commit workspace delete")
+    }
+
+    /// Adds each entry of `deltas` to its subreddit's running totals.
+    pub fn record_usage(&self, deltas: &[UsageStat]) -> Result<()> {
+        if self.is_private() {
+            return Ok(());
+        }
+        let mut conn = self.conn.lock();
+        let tx = conn.transaction().context("storage: begin usage update")?;
+        let now = Utc::now().timestamp();
+        for delta in deltas {
+            let subreddit = delta.subreddit.trim();
+            if subreddit.is_empty() {
+                continue;
+            }
+            tx.execute(
+                r#"
+INSERT INTO usage_stats (subreddit, seconds, posts_read, votes, updated_at)
+VALUES (?1, ?2, ?3, ?4, ?5)
+ON CONFLICT(subreddit) DO UPDATE SET
+  seconds = seconds + excluded.seconds,
+  posts_read = posts_read + excluded.posts_read,
+  votes = votes + excluded.votes,
+  updated_at = excluded.updated_at
+"#,
+                params![subreddit, delta.seconds, delta.posts_read, delta.votes, now],
+            )
+            .context("storage: record usage")?;
+        }
+        tx.commit().context("storage: commit usage update")
+    }
+
+    /// Remembers how many comments `post_name` had when its thread was opened.
+    pub fn record_thread_visit(&self, post_name: &str, comment_count: i64) -> Result<()> {
+        if post_name.is_empty() || self.is_private() {
+            return Ok(());
+        }
+        let conn = self.conn.lock();
+        conn.execute(
+            r#"
+INSERT INTO thread_visits (post_name, comment_count, visited_at)
+VALUES (?1, ?2, ?3)
+ON CONFLICT(post_name) DO UPDATE SET
+  comment_count = excluded.comment_count,
+  visited_at = excluded.visited_at
+"#,
+            params![post_name, comment_count, Utc::now().timestamp()],
+        )
+        .context("storage: record thread visit")?;
+        Ok(())
+    }
+
+    /// Comment counts from [`Store::record_thread_visit`] for the posts in
+    /// `post_names` that were opened before.
+    pub fn seen_comment_counts(&self, post_names: &[String]) -> Result<HashMap<String, i64>> {
+        let conn = self.conn.lock();
+        let mut stmt =
+            conn.prepare_cached("SELECT comment_count FROM thread_visits WHERE post_name = ?1")?;
+        let mut counts = HashMap::new();
+        for name in post_names {
+            let count = stmt
+                .query_row(params![name], |row| row.get::<_, i64>(0))
+                .optional()
+                .context("storage: query thread visit")?;
+            if let Some(count) = count {
+                counts.insert(name.clone(), count);
+            }
+        }
+        Ok(counts)
+    }
+
+    /// The comment sort saved for `post_name`, as Reddit names it.
+    pub fn comment_sort(&self, post_name: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock();
+        conn.query_row(
+            "SELECT sort FROM comment_sorts WHERE post_name = ?1",
+            params![post_name],
+            |row| row.get(0),
+        )
+        .optional()
+        .context("storage: query comment sort")
+    }
+
+    pub fn set_comment_sort(&self, post_name: &str, sort: &str) -> Result<()> {
+        if post_name.is_empty() || self.is_private() {
+            return Ok(());
+        }
+        let conn = self.conn.lock();
+        conn.execute(
+            r#"
+INSERT INTO comment_sorts (post_name, sort, updated_at)
+VALUES (?1, ?2, ?3)
+ON CONFLICT(post_name) DO UPDATE SET
+  sort = excluded.sort,
+  updated_at = excluded.updated_at
+"#,
+            params![post_name, sort, Utc::now().timestamp()],
+        )
+        .context("storage: save comment sort")?;
+        Ok(())
+    }
+
+    /// Drops comment sorts last picked before `now` minus the retention
+    /// period. Returns how many were removed.
+    pub fn prune_comment_sorts(&self, now: DateTime<Utc>) -> Result<usize> {
+        let cutoff = now.timestamp() - POST_STATE_RETENTION.as_secs() as i64;
+        let conn = self.conn.lock();
+        conn.execute(
+            "DELETE FROM comment_sorts WHERE updated_at < ?1",
+            params![cutoff],
+        )
+        .context("storage: prune comment sorts")
+    }
+
+    /// Drops thread visits older than the retention period. Returns how many
+    /// were removed.
+    pub fn prune_thread_visits(&self, now: DateTime<Utc>) -> Result<usize> {
+        let cutoff = now.timestamp() - POST_STATE_RETENTION.as_secs() as i64;
+        let conn = self.conn.lock();
+        conn.execute(
+            "DELETE FROM thread_visits WHERE visited_at < ?1",
+            params![cutoff],
+        )
+        .context("storage: prune thread visits")
+    }
+
+    /// Drops every kind of per-post state that has outlived its retention
+    /// period: comment sorts, thread visits, and history search entries.
+    pub fn prune_stale(&self, now: DateTime<Utc>) -> Result<Pruned> {
+        Ok(Pruned {
+            comment_sorts: self.prune_comment_sorts(now)?,
+            thread_visits: self.prune_thread_visits(now)?,
+            history_posts: self.prune_index(now)?,
+        })
+    }
+
+    /// Recorded totals, most time spent first.
+    pub fn usage_stats(&self) -> Result<Vec<UsageStat>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare_cached(
+            r#"
+SELECT subreddit, seconds, posts_read, votes FROM usage_stats
+ORDER BY seconds DESC, posts_read DESC, subreddit
+"#,
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(UsageStat {
+                    subreddit: row.get(0)?,
+                    seconds: row.get(1)?,
+                    posts_read: row.get(2)?,
+                    votes: row.get(3)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<UsageStat>>>()
+            .context("storage: list usage stats")?;
+        Ok(rows)
+    }
+
+    pub fn upsert_media_entry(&self, mut entry: MediaEntry) -> Result<i64> {
+        if entry.url.is_empty() {
+            bail!("storage: media url required");
+        }
+        if entry.fetched_at.timestamp() == 0 {
+            entry.fetched_at = Utc::now();
+        }
+        let expires = entry.expires_at.map(|dt| dt.timestamp());
+        let conn = self.conn.lock();
+        let id: i64 = conn.query_row(
+            r#"
+INSERT INTO media_cache (url, media_type, file_path, width, height, size_bytes, fetched_at, expires_at, checksum, last_accessed)
+VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?7)
+ON CONFLICT(url) DO UPDATE SET
+  media_type = excluded.media_type,
+  file_path = excluded.file_path,
+  width = excluded.width,
+  height = excluded.height,
+  size_bytes = excluded.size_bytes,
+  fetched_at = excluded.fetched_at,
+  expires_at = excluded.expires_at,
+  checksum = excluded.checksum,
+  last_accessed = excluded.last_accessed
+RETURNING id
+"#,
+            params![
+                entry.url,
+                entry.media_type,
+                entry.file_path,
+                entry.width,
+                entry.height,
+                entry.size_bytes,
+                entry.fetched_at.timestamp(),
+                expires,
+                entry.checksum,
+            ],
+            |row| row.get(0),
+        )?;
+        Ok(id)
+    }
+
+    pub fn get_media_entry_by_url(&self, url: &str) -> Result<Option<MediaEntry>> {
+        let conn = self.conn.lock();
+        conn.query_row(
+            r#"
+SELECT id, url, media_type, file_path, width, height, size_bytes, fetched_at, expires_at, checksum
+FROM media_cache
+WHERE url = ?1
+"#,
+            params![url],
+            media_entry_from_row,
+        )
+        .optional()
+        .context("storage: query media entry")
+    }
+
+    /// Marks a cache hit so quota eviction keeps recently shown media.
+    pub fn touch_media_entry(&self, id: i64) -> Result<()> {
+        let conn = self.conn.lock();
+        conn.execute(
+            "UPDATE media_cache SET last_accessed = ?1 WHERE id = ?2",
+            params![Utc::now().timestamp(), id],
+        )
+        .context("storage: touch media entry")?;
+        Ok(())
+    }
+
+    pub fn media_cache_stats(&self) -> Result<MediaCacheStats> {
+        let conn = self.conn.lock();
+        conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(size_bytes), 0) FROM media_cache",
+            [],
+            |row| {
+                Ok(MediaCacheStats {
+                    entries: row.get(0)?,
+                    size_bytes: row.get(1)?,
+                })
+            },
+        )
+        .context("storage: media cache stats")
+    }
+
+    pub fn list_all_media(&self) -> Result<Vec<MediaEntry>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare_cached(
+            r#"
+SELECT id, url, media_type, file_path, width, height, size_bytes, fetched_at, expires_at, checksum
+FROM media_cache
+"#,
+        )?;
+        let rows = stmt
+            .query_map([], media_entry_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    pub fn total_media_size(&self) -> Result<i64> {
+        let conn = self.conn.lock();
+        let total: Option<i64> = conn.query_row(
+            "SELECT COALESCE(SUM(size_bytes), 0) FROM media_cache",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(total.unwrap_or(0))
+    }
+
+    pub fn list_expired_media(
+        &self,
+        cutoff: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<MediaEntry>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare_cached(
+            r#"
+SELECT id, url, media_type, file_path, width, height, size_bytes, fetched_at, expires_at, checksum
+FROM media_cache
+WHERE expires_at IS NOT NULL AND expires_at <= ?1
+ORDER BY expires_at ASC
+LIMIT ?2
+"#,
+        )?;
+        let rows = stmt
+            .query_map(
+                params![cutoff.timestamp(), limit as i64],
+                media_entry_from_row,
+            )?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    pub fn list_least_recent_media(&self, limit: usize) -> Result<Vec<MediaEntry>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare_cached(
+            r#"
+SELECT id, url, media_type, file_path, width, height, size_bytes, fetched_at, expires_at, checksum
+FROM media_cache
+ORDER BY COALESCE(last_accessed, fetched_at) ASC
+LIMIT ?1
+"#,
+        )?;
+        let rows = stmt
+            .query_map(params![limit as i64], media_entry_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    pub fn delete_media_entries(&self, ids: &[i64]) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let placeholders = ids
+            .iter()
+            .enumerate()
+            .map(|(i, _)| format!("?{}", i + 1))
+            .collect::<Vec<_>>()
+            .join(",");
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(&format!(
+            "DELETE FROM media_cache WHERE id IN ({})",
+            placeholders
+        ))?;
+        let params_vec = ids
+            .iter()
+            .map(|id| id as &dyn rusqlite::ToSql)
+            .collect::<Vec<_>>();
+        stmt.execute(rusqlite::params_from_iter(params_vec))?;
+        Ok(())
+    }
+}
+
+fn account_from_row(row: &Row<'_>) -> rusqlite::Result<Account> {
+    let created: i64 = row.get(4)?;
+    let updated: i64 = row.get(5)?;
+    Ok(Account {
+        id: row.get(0)?,
         reddit_id: row.get(1)?,
         username: row.get(2)?,
         display_name: row.get(3)?,
@@ -506,6 +1540,21 @@ fn media_entry_from_row(row: &Row<'_>) -> rusqlite::Result<MediaEntry> {
     })
 }
 
+/// Turns free text into an FTS5 expression: every word must appear, and the
+/// last one may be a prefix so results update while typing.
+fn fts_query(raw: &str) -> Option<String> {
+    let terms: Vec<String> = raw
+        .split_whitespace()
+        .map(|word| word.replace('"', ""))
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("\"{word}\""))
+        .collect();
+    if terms.is_empty() {
+        return None;
+    }
+    Some(format!("{}*", terms.join(" ")))
+}
+
 fn migrate(conn: &Connection) -> Result<()> {
     conn.execute(
         r#"
@@ -517,22 +1566,28 @@ CREATE TABLE IF NOT EXISTS schema_migrations (
         [],
     )?;
 
-    let current: i64 = conn
-        .query_row(
-            "SELECT COALESCE(MAX(version), 0) FROM schema_migrations",
-            [],
-            |row| row.get(0),
-        )
-        .unwrap_or(0);
+    let current = applied_version(conn)?;
+    let latest = latest_schema_version();
+    if current > latest {
+        bail!(
+            "storage: database schema v{current} is newer than this build supports (v{latest}); \
+             update HN-TUI or restore an older backup"
+        );
+    }
 
-    let migrations = migrations();
-    for (idx, sql) in migrations.iter().enumerate() {
+    for (idx, sql) in migrations().iter().enumerate() {
         let version = (idx + 1) as i64;
         if version <= current {
             continue;
         }
-        conn.execute_batch(sql)?;
-        conn.execute(
+        // A migration and its version row land together or not at all, so an
+        // interrupted upgrade is retried on the next launch.
+        let tx = conn
+            .unchecked_transaction()
+            .context("storage: begin migration")?;
+        tx.execute_batch(sql)
+            .with_context(|| format!("storage: apply migration {version}"))?;
+        tx.execute(
             "INSERT INTO schema_migrations (version, applied_at) VALUES (?1, ?2)",
             params![
                 version,
@@ -542,10 +1597,37 @@ CREATE TABLE IF NOT EXISTS schema_migrations (
                     .as_secs() as i64,
             ],
         )?;
+        tx.commit()
+            .with_context(|| format!("storage: commit migration {version}"))?;
     }
     Ok(())
 }
 
+fn applied_version(conn: &Connection) -> Result<i64> {
+    conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM schema_migrations",
+        [],
+        |row| row.get(0),
+    )
+    .context("storage: read schema version")
+}
+
+fn database_size(conn: &Connection) -> Result<i64> {
+    conn.query_row(
+        "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+        [],
+        |row| row.get(0),
+    )
+    .context("storage: read database size")
+}
+
+/// Schema version this build migrates databases to.
+pub fn latest_schema_version() -> i64 {
+    migrations().len() as i64
+}
+
+/// Forward-only schema changes; the Nth entry is schema version N. Append new
+/// migrations and never edit shipped ones, or older databases diverge.
 fn migrations() -> Vec<&'static str> {
     vec![
         r#"
@@ -589,12 +1671,148 @@ CREATE TABLE IF NOT EXISTS app_state (
   key TEXT PRIMARY KEY,
   value TEXT NOT NULL
 );
+"#,
+        r#"
+CREATE TABLE IF NOT EXISTS cached_posts (
+  name TEXT PRIMARY KEY,
+  payload TEXT NOT NULL,
+  indexed_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS content_items (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  item_name TEXT NOT NULL UNIQUE,
+  post_name TEXT NOT NULL,
+  title TEXT NOT NULL,
+  body TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_content_items_post ON content_items(post_name);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS content_fts USING fts5(
+  title,
+  body,
+  content='content_items',
+  content_rowid='id'
+);
+
+CREATE TRIGGER IF NOT EXISTS content_items_ai AFTER INSERT ON content_items BEGIN
+  INSERT INTO content_fts(rowid, title, body) VALUES (new.id, new.title, new.body);
+END;
+
+CREATE TRIGGER IF NOT EXISTS content_items_ad AFTER DELETE ON content_items BEGIN
+  INSERT INTO content_fts(content_fts, rowid, title, body) VALUES ('delete', old.id, old.title, old.body);
+END;
+
+CREATE TRIGGER IF NOT EXISTS content_items_au AFTER UPDATE ON content_items BEGIN
+  INSERT INTO content_fts(content_fts, rowid, title, body) VALUES ('delete', old.id, old.title, old.body);
+  INSERT INTO content_fts(rowid, title, body) VALUES (new.id, new.title, new.body);
+END;
+"#,
+        r#"
+CREATE TABLE IF NOT EXISTS comment_drafts (
+  parent TEXT PRIMARY KEY,
+  body TEXT NOT NULL,
+  updated_at INTEGER NOT NULL
+);
+"#,
+        r#"
+ALTER TABLE media_cache ADD COLUMN last_accessed INTEGER;
+UPDATE media_cache SET last_accessed = fetched_at;
+CREATE INDEX IF NOT EXISTS idx_media_cache_last_accessed ON media_cache(last_accessed);
+"#,
+        r#"
+CREATE TABLE IF NOT EXISTS search_history (
+  query TEXT PRIMARY KEY COLLATE NOCASE,
+  last_used INTEGER NOT NULL
+);
+"#,
+        r#"
+CREATE TABLE IF NOT EXISTS subreddit_visits (
+  name TEXT PRIMARY KEY COLLATE NOCASE,
+  visits INTEGER NOT NULL,
+  last_visited INTEGER NOT NULL
+);
+"#,
+        r#"
+CREATE TABLE IF NOT EXISTS pinned_subreddits (
+  name TEXT PRIMARY KEY COLLATE NOCASE,
+  position INTEGER NOT NULL
+);
+"#,
+        r#"
+CREATE TABLE IF NOT EXISTS keyword_alerts (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  keyword TEXT NOT NULL COLLATE NOCASE,
+  subreddit TEXT NOT NULL COLLATE NOCASE,
+  created_at INTEGER NOT NULL,
+  UNIQUE (keyword, subreddit)
+);
+CREATE TABLE IF NOT EXISTS alert_matches (
+  alert_id INTEGER NOT NULL,
+  post_name TEXT NOT NULL,
+  title TEXT NOT NULL,
+  subreddit TEXT NOT NULL,
+  permalink TEXT NOT NULL,
+  matched_at INTEGER NOT NULL,
+  PRIMARY KEY (alert_id, post_name)
+);
+"#,
+        r#"
+CREATE TABLE IF NOT EXISTS session_state (
+  id INTEGER PRIMARY KEY CHECK (id = 1),
+  feed TEXT NOT NULL,
+  sort TEXT NOT NULL,
+  post_name TEXT,
+  post_offset INTEGER NOT NULL DEFAULT 0,
+  selected_comment INTEGER NOT NULL DEFAULT 0,
+  comment_offset INTEGER NOT NULL DEFAULT 0,
+  content_scroll INTEGER NOT NULL DEFAULT 0,
+  saved_at INTEGER NOT NULL
+);
+"#,
+        r#"
+CREATE TABLE IF NOT EXISTS workspaces (
+  name TEXT PRIMARY KEY COLLATE NOCASE,
+  active_feed TEXT NOT NULL,
+  sort TEXT NOT NULL,
+  updated_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS workspace_feeds (
+  workspace TEXT NOT NULL COLLATE NOCASE,
+  position INTEGER NOT NULL,
+  feed TEXT NOT NULL,
+  PRIMARY KEY (workspace, position)
+);
+"#,
+        r#"
+CREATE TABLE IF NOT EXISTS usage_stats (
+  subreddit TEXT PRIMARY KEY COLLATE NOCASE,
+  seconds INTEGER NOT NULL DEFAULT 0,
+  posts_read INTEGER NOT NULL DEFAULT 0,
+  votes INTEGER NOT NULL DEFAULT 0,
+  updated_at INTEGER NOT NULL
+);
+"#,
+        r#"
+CREATE TABLE IF NOT EXISTS thread_visits (
+  post_name TEXT PRIMARY KEY,
+  comment_count INTEGER NOT NULL,
+  visited_at INTEGER NOT NULL
+);
+"#,
+        r#"
+CREATE TABLE IF NOT EXISTS comment_sorts (
+  post_name TEXT PRIMARY KEY,
+  sort TEXT NOT NULL,
+  updated_at INTEGER NOT NULL
+);
 "#,
     ]
 }
 
 pub fn default_path() -> Option<PathBuf> {
-    dirs::config_dir().map(|dir| dir.join("reddix").join("state.db"))
+    dirs::config_dir().map(|dir| profile::scope(dir.join("reddix")).join("state.db"))
 }
 
 #[cfg(test)]
@@ -653,4 +1871,515 @@ mod tests {
 
         store.close().unwrap();
     }
+
+    #[test]
+    fn remember_update_skip_and_snooze() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("state.db");
+        let store = Store::open(Options { path: Some(path) }).unwrap();
+
+        assert_eq!(store.skipped_update_version().unwrap(), None);
+        store
+            .set_skipped_update_version(Some("0.2.0"))
+            .expect("persist skipped version");
+        assert_eq!(
+            store.skipped_update_version().unwrap().as_deref(),
+            Some("0.2.0")
+        );
+        store.set_skipped_update_version(None).expect("clear skip");
+        assert_eq!(store.skipped_update_version().unwrap(), None);
+
+        store
+            .set_update_snoozed_until(Some(1_700_000_000))
+            .expect("persist snooze");
+        assert_eq!(store.update_snoozed_until().unwrap(), Some(1_700_000_000));
+
+        store.close().unwrap();
+    }
+
+    #[test]
+    fn search_cached_posts_matches_titles_and_comments() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("state.db");
+        let store = Store::open(Options { path: Some(path) }).unwrap();
+
+        let posts = vec![
+            CachedPost {
+                name: "s_1".into(),
+                payload: "first".into(),
+            },
+            CachedPost {
+                name: "s_2".into(),
+                payload: "second".into(),
+            },
+        ];
+        let items = vec![
+            IndexedItem {
+                item_name: "s_1".into(),
+                post_name: "s_1".into(),
+                title: "Rust compiler internals".into(),
+                body: String::new(),
+            },
+            IndexedItem {
+                item_name: "s_2".into(),
+                post_name: "s_2".into(),
+                title: "Gardening tips".into(),
+                body: String::new(),
+            },
+            IndexedItem {
+                item_name: "c_9".into(),
+                post_name: "s_2".into(),
+                title: String::new(),
+                body: "Tomatoes love the borrow checker".into(),
+            },
+        ];
+        store.index_content(&posts, &items).expect("index content");
+
+        assert_eq!(
+            store.search_cached_posts("compil", 10).unwrap(),
+            vec!["first"]
+        );
+        assert_eq!(
+            store.search_cached_posts("borrow check", 10).unwrap(),
+            vec!["second"]
+        );
+        assert!(store.search_cached_posts("\"  ", 10).unwrap().is_empty());
+
+        // Re-indexing an item replaces its text instead of matching stale words.
+        store
+            .index_content(
+                &[],
+                &[IndexedItem {
+                    item_name: "c_9".into(),
+                    post_name: "s_2".into(),
+                    title: String::new(),
+                    body: "Compost ratios".into(),
+                }],
+            )
+            .expect("reindex comment");
+        assert!(store.search_cached_posts("borrow", 10).unwrap().is_empty());
+
+        assert_eq!(store.prune_index(Utc::now()).unwrap(), 0);
+        let later = Utc::now() + chrono::Duration::days(100);
+        assert_eq!(store.prune_index(later).unwrap(), 2);
+        assert!(store.search_cached_posts("compost", 10).unwrap().is_empty());
+
+        store.close().unwrap();
+    }
+
+    #[test]
+    fn remember_comment_drafts() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("state.db");
+        let store = Store::open(Options { path: Some(path) }).unwrap();
+
+        assert_eq!(store.comment_draft("t1_abc").unwrap(), None);
+        store
+            .save_comment_draft("t1_abc", "half-written reply")
+            .expect("persist draft");
+        store
+            .save_comment_draft("t1_abc", "half-written reply, revised")
+            .expect("update draft");
+        assert_eq!(
+            store.comment_draft("t1_abc").unwrap().as_deref(),
+            Some("half-written reply, revised")
+        );
+        assert_eq!(store.comment_draft("t3_other").unwrap(), None);
+
+        store
+            .save_comment_draft("t1_abc", "  ")
+            .expect("clear draft");
+        assert_eq!(store.comment_draft("t1_abc").unwrap(), None);
+
+        store.close().unwrap();
+    }
+
+    #[test]
+    fn remember_recent_searches() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("state.db");
+        let store = Store::open(Options { path: Some(path) }).unwrap();
+
+        store.record_search("rust async").expect("record first");
+        store.record_search("ratatui").expect("record second");
+        store.record_search("Rust Async").expect("record repeat");
+        store.record_search("   ").expect("ignore blank");
+        assert_eq!(
+            store.recent_searches(10).unwrap(),
+            vec!["Rust Async".to_string(), "ratatui".to_string()]
+        );
+
+        store.delete_search("rust async").expect("delete search");
+        assert_eq!(store.recent_searches(10).unwrap(), vec!["ratatui"]);
+
+        store.close().unwrap();
+    }
+
+    #[test]
+    fn rank_recent_subreddits_by_visits() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("state.db");
+        let store = Store::open(Options { path: Some(path) }).unwrap();
+
+        store.record_subreddit_visit("r/pics").unwrap();
+        for _ in 0..3 {
+            store.record_subreddit_visit("r/rust").unwrap();
+        }
+        store.record_subreddit_visit("r/Rust").unwrap();
+        assert_eq!(
+            store.recent_subreddits(5).unwrap(),
+            vec!["r/rust".to_string(), "r/pics".to_string()]
+        );
+        assert_eq!(store.recent_subreddits(1).unwrap(), vec!["r/rust"]);
+
+        store.close().unwrap();
+    }
+
+    #[test]
+    fn remember_pinned_subreddit_order() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("state.db");
+        let store = Store::open(Options { path: Some(path) }).unwrap();
+
+        assert!(store.pinned_subreddits().unwrap().is_empty());
+        let pins = vec!["r/rust".to_string(), "r/pics".to_string()];
+        store.set_pinned_subreddits(&pins).unwrap();
+        assert_eq!(store.pinned_subreddits().unwrap(), pins);
+
+        let reordered = vec!["r/pics".to_string(), "r/rust".to_string()];
+        store.set_pinned_subreddits(&reordered).unwrap();
+        assert_eq!(store.pinned_subreddits().unwrap(), reordered);
+
+        store.set_pinned_subreddits(&[]).unwrap();
+        assert!(store.pinned_subreddits().unwrap().is_empty());
+
+        store.close().unwrap();
+    }
+
+    #[test]
+    fn keyword_alerts_record_each_match_once() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("state.db");
+        let store = Store::open(Options { path: Some(path) }).unwrap();
+
+        let id = store.add_keyword_alert("rust", "r/programming").unwrap();
+        let again = store.add_keyword_alert("Rust", "r/programming").unwrap();
+        assert_eq!(again, id);
+        let alerts = store.keyword_alerts().unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].keyword, "rust");
+
+        let found = AlertMatch {
+            alert_id: id,
+            keyword: "rust".into(),
+            post_name: "t3_abc".into(),
+            title: "Rust 2.0 released".into(),
+            subreddit: "r/programming".into(),
+            permalink: "/r/programming/comments/abc/".into(),
+            matched_at: 1,
+        };
+        assert!(store.record_alert_match(&found).unwrap());
+        assert!(!store.record_alert_match(&found).unwrap());
+        assert_eq!(store.alert_matches(10).unwrap(), vec![found.clone()]);
+
+        // Only the newest matches are kept.
+        for n in 0..ALERT_MATCH_LIMIT {
+            let newer = AlertMatch {
+                post_name: format!("t3_{n}"),
+                matched_at: 2 + n as i64,
+                ..found.clone()
+            };
+            store.record_alert_match(&newer).unwrap();
+        }
+        let kept = store.alert_matches(ALERT_MATCH_LIMIT + 1).unwrap();
+        assert_eq!(kept.len(), ALERT_MATCH_LIMIT);
+        assert!(!kept.contains(&found));
+
+        store.delete_keyword_alert(id).unwrap();
+        assert!(store.keyword_alerts().unwrap().is_empty());
+        assert!(store.alert_matches(10).unwrap().is_empty());
+
+        store.close().unwrap();
+    }
+
+    #[test]
+    fn session_state_keeps_latest_save() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("state.db");
+        let store = Store::open(Options { path: Some(path) }).unwrap();
+
+        assert_eq!(store.session_state().unwrap(), None);
+        let mut state = SessionState {
+            feed: "r/rust".into(),
+            sort: "new".into(),
+            post_name: Some("t3_abc".into()),
+            post_offset: 12,
+            selected_comment: 4,
+            comment_offset: 2,
+            content_scroll: 30,
+        };
+        store.save_session_state(&state).unwrap();
+        state.feed = "r/frontpage".into();
+        state.post_name = None;
+        store.save_session_state(&state).unwrap();
+        assert_eq!(store.session_state().unwrap(), Some(state));
+
+        store.close().unwrap();
+    }
+
+    #[test]
+    fn workspaces_replace_by_name() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("state.db");
+        let store = Store::open(Options { path: Some(path) }).unwrap();
+
+        let mut news = Workspace {
+            name: "news".into(),
+            feeds: vec!["r/news".into(), "r/worldnews".into()],
+            active_feed: "r/news".into(),
+            sort: "new".into(),
+        };
+        store.save_workspace(&news).unwrap();
+        news.feeds = vec!["r/europe".into()];
+        news.sort = "top".into();
+        store.save_workspace(&news).unwrap();
+        let rust = Workspace {
+            name: "rust".into(),
+            feeds: Vec::new(),
+            active_feed: "r/rust".into(),
+            sort: "hot".into(),
+        };
+        store.save_workspace(&rust).unwrap();
+        assert_eq!(store.workspaces().unwrap(), vec![news, rust.clone()]);
+
+        store.delete_workspace("NEWS").unwrap();
+        assert_eq!(store.workspaces().unwrap(), vec![rust]);
+
+        store.close().unwrap();
+    }
+
+    #[test]
+    fn usage_stats_accumulate_per_subreddit() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("state.db");
+        let store = Store::open(Options { path: Some(path) }).unwrap();
+
+        let stat = |subreddit: &str, seconds, posts_read, votes| UsageStat {
+            subreddit: subreddit.into(),
+            seconds,
+            posts_read,
+            votes,
+        };
+        store
+            .record_usage(&[stat("rust", 60, 2, 1), stat("linux", 90, 1, 0)])
+            .unwrap();
+        store.record_usage(&[stat("Rust", 45, 1, 2)]).unwrap();
+
+        assert_eq!(
+            store.usage_stats().unwrap(),
+            vec![stat("rust", 105, 3, 3), stat("linux", 90, 1, 0)]
+        );
+
+        store.close().unwrap();
+    }
+
+    #[test]
+    fn private_mode_skips_browsing_records() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("state.db");
+        let store = Store::open(Options { path: Some(path) }).unwrap();
+
+        store.record_search("before").unwrap();
+        store.clone().set_private(true);
+        assert!(store.is_private());
+        store.record_search("during").unwrap();
+        store.record_subreddit_visit("rust").unwrap();
+        store.record_thread_visit("t3_abc", 5).unwrap();
+        store.set_comment_sort("t3_abc", "new").unwrap();
+        store.save_comment_draft("t1_abc", "secret").unwrap();
+        store.set_pinned_subreddits(&["rust".to_string()]).unwrap();
+
+        assert_eq!(
+            store.recent_searches(10).unwrap(),
+            vec!["before".to_string()]
+        );
+        assert!(store.recent_subreddits(10).unwrap().is_empty());
+        assert!(store
+            .seen_comment_counts(&["t3_abc".to_string()])
+            .unwrap()
+            .is_empty());
+        assert_eq!(store.comment_sort("t3_abc").unwrap(), None);
+        assert_eq!(store.comment_draft("t1_abc").unwrap(), None);
+        assert_eq!(store.pinned_subreddits().unwrap(), vec!["rust".to_string()]);
+
+        store.set_private(false);
+        store.record_search("after").unwrap();
+        assert_eq!(
+            store.recent_searches(10).unwrap(),
+            vec!["after".to_string(), "before".to_string()]
+        );
+
+        store.close().unwrap();
+    }
+
+    #[test]
+    fn thread_visits_keep_the_latest_comment_count() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("state.db");
+        let store = Store::open(Options { path: Some(path) }).unwrap();
+
+        store.record_thread_visit("t3_a", 10).unwrap();
+        store.record_thread_visit("t3_a", 25).unwrap();
+        let names = ["t3_a".to_string(), "t3_b".to_string()];
+        let counts = store.seen_comment_counts(&names).unwrap();
+        assert_eq!(counts.len(), 1);
+        assert_eq!(counts.get("t3_a"), Some(&25));
+
+        let later = Utc::now() + chrono::Duration::days(200);
+        let pruned = store.prune_stale(later).unwrap();
+        assert_eq!(pruned.thread_visits, 1);
+        assert!(store.seen_comment_counts(&names).unwrap().is_empty());
+
+        store.close().unwrap();
+    }
+
+    #[test]
+    fn comment_sorts_are_saved_per_post() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("state.db");
+        let store = Store::open(Options { path: Some(path) }).unwrap();
+
+        assert_eq!(store.comment_sort("t3_a").unwrap(), None);
+        store.set_comment_sort("t3_a", "top").unwrap();
+        store.set_comment_sort("t3_a", "new").unwrap();
+        assert_eq!(store.comment_sort("t3_a").unwrap().as_deref(), Some("new"));
+        assert_eq!(store.comment_sort("t3_b").unwrap(), None);
+
+        assert_eq!(store.prune_comment_sorts(Utc::now()).unwrap(), 0);
+        let later = Utc::now() + chrono::Duration::days(200);
+        assert_eq!(store.prune_comment_sorts(later).unwrap(), 1);
+        assert_eq!(store.comment_sort("t3_a").unwrap(), None);
+
+        store.close().unwrap();
+    }
+
+    #[test]
+    fn media_eviction_prefers_least_recently_used() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("state.db");
+        let store = Store::open(Options { path: Some(path) }).unwrap();
+
+        let entry = |url: &str, age_secs: i64| MediaEntry {
+            id: 0,
+            url: url.into(),
+            media_type: "image/png".into(),
+            file_path: format!("/tmp/{url}.bin"),
+            width: 0,
+            height: 0,
+            size_bytes: 100,
+            fetched_at: Utc::now() - chrono::Duration::seconds(age_secs),
+            expires_at: None,
+            checksum: String::new(),
+        };
+        let older = store.upsert_media_entry(entry("older", 100)).unwrap();
+        store.upsert_media_entry(entry("newer", 50)).unwrap();
+        store.touch_media_entry(older).expect("touch entry");
+
+        let order = store.list_least_recent_media(10).unwrap();
+        assert_eq!(order[0].url, "newer");
+        assert_eq!(
+            store.media_cache_stats().unwrap(),
+            MediaCacheStats {
+                entries: 2,
+                size_bytes: 200,
+            }
+        );
+
+        store.close().unwrap();
+    }
+
+    #[test]
+    fn database_token_storage_keeps_secrets_in_the_table() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("state.db");
+        let store = Store::open(Options { path: Some(path) })
+            .unwrap()
+            .with_token_storage(secrets::Mode::Database);
+        let account_id = store
+            .upsert_account(Account {
+                id: 0,
+                reddit_id: "t2_me".to_string(),
+                username: "me".to_string(),
+                display_name: "me".to_string(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .unwrap();
+        store
+            .upsert_token(Token {
+                account_id,
+                access_token: "access".to_string(),
+                refresh_token: "refresh".to_string(),
+                token_type: "bearer".to_string(),
+                scope: vec!["read".to_string(), "vote".to_string()],
+                expires_at: Utc::now(),
+            })
+            .unwrap();
+
+        let token = store.get_token(account_id).unwrap().unwrap();
+        assert_eq!(token.access_token, "access");
+        assert_eq!(token.refresh_token, "refresh");
+        assert_eq!(token.scope, ["read", "vote"]);
+        assert!(store.get_token(account_id + 1).unwrap().is_none());
+
+        store.close().unwrap();
+    }
+
+    #[test]
+    fn newer_schemas_are_refused() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("state.db");
+        let store = Store::open(Options {
+            path: Some(path.clone()),
+        })
+        .unwrap();
+        assert_eq!(store.schema_version().unwrap(), latest_schema_version());
+        assert!(store.check_integrity().unwrap().is_empty());
+        store.close().unwrap();
+
+        let conn = Connection::open(&path).unwrap();
+        conn.execute(
+            "INSERT INTO schema_migrations (version, applied_at) VALUES (?1, 0)",
+            params![latest_schema_version() + 1],
+        )
+        .unwrap();
+        drop(conn);
+        let err = Store::open(Options { path: Some(path) }).unwrap_err();
+        assert!(err.to_string().contains("newer than this build"));
+    }
+
+    #[test]
+    fn compacting_returns_freed_pages() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("state.db");
+        let store = Store::open(Options { path: Some(path) }).unwrap();
+        let posts: Vec<_> = (0..200)
+            .map(|i| CachedPost {
+                name: format!("t3_{i}"),
+                payload: "x".repeat(4096),
+            })
+            .collect();
+        store.index_content(&posts, &[]).unwrap();
+        store
+            .conn
+            .lock()
+            .execute("DELETE FROM cached_posts", [])
+            .unwrap();
+
+        let compaction = store.compact().unwrap();
+        assert!(compaction.bytes_after < compaction.bytes_before);
+        assert_eq!(store.size_bytes().unwrap(), compaction.bytes_after);
+        assert!(store.check_integrity().unwrap().is_empty());
+        store.close().unwrap();
+    }
 }
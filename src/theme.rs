@@ -0,0 +1,228 @@
+use std::str::FromStr;
+
+use once_cell::sync::OnceCell;
+use ratatui::style::Color;
+
+use crate::config;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Theme {
+    #[default]
+    Default,
+    HighContrast,
+    Monochrome,
+}
+
+impl Theme {
+    pub fn from_config(value: &str) -> Self {
+        match value.trim().to_ascii_lowercase().replace('_', "-").as_str() {
+            "high-contrast" | "contrast" => Theme::HighContrast,
+            "monochrome" | "mono" | "none" => Theme::Monochrome,
+            _ => Theme::Default,
+        }
+    }
+
+    pub fn palette(self) -> Palette {
+        let (base, depth): (Palette, &[Color]) = match self {
+            Theme::Default => (DEFAULT_PALETTE, &DEFAULT_COMMENT_DEPTH),
+            Theme::HighContrast => (HIGH_CONTRAST_PALETTE, &HIGH_CONTRAST_COMMENT_DEPTH),
+            Theme::Monochrome => (MONOCHROME_PALETTE, &[Color::Reset]),
+        };
+        Palette {
+            comment_depth: depth.to_vec(),
+            ..base
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+    pub bg: Color,
+    pub panel_bg: Color,
+    pub panel_focused_bg: Color,
+    pub panel_selected_bg: Color,
+    pub border_idle: Color,
+    pub border_focused: Color,
+    pub text_primary: Color,
+    pub text_secondary: Color,
+    pub accent: Color,
+    pub success: Color,
+    pub error: Color,
+    pub comment_depth: Vec<Color>,
+    /// Prefix nested comments with depth-specific guide glyphs instead of blank indent.
+    pub indent_guides: bool,
+    /// Draw `▶` next to the selected row instead of relying on background color.
+    pub selection_markers: bool,
+    /// Mark controversial comment scores with `†`, as old.reddit does.
+    pub controversial_marker: bool,
+    /// Drop the fixed colors the markdown renderer uses for quotes, code, and links.
+    pub monochrome: bool,
+}
+
+const DEFAULT_PALETTE: Palette = Palette {
+    bg: Color::Rgb(30, 30, 46),
+    panel_bg: Color::Rgb(24, 24, 36),
+    panel_focused_bg: Color::Rgb(49, 50, 68),
+    panel_selected_bg: Color::Rgb(69, 71, 90),
+    border_idle: Color::Rgb(49, 50, 68),
+    border_focused: Color::Rgb(137, 180, 250),
+    text_primary: Color::Rgb(205, 214, 244),
+    text_secondary: Color::Rgb(166, 173, 200),
+    accent: Color::Rgb(137, 180, 250),
+    success: Color::Rgb(166, 227, 161),
+    error: Color::Rgb(243, 139, 168),
+    comment_depth: Vec::new(),
+    indent_guides: false,
+    selection_markers: false,
+    controversial_marker: true,
+    monochrome: false,
+};
+
+const DEFAULT_COMMENT_DEPTH: [Color; 6] = [
+    Color::Rgb(250, 179, 135),
+    Color::Rgb(166, 227, 161),
+    Color::Rgb(203, 166, 247),
+    Color::Rgb(245, 194, 231),
+    Color::Rgb(137, 220, 235),
+    Color::Rgb(249, 226, 175),
+];
+
+// Named ANSI colors so limited terminals map them onto their own bright palette.
+const HIGH_CONTRAST_PALETTE: Palette = Palette {
+    bg: Color::Black,
+    panel_bg: Color::Black,
+    panel_focused_bg: Color::Blue,
+    panel_selected_bg: Color::Blue,
+    border_idle: Color::White,
+    border_focused: Color::Yellow,
+    text_primary: Color::White,
+    text_secondary: Color::Gray,
+    accent: Color::Yellow,
+    success: Color::LightGreen,
+    error: Color::LightRed,
+    comment_depth: Vec::new(),
+    indent_guides: false,
+    selection_markers: true,
+    controversial_marker: true,
+    monochrome: false,
+};
+
+const HIGH_CONTRAST_COMMENT_DEPTH: [Color; 6] = [
+    Color::Yellow,
+    Color::LightCyan,
+    Color::LightGreen,
+    Color::LightMagenta,
+    Color::White,
+    Color::LightBlue,
+];
+
+const MONOCHROME_PALETTE: Palette = Palette {
+    bg: Color::Reset,
+    panel_bg: Color::Reset,
+    panel_focused_bg: Color::Reset,
+    panel_selected_bg: Color::Reset,
+    border_idle: Color::Reset,
+    border_focused: Color::Reset,
+    text_primary: Color::Reset,
+    text_secondary: Color::Reset,
+    accent: Color::Reset,
+    success: Color::Reset,
+    error: Color::Reset,
+    comment_depth: Vec::new(),
+    indent_guides: true,
+    selection_markers: true,
+    controversial_marker: true,
+    monochrome: true,
+};
+
+const INDENT_GUIDES: [&str; 4] = ["│", "┆", "┊", "╎"];
+
+impl Palette {
+    /// Builds the palette for `ui.theme`, then applies `ui.comment_depth_colors`
+    /// and `ui.comment_indent_guides` on top. Unparseable colors are skipped.
+    pub fn from_config(ui: &config::UIConfig) -> Self {
+        let mut palette = Theme::from_config(&ui.theme).palette();
+        let colors: Vec<Color> = ui
+            .comment_depth_colors
+            .iter()
+            .filter_map(|raw| Color::from_str(raw.trim()).ok())
+            .collect();
+        if !colors.is_empty() {
+            palette.comment_depth = colors;
+        }
+        palette.indent_guides |= ui.comment_indent_guides;
+        palette.controversial_marker &= !ui.hide_controversial_marker;
+        palette
+    }
+}
+
+static PALETTE: OnceCell<Palette> = OnceCell::new();
+
+/// Installs the palette for the session. Only the first call takes effect.
+pub fn install(palette: Palette) {
+    let _ = PALETTE.set(palette);
+}
+
+pub fn palette() -> &'static Palette {
+    PALETTE.get_or_init(|| Theme::Default.palette())
+}
+
+pub fn comment_depth_color(depth: usize) -> Color {
+    let colors = &palette().comment_depth;
+    if colors.is_empty() {
+        return Color::Reset;
+    }
+    colors[depth % colors.len()]
+}
+
+pub fn indent_guide(depth: usize) -> &'static str {
+    INDENT_GUIDES[depth % INDENT_GUIDES.len()]
+}
+
+/// Maps a fixed accent color onto the active theme.
+pub fn tint(color: Color) -> Color {
+    if palette().monochrome {
+        Color::Reset
+    } else {
+        color
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn theme_names_parse() {
+        assert_eq!(Theme::from_config("high_contrast"), Theme::HighContrast);
+        assert_eq!(Theme::from_config("Monochrome"), Theme::Monochrome);
+        assert_eq!(Theme::from_config("default"), Theme::Default);
+        assert_eq!(Theme::from_config("unknown"), Theme::Default);
+        assert!(Theme::Monochrome.palette().selection_markers);
+    }
+
+    #[test]
+    fn palette_applies_depth_colors() {
+        let ui = config::UIConfig {
+            comment_depth_colors: vec!["#ff0000".into(), "bogus".into(), "cyan".into()],
+            comment_indent_guides: true,
+            ..Default::default()
+        };
+        let palette = Palette::from_config(&ui);
+        assert_eq!(
+            palette.comment_depth,
+            vec![Color::Rgb(255, 0, 0), Color::Cyan]
+        );
+        assert!(palette.indent_guides);
+        assert!(palette.controversial_marker);
+    }
+
+    #[test]
+    fn controversial_marker_can_be_hidden() {
+        let ui = config::UIConfig {
+            hide_controversial_marker: true,
+            ..Default::default()
+        };
+        assert!(!Palette::from_config(&ui).controversial_marker);
+    }
+}
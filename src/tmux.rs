@@ -0,0 +1,163 @@
+use std::env;
+use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+
+/// tmux settings that let inline images through to the outer terminal.
+pub const SETUP: &str = "\
+set -g allow-passthrough on
+set -ga update-environment TERM
+set -ga update-environment TERM_PROGRAM";
+
+/// First release with the `allow-passthrough` option; older releases pass
+/// escape sequences through unconditionally.
+const PASSTHROUGH_OPTION_SINCE: Version = Version { major: 3, minor: 3 };
+
+static PROBE: OnceLock<Option<Probe>> = OnceLock::new();
+static PANE_OFFSET: Mutex<Option<(u16, u16)>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Probe {
+    pub version: Option<Version>,
+    pub passthrough: bool,
+    /// `TERM` of the terminal tmux itself runs in.
+    pub client_term: Option<String>,
+}
+
+/// True when this process runs in a tmux pane, so escape sequences meant
+/// for the outer terminal must be wrapped with [`wrap`].
+pub fn active() -> bool {
+    env::var("TMUX").is_ok_and(|value| !value.is_empty())
+}
+
+/// Asks the tmux server for its version, `allow-passthrough`, and the outer
+/// terminal. Runs once; `None` outside tmux or when `tmux` cannot be run.
+pub fn probe() -> Option<&'static Probe> {
+    PROBE
+        .get_or_init(|| {
+            if !active() {
+                return None;
+            }
+            let version = tmux_output(&["-V"])?;
+            let info = tmux_output(&["display", "-p", "#{allow-passthrough}\t#{client_termname}"])
+                .unwrap_or_default();
+            let (option, client_term) = info.split_once('\t').unwrap_or((info.as_str(), ""));
+            let version = parse_version(&version);
+            let probe = Probe {
+                version,
+                passthrough: passthrough_allowed(version, option),
+                client_term: Some(client_term.trim().to_string()).filter(|term| !term.is_empty()),
+            };
+            tracing::debug!(?probe, "tmux probe");
+            Some(probe)
+        })
+        .as_ref()
+}
+
+fn tmux_output(args: &[&str]) -> Option<String> {
+    let output = Command::new("tmux").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Parses `tmux -V` output such as `tmux 3.3a` or `tmux next-3.5`.
+fn parse_version(raw: &str) -> Option<Version> {
+    let raw = raw.trim().strip_prefix("tmux ")?;
+    let raw = raw.strip_prefix("next-").unwrap_or(raw);
+    let (major, rest) = raw.split_once('.')?;
+    let minor: String = rest.chars().take_while(char::is_ascii_digit).collect();
+    Some(Version {
+        major: major.parse().ok()?,
+        minor: minor.parse().ok()?,
+    })
+}
+
+/// Unparseable versions (`tmux master`) are treated as current releases.
+fn passthrough_allowed(version: Option<Version>, option: &str) -> bool {
+    if version.is_some_and(|version| version < PASSTHROUGH_OPTION_SINCE) {
+        return true;
+    }
+    matches!(option.trim(), "on" | "all")
+}
+
+/// Wraps `sequence` in a tmux passthrough DCS, doubling every escape so
+/// nested terminators reach the outer terminal intact.
+pub fn wrap(sequence: &str) -> String {
+    format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+}
+
+/// Column and row of the current pane within the tmux window.
+pub fn pane_offset() -> (u16, u16) {
+    let mut cached = PANE_OFFSET.lock().unwrap_or_else(|err| err.into_inner());
+    *cached.get_or_insert_with(|| {
+        tmux_output(&["display", "-p", "#{pane_left} #{pane_top}"])
+            .and_then(|raw| parse_pane_offset(&raw))
+            .unwrap_or((0, 0))
+    })
+}
+
+/// Drops the cached pane offset; call when the pane may have moved.
+pub fn refresh_pane_offset() {
+    *PANE_OFFSET.lock().unwrap_or_else(|err| err.into_inner()) = None;
+}
+
+fn parse_pane_offset(raw: &str) -> Option<(u16, u16)> {
+    let (left, top) = raw.trim().split_once(' ')?;
+    Some((left.parse().ok()?, top.parse().ok()?))
+}
+
+/// `sequence` drawn at pane cell `(col, row)`. The cursor move travels inside
+/// the passthrough in outer-terminal coordinates, because the outer cursor
+/// need not match the pane cursor when tmux forwards the sequence; it is
+/// saved and restored around the move.
+pub fn wrap_at(sequence: &str, col: u16, row: u16, pane_offset: (u16, u16)) -> String {
+    let (left, top) = pane_offset;
+    let col = u32::from(col) + u32::from(left) + 1;
+    let row = u32::from(row) + u32::from(top) + 1;
+    wrap(&format!("\x1b7\x1b[{row};{col}H{sequence}\x1b8"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_parse_from_release_and_next_builds() {
+        let version = |major, minor| Some(Version { major, minor });
+        assert_eq!(parse_version("tmux 3.3a"), version(3, 3));
+        assert_eq!(parse_version("tmux 2.9"), version(2, 9));
+        assert_eq!(parse_version("tmux next-3.5\n"), version(3, 5));
+        assert_eq!(parse_version("tmux master"), None);
+    }
+
+    #[test]
+    fn passthrough_needs_the_option_from_3_3() {
+        let old = parse_version("tmux 3.2a");
+        let new = parse_version("tmux 3.4");
+        assert!(passthrough_allowed(old, ""));
+        assert!(!passthrough_allowed(new, "off"));
+        assert!(passthrough_allowed(new, "on"));
+        assert!(passthrough_allowed(new, "all"));
+        assert!(!passthrough_allowed(None, ""));
+    }
+
+    #[test]
+    fn wrapped_sequences_double_every_escape() {
+        assert_eq!(
+            wrap("\x1b_Ga=d;\x1b\\"),
+            "\x1bPtmux;\x1b\x1b_Ga=d;\x1b\x1b\\\x1b\\"
+        );
+        assert_eq!(
+            wrap_at("X", 2, 3, (40, 10)),
+            "\x1bPtmux;\x1b\x1b7\x1b\x1b[14;43HX\x1b\x1b8\x1b\\"
+        );
+        assert_eq!(parse_pane_offset("81 0\n"), Some((81, 0)));
+    }
+}
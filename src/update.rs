@@ -7,12 +7,13 @@ use std::process::Command;
 use std::time::Duration;
 
 use anyhow::{bail, Context, Result};
-use reqwest::blocking::Client;
 use reqwest::StatusCode;
 use semver::Version;
 use serde::Deserialize;
 use tempfile::Builder as TempFileBuilder;
 
+use crate::net;
+
 #[cfg(target_os = "windows")]
 const INSTALLER_NAME: &str = "hn-tui-installer.ps1";
 #[cfg(not(target_os = "windows"))]
@@ -21,9 +22,129 @@ const INSTALLER_NAME: &str = "hn-tui-installer.sh";
 pub const SKIP_UPDATE_ENV: &str = "HN_TUI_SKIP_UPDATE_CHECK";
 
 const RELEASES_URL: &str = "https://api.github.com/repos/danielmerja/hn-tui/releases/latest";
+const RELEASE_LIST_URL: &str =
+    "https://api.github.com/repos/danielmerja/hn-tui/releases?per_page=20";
 const FORCE_VERSION_ENV: &str = "HN_TUI_FORCE_UPDATE_VERSION";
 const FORCE_URL_ENV: &str = "HN_TUI_FORCE_UPDATE_URL";
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Channel {
+    #[default]
+    Stable,
+    Prerelease,
+}
+
+impl Channel {
+    pub fn from_config(value: &str) -> Self {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "prerelease" | "pre-release" | "beta" | "nightly" => Channel::Prerelease,
+            _ => Channel::Stable,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Channel::Stable => "stable",
+            Channel::Prerelease => "pre-release",
+        }
+    }
+}
+
+/// Packagers can set `HN_TUI_INSTALL_METHOD` at build time (`cargo`, `homebrew`,
+/// `aur`, `distro`, `nix`) so the updater never overwrites a managed binary.
+const BUILD_INSTALL_METHOD: Option<&str> = option_env!("HN_TUI_INSTALL_METHOD");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstallMethod {
+    Installer,
+    Cargo,
+    Homebrew,
+    Aur,
+    Distro,
+    Nix,
+}
+
+impl InstallMethod {
+    pub fn detect() -> Self {
+        if let Some(method) = BUILD_INSTALL_METHOD.and_then(Self::from_build_hint) {
+            return method;
+        }
+        let Ok(exe) = env::current_exe() else {
+            return InstallMethod::Installer;
+        };
+        let exe = exe.canonicalize().unwrap_or(exe);
+        Self::from_path(&exe, Path::new("/etc/arch-release").exists())
+    }
+
+    fn from_build_hint(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "cargo" => Some(InstallMethod::Cargo),
+            "homebrew" | "brew" => Some(InstallMethod::Homebrew),
+            "aur" | "pacman" => Some(InstallMethod::Aur),
+            "distro" | "deb" | "rpm" | "package" => Some(InstallMethod::Distro),
+            "nix" => Some(InstallMethod::Nix),
+            "installer" | "github" => Some(InstallMethod::Installer),
+            _ => None,
+        }
+    }
+
+    fn from_path(exe: &Path, arch_linux: bool) -> Self {
+        let path = exe.to_string_lossy().replace('\\', "/");
+        if path.contains("/.cargo/bin/") {
+            InstallMethod::Cargo
+        } else if path.contains("/Cellar/")
+            || path.starts_with("/opt/homebrew/")
+            || path.starts_with("/home/linuxbrew/")
+        {
+            InstallMethod::Homebrew
+        } else if path.starts_with("/nix/store/") {
+            InstallMethod::Nix
+        } else if path.starts_with("/usr/bin/") || path.starts_with("/usr/sbin/") {
+            if arch_linux {
+                InstallMethod::Aur
+            } else {
+                InstallMethod::Distro
+            }
+        } else {
+            InstallMethod::Installer
+        }
+    }
+
+    pub fn is_managed(self) -> bool {
+        self != InstallMethod::Installer
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            InstallMethod::Installer => "installer",
+            InstallMethod::Cargo => "cargo",
+            InstallMethod::Homebrew => "Homebrew",
+            InstallMethod::Aur => "the AUR",
+            InstallMethod::Distro => "your system package manager",
+            InstallMethod::Nix => "Nix",
+        }
+    }
+
+    pub fn upgrade_command(self) -> Option<&'static str> {
+        match self {
+            InstallMethod::Installer => None,
+            InstallMethod::Cargo => Some("cargo install hn-tui --locked"),
+            InstallMethod::Homebrew => Some("brew upgrade hn-tui"),
+            InstallMethod::Aur => Some("paru -Syu hn-tui"),
+            InstallMethod::Distro => Some("your package manager (apt, dnf, …)"),
+            InstallMethod::Nix => Some("nix profile upgrade hn-tui"),
+        }
+    }
+
+    pub fn upgrade_hint(self) -> Option<String> {
+        let command = self.upgrade_command()?;
+        Some(format!(
+            "HN-TUI was installed via {}; upgrade with {command}.",
+            self.label()
+        ))
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct UpdateInfo {
     pub version: Version,
@@ -39,13 +160,12 @@ struct Release {
     prerelease: bool,
 }
 
-pub fn check_for_update(current: &Version) -> Result<Option<UpdateInfo>> {
+pub fn check_for_update(current: &Version, channel: Channel) -> Result<Option<UpdateInfo>> {
     if let Some(update) = forced_update(current)? {
         return Ok(Some(update));
     }
 
-    let client = Client::builder()
-        .timeout(Duration::from_secs(8))
+    let client = net::client_builder()
         .user_agent(format!(
             "hn-tui/{version} (update-check)",
             version = crate::VERSION
@@ -53,11 +173,14 @@ pub fn check_for_update(current: &Version) -> Result<Option<UpdateInfo>> {
         .build()
         .context("build update HTTP client")?;
 
-    let response = client
-        .get(RELEASES_URL)
-        .header("Accept", "application/vnd.github+json")
-        .send()
-        .context("request latest release metadata")?;
+    let url = match channel {
+        Channel::Stable => RELEASES_URL,
+        Channel::Prerelease => RELEASE_LIST_URL,
+    };
+    let request = client
+        .get(url)
+        .header("Accept", "application/vnd.github+json");
+    let response = net::send(request).context("request latest release metadata")?;
 
     if response.status() == StatusCode::NOT_FOUND {
         return Ok(None);
@@ -71,31 +194,43 @@ pub fn check_for_update(current: &Version) -> Result<Option<UpdateInfo>> {
         bail!("update check failed with status {}", response.status());
     }
 
-    let release: Release = response
-        .json()
-        .context("decode release response from GitHub")?;
+    let releases: Vec<Release> = match channel {
+        Channel::Stable => vec![response
+            .json()
+            .context("decode release response from GitHub")?],
+        Channel::Prerelease => response.json().context("decode release list from GitHub")?,
+    };
 
-    if release.draft || release.prerelease {
-        return Ok(None);
-    }
+    Ok(select_release(releases, current, channel))
+}
 
-    let tag = release.tag_name.trim();
-    let normalized = tag
-        .strip_prefix('v')
-        .or_else(|| tag.strip_prefix('V'))
-        .unwrap_or(tag);
-    let version = Version::parse(normalized)
-        .with_context(|| format!("parse release tag {tag:?} as semantic version"))?;
-
-    if &version > current {
-        Ok(Some(UpdateInfo {
-            version,
-            release_url: release.html_url,
-            tag: tag.to_string(),
-        }))
-    } else {
-        Ok(None)
-    }
+fn select_release(
+    releases: Vec<Release>,
+    current: &Version,
+    channel: Channel,
+) -> Option<UpdateInfo> {
+    releases
+        .into_iter()
+        .filter(|release| !release.draft)
+        .filter(|release| channel == Channel::Prerelease || !release.prerelease)
+        .filter_map(|release| {
+            let tag = release.tag_name.trim().to_string();
+            let normalized = tag
+                .strip_prefix('v')
+                .or_else(|| tag.strip_prefix('V'))
+                .unwrap_or(&tag);
+            let version = Version::parse(normalized).ok()?;
+            if channel == Channel::Stable && !version.pre.is_empty() {
+                return None;
+            }
+            Some(UpdateInfo {
+                version,
+                release_url: release.html_url,
+                tag,
+            })
+        })
+        .filter(|info| &info.version > current)
+        .max_by(|a, b| a.version.cmp(&b.version))
 }
 
 fn forced_update(current: &Version) -> Result<Option<UpdateInfo>> {
@@ -156,9 +291,12 @@ fn release_download_base(release_url: &str, tag: &str) -> String {
 }
 
 pub fn install_update(info: &UpdateInfo) -> Result<()> {
+    if let Some(hint) = InstallMethod::detect().upgrade_hint() {
+        bail!(hint);
+    }
     let installer_url = info.installer_url();
 
-    let client = Client::builder()
+    let client = net::client_builder()
         .timeout(Duration::from_secs(60))
         .user_agent(format!(
             "hn-tui/{version} (update-install)",
@@ -269,6 +407,44 @@ mod tests {
         env::remove_var(FORCE_URL_ENV);
     }
 
+    #[test]
+    fn install_method_from_path() {
+        let detect = |path: &str, arch| InstallMethod::from_path(Path::new(path), arch);
+        assert_eq!(
+            detect("/home/me/.cargo/bin/hn-tui", false),
+            InstallMethod::Cargo
+        );
+        assert_eq!(
+            detect("/opt/homebrew/Cellar/hn-tui/0.1.0/bin/hn-tui", false),
+            InstallMethod::Homebrew
+        );
+        assert_eq!(detect("/usr/bin/hn-tui", true), InstallMethod::Aur);
+        assert_eq!(detect("/usr/bin/hn-tui", false), InstallMethod::Distro);
+        assert_eq!(
+            detect("/home/me/.local/bin/hn-tui", false),
+            InstallMethod::Installer
+        );
+    }
+
+    fn release(tag: &str, prerelease: bool) -> Release {
+        Release {
+            tag_name: tag.to_string(),
+            html_url: format!("https://example.com/{tag}"),
+            draft: false,
+            prerelease,
+        }
+    }
+
+    #[test]
+    fn select_release_respects_channel() {
+        let current = Version::parse("0.1.0").unwrap();
+        let releases = || vec![release("v0.1.1", false), release("v0.2.0-beta.1", true)];
+        let stable = select_release(releases(), &current, Channel::Stable).unwrap();
+        assert_eq!(stable.tag, "v0.1.1");
+        let beta = select_release(releases(), &current, Channel::Prerelease).unwrap();
+        assert_eq!(beta.version, Version::parse("0.2.0-beta.1").unwrap());
+    }
+
     #[test]
     fn forced_update_is_ignored_when_version_not_newer() {
         let _guard = guard_env();
@@ -1,14 +1,14 @@
 use std::borrow::Cow;
-use std::fs::{self, OpenOptions};
+use std::fs;
 use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
 use std::process::{Command, ExitStatus, Stdio};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use crossbeam_channel::{bounded, Receiver, Sender, TryRecvError};
-use once_cell::sync::OnceCell;
 use serde_json::json;
 
 #[cfg(any(unix, target_os = "windows"))]
@@ -16,54 +16,12 @@ use rand::{distributions::Alphanumeric, Rng};
 #[cfg(unix)]
 use std::os::unix::net::UnixStream;
 
+use crate::net;
 use crate::reddit::{self, PostMedia, RedditVideo};
 
-fn video_debug_enabled() -> bool {
-    static FLAG: OnceCell<bool> = OnceCell::new();
-    *FLAG.get_or_init(|| {
-        std::env::var("HN_TUI_DEBUG_VIDEO")
-            .map(|val| {
-                let trimmed = val.trim();
-                !(trimmed.is_empty()
-                    || trimmed.eq_ignore_ascii_case("0")
-                    || trimmed.eq_ignore_ascii_case("false")
-                    || trimmed.eq_ignore_ascii_case("no")
-                    || trimmed.eq_ignore_ascii_case("off"))
-            })
-            .unwrap_or(false)
-    })
-}
+/// Process ids of inline mpv players that are still running.
+static RUNNING_PLAYERS: Mutex<Vec<u32>> = Mutex::new(Vec::new());
 
-fn video_debug_writer() -> Option<&'static Mutex<std::fs::File>> {
-    static WRITER: OnceCell<Option<Mutex<std::fs::File>>> = OnceCell::new();
-    WRITER
-        .get_or_init(|| {
-            std::env::var("HN_TUI_DEBUG_VIDEO_LOG")
-                .ok()
-                .and_then(|path| {
-                    OpenOptions::new()
-                        .create(true)
-                        .append(true)
-                        .open(path)
-                        .map(Mutex::new)
-                        .ok()
-                })
-        })
-        .as_ref()
-}
-
-pub fn debug_log(message: impl AsRef<str>) {
-    if !video_debug_enabled() {
-        return;
-    }
-    if let Some(writer) = video_debug_writer() {
-        if let Ok(mut file) = writer.lock() {
-            let _ = writeln!(file, "{}", message.as_ref());
-            return;
-        }
-    }
-    eprintln!("{}", message.as_ref());
-}
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct VideoSource {
     pub playback_url: String,
@@ -114,7 +72,7 @@ fn some_positive(value: i64) -> Option<i64> {
 }
 
 fn sanitize_url(raw: &str) -> String {
-    raw.trim().replace("&amp;", "&")
+    crate::markdown::decode_html_entities(raw.trim())
 }
 
 fn push_http_headers(args: &mut Vec<String>) {
@@ -133,6 +91,38 @@ fn push_http_headers(args: &mut Vec<String>) {
     }
 }
 
+/// mpv fetches remote URLs itself, so it has to follow the configured route:
+/// an HTTP proxy is passed on, and a SOCKS route, which mpv can't use, refuses
+/// streaming so a video is only played once it was downloaded through it.
+fn push_proxy_args(args: &mut Vec<String>, playback: &str) -> Result<()> {
+    let proxy = proxy_args(
+        playback,
+        net::route_label().as_deref(),
+        net::http_proxy_url().as_deref(),
+    )?;
+    args.extend(proxy);
+    Ok(())
+}
+
+fn proxy_args(
+    playback: &str,
+    socks_route: Option<&str>,
+    http_proxy: Option<&str>,
+) -> Result<Vec<String>> {
+    let remote = playback.starts_with("http://") || playback.starts_with("https://");
+    if !remote {
+        return Ok(Vec::new());
+    }
+    if let Some(route) = socks_route {
+        bail!(
+            "video: streaming is off while traffic goes through {route}, and the download failed"
+        );
+    }
+    Ok(http_proxy
+        .map(|proxy| vec![format!("--http-proxy={proxy}")])
+        .unwrap_or_default())
+}
+
 pub fn find_video_source(post: &reddit::Post) -> Option<VideoSource> {
     video_from_media(post.secure_media.as_ref(), &post.title)
         .or_else(|| video_from_media(post.media.as_ref(), &post.title))
@@ -247,13 +237,10 @@ pub fn spawn_inline_player(opts: InlineLaunchOptions<'_>) -> Result<InlineSessio
     let playback_target = opts.playback.into_owned();
     let remote_url = opts.source.playback_url.clone();
     let label = opts.source.label.clone();
-    let debug_enabled = video_debug_enabled();
-    #[cfg(unix)]
+    let debug_enabled = tracing::enabled!(tracing::Level::DEBUG);
     let ipc_path = unique_ipc_path();
-    #[cfg(not(unix))]
-    let ipc_path: Option<String> = None;
     let ipc_path_for_session = ipc_path.clone();
-    debug_log(format!(
+    tracing::debug!(
         "spawning inline mpv rows={} cols={} term={}x{} pixels={}x{} url={} playback={} ipc={}",
         opts.rows,
         opts.cols,
@@ -264,12 +251,12 @@ pub fn spawn_inline_player(opts: InlineLaunchOptions<'_>) -> Result<InlineSessio
         remote_url,
         playback_target,
         ipc_path.as_deref().unwrap_or("n/a")
-    ));
+    );
     #[cfg(unix)]
     if let Some(path) = &ipc_path {
         if let Err(err) = fs::remove_file(path) {
-            if err.kind() != std::io::ErrorKind::NotFound && video_debug_enabled() {
-                debug_log(format!("failed to remove stale mpv ipc path {path}: {err}"));
+            if err.kind() != std::io::ErrorKind::NotFound {
+                tracing::debug!("failed to remove stale mpv ipc path {path}: {err}");
             }
         }
     }
@@ -314,9 +301,10 @@ pub fn spawn_inline_player(opts: InlineLaunchOptions<'_>) -> Result<InlineSessio
             }
 
             push_http_headers(&mut args);
+            push_proxy_args(&mut args, &playback_target)?;
 
             if debug_enabled {
-                debug_log(format!("mpv args: {:?}", args));
+                tracing::debug!("mpv args: {:?}", args);
             }
 
             let mut command = Command::new(&mpv_path);
@@ -352,13 +340,14 @@ pub fn spawn_inline_player(opts: InlineLaunchOptions<'_>) -> Result<InlineSessio
             let mut child = command
                 .spawn()
                 .with_context(|| format!("launch mpv to play {}", remote_url))?;
+            let _registration = PlayerRegistration::new(child.id());
             let mut stderr_handle = None;
             if debug_enabled {
                 if let Some(stderr) = child.stderr.take() {
                     stderr_handle = Some(thread::spawn(move || {
                         let reader = BufReader::new(stderr);
                         for line in reader.lines().map_while(Result::ok) {
-                            debug_log(format!("mpv stderr: {}", line));
+                            tracing::debug!("mpv stderr: {}", line);
                         }
                     }));
                 }
@@ -369,7 +358,7 @@ pub fn spawn_inline_player(opts: InlineLaunchOptions<'_>) -> Result<InlineSessio
                     let _ = child.kill();
                     let status = child.wait().context("wait for mpv after stop request")?;
                     if debug_enabled {
-                        debug_log(format!("mpv stopped with status {:?}", status.code()));
+                        tracing::debug!("mpv stopped with status {:?}", status.code());
                     }
                     if let Some(handle) = stderr_handle.take() {
                         let _ = handle.join();
@@ -380,7 +369,7 @@ pub fn spawn_inline_player(opts: InlineLaunchOptions<'_>) -> Result<InlineSessio
                 match child.try_wait() {
                     Ok(Some(status)) => {
                         if debug_enabled {
-                            debug_log(format!("mpv exited with status {:?}", status.code()));
+                            tracing::debug!("mpv exited with status {:?}", status.code());
                         }
                         if let Some(handle) = stderr_handle.take() {
                             let _ = handle.join();
@@ -390,7 +379,7 @@ pub fn spawn_inline_player(opts: InlineLaunchOptions<'_>) -> Result<InlineSessio
                     Ok(None) => thread::sleep(Duration::from_millis(30)),
                     Err(err) => {
                         if debug_enabled {
-                            debug_log(format!("mpv poll error: {}", err));
+                            tracing::debug!("mpv poll error: {}", err);
                         }
                         if let Some(handle) = stderr_handle.take() {
                             let _ = handle.join();
@@ -416,6 +405,58 @@ pub fn spawn_inline_player(opts: InlineLaunchOptions<'_>) -> Result<InlineSessio
     })
 }
 
+/// Keeps a player listed in [`RUNNING_PLAYERS`] until it has been reaped.
+struct PlayerRegistration(u32);
+
+impl PlayerRegistration {
+    fn new(pid: u32) -> Self {
+        running_players().push(pid);
+        Self(pid)
+    }
+}
+
+impl Drop for PlayerRegistration {
+    fn drop(&mut self) {
+        running_players().retain(|pid| *pid != self.0);
+    }
+}
+
+fn running_players() -> std::sync::MutexGuard<'static, Vec<u32>> {
+    RUNNING_PLAYERS
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+}
+
+/// Kills every inline player immediately, without waiting for the session
+/// threads. Meant for emergency cleanup when the UI is going away, such as
+/// from a panic hook; external players are left alone.
+pub fn kill_running_players() {
+    for pid in running_players().iter().copied() {
+        kill_process(pid);
+    }
+}
+
+#[cfg(unix)]
+fn kill_process(pid: u32) {
+    if let Ok(pid) = libc::pid_t::try_from(pid) {
+        unsafe {
+            libc::kill(pid, libc::SIGKILL);
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn kill_process(pid: u32) {
+    let _ = Command::new("taskkill")
+        .args(["/F", "/PID", &pid.to_string()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
+
+#[cfg(all(not(unix), not(target_os = "windows")))]
+fn kill_process(_pid: u32) {}
+
 pub fn spawn_external_player(opts: ExternalLaunchOptions<'_>) -> Result<()> {
     if opts.playback.trim().is_empty() {
         return Err(anyhow!("video playback path missing"));
@@ -434,6 +475,7 @@ pub fn spawn_external_player(opts: ExternalLaunchOptions<'_>) -> Result<()> {
     args.push("--ytdl=no".to_string());
 
     push_http_headers(&mut args);
+    push_proxy_args(&mut args, opts.playback)?;
 
     if !opts.source.label.is_empty() {
         args.push(format!("--force-media-title={}", opts.source.label));
@@ -452,6 +494,59 @@ pub fn spawn_external_player(opts: ExternalLaunchOptions<'_>) -> Result<()> {
     Ok(())
 }
 
+/// Reddit serves DASH video and audio as separate files next to each other.
+/// Returns the audio renditions to try for a `DASH_<res>.mp4` URL, best first,
+/// or nothing when the URL isn't a v.redd.it DASH stream.
+pub fn dash_audio_urls(video_url: &str) -> Vec<String> {
+    const AUDIO_NAMES: [&str; 4] = [
+        "DASH_AUDIO_128.mp4",
+        "DASH_AUDIO_64.mp4",
+        "DASH_audio.mp4",
+        "audio",
+    ];
+    let base = video_url.split(['?', '#']).next().unwrap_or_default();
+    if !base.contains("v.redd.it/") {
+        return Vec::new();
+    }
+    let Some((dir, file)) = base.rsplit_once('/') else {
+        return Vec::new();
+    };
+    if !file.starts_with("DASH_") || file.starts_with("DASH_AUDIO") {
+        return Vec::new();
+    }
+    AUDIO_NAMES
+        .iter()
+        .map(|name| format!("{dir}/{name}"))
+        .collect()
+}
+
+/// Copies the video stream of `video` and the audio stream of `audio` into
+/// `output` without re-encoding.
+pub fn mux_audio(ffmpeg_path: &str, video: &Path, audio: &Path, output: &Path) -> Result<()> {
+    let result = Command::new(ffmpeg_path)
+        .args(["-y", "-loglevel", "error", "-i"])
+        .arg(video)
+        .arg("-i")
+        .arg(audio)
+        .args(["-map", "0:v:0", "-map", "1:a:0", "-c", "copy"])
+        .arg(output)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .with_context(|| format!("launch {ffmpeg_path}"))?;
+    if !result.status.success() {
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        let reason = stderr.lines().rev().find(|line| !line.trim().is_empty());
+        return Err(anyhow!(
+            "ffmpeg exited with {}: {}",
+            result.status,
+            reason.unwrap_or("no output")
+        ));
+    }
+    Ok(())
+}
+
 #[derive(Clone, Copy)]
 pub enum VideoCommand {
     TogglePause,
@@ -486,6 +581,8 @@ fn send_ipc_command_inner(path: &str, serialized: &str) -> Result<()> {
 
     const PIPE_RETRIES: usize = 5;
     const PIPE_RETRY_DELAY: Duration = Duration::from_millis(100);
+    /// `ERROR_PIPE_BUSY`: mpv is still serving another client on this pipe.
+    const ERROR_PIPE_BUSY: i32 = 231;
 
     for attempt in 0..PIPE_RETRIES {
         match OpenOptions::new().read(true).write(true).open(path) {
@@ -497,7 +594,11 @@ fn send_ipc_command_inner(path: &str, serialized: &str) -> Result<()> {
                 pipe.flush().ok();
                 return Ok(());
             }
-            Err(err) if err.kind() == ErrorKind::NotFound && attempt + 1 < PIPE_RETRIES => {
+            Err(err)
+                if (err.kind() == ErrorKind::NotFound
+                    || err.raw_os_error() == Some(ERROR_PIPE_BUSY))
+                    && attempt + 1 < PIPE_RETRIES =>
+            {
                 thread::sleep(PIPE_RETRY_DELAY);
             }
             Err(err) => {
@@ -549,8 +650,8 @@ fn unique_ipc_path() -> Option<String> {
 #[cfg(unix)]
 fn cleanup_ipc_path(path: &str) {
     if let Err(err) = fs::remove_file(path) {
-        if err.kind() != std::io::ErrorKind::NotFound && video_debug_enabled() {
-            debug_log(format!("failed to remove mpv ipc path {path}: {err}"));
+        if err.kind() != std::io::ErrorKind::NotFound {
+            tracing::debug!("failed to remove mpv ipc path {path}: {err}");
         }
     }
 }
@@ -572,6 +673,16 @@ fn command_payload(command: VideoCommand) -> serde_json::Value {
 mod tests {
     use super::*;
 
+    #[test]
+    fn players_stay_registered_until_reaped() {
+        let pid = u32::MAX - 7;
+        {
+            let _registration = PlayerRegistration::new(pid);
+            assert!(running_players().contains(&pid));
+        }
+        assert!(!running_players().contains(&pid));
+    }
+
     #[test]
     fn prefers_fallback_url_when_available() {
         let video = RedditVideo {
@@ -597,6 +708,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn dash_audio_urls_sit_next_to_the_video() {
+        let urls = dash_audio_urls("https://v.redd.it/abc123/DASH_720.mp4?source=fallback");
+        assert_eq!(urls[0], "https://v.redd.it/abc123/DASH_AUDIO_128.mp4");
+        assert_eq!(urls.last().unwrap(), "https://v.redd.it/abc123/audio");
+        assert!(dash_audio_urls("https://stream.test/fallback.mp4").is_empty());
+        assert!(dash_audio_urls("https://v.redd.it/abc123/HLSPlaylist.m3u8").is_empty());
+    }
+
     #[test]
     fn prefers_hls_when_fallback_missing() {
         let video = RedditVideo {
@@ -637,4 +757,20 @@ mod tests {
         };
         assert!(VideoSource::from_reddit_video(&video, Cow::Borrowed("Sample title")).is_none());
     }
+
+    #[test]
+    fn remote_playback_follows_the_network_route() {
+        let url = "https://v.redd.it/abc/DASH_720.mp4";
+        assert!(proxy_args(url, None, None).unwrap().is_empty());
+        assert_eq!(
+            proxy_args(url, None, Some("http://proxy:3128")).unwrap(),
+            vec!["--http-proxy=http://proxy:3128"]
+        );
+        assert!(proxy_args(url, Some("SOCKS 127.0.0.1:9050"), None).is_err());
+        assert!(
+            proxy_args("/cache/video.mp4", Some("SOCKS 127.0.0.1:9050"), None)
+                .unwrap()
+                .is_empty()
+        );
+    }
 }
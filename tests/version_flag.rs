@@ -28,3 +28,27 @@ fn prints_help() {
     assert!(stdout.contains("HN-TUI"));
     assert!(stdout.contains("--version"));
 }
+
+#[test]
+fn rss_requires_feed_name() {
+    let exe = env!("CARGO_BIN_EXE_hn-tui");
+    let output = Command::new(exe)
+        .arg("--rss")
+        .output()
+        .expect("run hn-tui --rss");
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).expect("stderr utf8");
+    assert!(stderr.contains("--rss requires a feed name"));
+}
+
+#[test]
+fn output_needs_rss() {
+    let exe = env!("CARGO_BIN_EXE_hn-tui");
+    let output = Command::new(exe)
+        .args(["--output", "feed.xml"])
+        .output()
+        .expect("run hn-tui --output");
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).expect("stderr utf8");
+    assert!(stderr.contains("--output only applies to --rss"));
+}